@@ -0,0 +1,129 @@
+import { useEffect, useRef, useState } from 'react';
+import { Box, CircularProgress, Stack, Typography } from '@mui/material';
+import { Turnstile } from '@marsidev/react-turnstile';
+import { useTranslation } from 'react-i18next';
+import { fetchPowSolution, type CaptchaConfig } from '../utils/captcha';
+
+declare global {
+  interface Window {
+    hcaptcha?: {
+      render: (
+        container: HTMLElement,
+        options: { sitekey: string; callback: (token: string) => void; 'expired-callback': () => void },
+      ) => string;
+      remove: (id: string) => void;
+    };
+  }
+}
+
+let hcaptchaScript: Promise<void> | null = null;
+
+function loadHcaptcha(): Promise<void> {
+  if (!hcaptchaScript) {
+    hcaptchaScript = new Promise((resolve, reject) => {
+      const script = document.createElement('script');
+      script.src = 'https://js.hcaptcha.com/1/api.js?render=explicit';
+      script.async = true;
+      script.onload = () => resolve();
+      script.onerror = () => {
+        hcaptchaScript = null;
+        reject(new Error('failed to load hCaptcha'));
+      };
+      document.head.appendChild(script);
+    });
+  }
+  return hcaptchaScript;
+}
+
+function HCaptcha({ siteKey, onToken }: { siteKey: string; onToken: (token: string) => void }) {
+  const container = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    let widget: string | undefined;
+    let cancelled = false;
+    loadHcaptcha()
+      .then(() => {
+        if (cancelled || !container.current || !window.hcaptcha) return;
+        widget = window.hcaptcha.render(container.current, {
+          sitekey: siteKey,
+          callback: onToken,
+          'expired-callback': () => onToken(''),
+        });
+      })
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+      if (widget !== undefined) window.hcaptcha?.remove(widget);
+    };
+  }, [siteKey, onToken]);
+
+  return <div ref={container} data-testid="hcaptcha" />;
+}
+
+function PowSolver({ challengeUrl, onToken }: { challengeUrl: string; onToken: (token: string) => void }) {
+  const { t } = useTranslation();
+  const [state, setState] = useState<'solving' | 'solved' | 'failed'>('solving');
+
+  useEffect(() => {
+    let cancelled = false;
+    fetchPowSolution(challengeUrl)
+      .then((solution) => {
+        if (cancelled) return;
+        setState('solved');
+        onToken(solution);
+      })
+      .catch(() => {
+        if (!cancelled) setState('failed');
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [challengeUrl, onToken]);
+
+  if (state === 'solved') return null;
+  return (
+    <Stack direction="row" spacing={1} alignItems="center">
+      {state === 'solving' && <CircularProgress size={16} />}
+      <Typography variant="body2" color={state === 'failed' ? 'error' : 'text.secondary'}>
+        {t(state === 'failed' ? 'captcha.failed' : 'captcha.solving')}
+      </Typography>
+    </Stack>
+  );
+}
+
+interface CaptchaProps {
+  config: CaptchaConfig;
+  onToken: (token: string) => void;
+  // Where the proof-of-work provider fetches its challenge.
+  challengeUrl?: string;
+}
+
+// Renders the widget of the configured provider, or solves its proof-of-work challenge, and
+// reports the token to send as `captcha_token`. Renders nothing for the `none` provider.
+export function Captcha({ config, onToken, challengeUrl = '/api/pow/challenge' }: CaptchaProps) {
+  let widget = null;
+  switch (config.provider) {
+    case 'turnstile':
+      widget = (
+        <Turnstile
+          siteKey={config.site_key || import.meta.env.VITE_TURNSTILE_SITE_KEY || ''}
+          onSuccess={onToken}
+        />
+      );
+      break;
+    case 'hcaptcha':
+      widget = <HCaptcha siteKey={config.site_key || ''} onToken={onToken} />;
+      break;
+    case 'pow':
+      widget = <PowSolver challengeUrl={challengeUrl} onToken={onToken} />;
+      break;
+    case 'none':
+      return null;
+  }
+
+  return (
+    <Box display="flex" justifyContent="center">
+      {widget}
+    </Box>
+  );
+}
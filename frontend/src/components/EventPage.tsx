@@ -1,15 +1,16 @@
 import { useEffect, useMemo, useState } from 'react';
 import { useSearchParams, useNavigate } from 'react-router-dom';
-import { Box, Container, Grid, Paper, Stack, Typography, Button, Dialog, DialogTitle, DialogContent, DialogContentText, TextField, DialogActions, FormControlLabel, Switch } from '@mui/material';
+import { Box, Container, Grid, Paper, Stack, Typography, Button, Dialog, DialogTitle, DialogContent, DialogContentText, TextField, DialogActions, FormControlLabel, Switch, MenuItem } from '@mui/material';
 import { DatePicker } from '@mui/x-date-pickers';
 import { EventHeader } from './EventHeader';
 import { GuessesChart } from './GuessesChart';
 import { GuessForm } from './GuessForm';
 import { GuessList } from './GuessList';
-import type { ChartPoint, EventData, Guess } from './types';
+import type { ChartPoint, EventData, Guess, SealedGuess } from './types';
 import { useTranslation } from 'react-i18next';
 import dayjs, { Dayjs } from 'dayjs';
 import { EU_DATE_FORMAT, formatEuDate } from '../utils/date';
+import { clearViewerToken, loadViewerToken, saveViewerToken, viewerHeaders, withViewerToken } from '../utils/viewerToken';
 
 interface EventEndedAnnouncement {
   event_id: string;
@@ -23,11 +24,17 @@ interface EventEndedAnnouncement {
 export default function EventPage() {
   const [searchParams] = useSearchParams();
   const navigate = useNavigate();
-  const { t } = useTranslation();
+  const { t, i18n } = useTranslation();
   const eventKey = searchParams.get('key');
   
   const [event, setEvent] = useState<EventData | null>(null);
   const [guesses, setGuesses] = useState<Guess[]>([]);
+  const [guessesHidden, setGuessesHidden] = useState(false);
+  const [sealedGuesses, setSealedGuesses] = useState<SealedGuess[]>([]);
+  const [viewerToken, setViewerToken] = useState<string | null>(() => (eventKey ? loadViewerToken(eventKey) : null));
+  const [passcodeRequired, setPasscodeRequired] = useState(false);
+  const [passcodeDraft, setPasscodeDraft] = useState('');
+  const [passcodeError, setPasscodeError] = useState<string | null>(null);
   const [deleteDialogOpen, setDeleteDialogOpen] = useState(false);
   const [deleteKey, setDeleteKey] = useState('');
 
@@ -52,6 +59,12 @@ export default function EventPage() {
   const [answerError, setAnswerError] = useState<string | null>(null);
   const [endedAnnouncement, setEndedAnnouncement] = useState<EventEndedAnnouncement | null>(null);
 
+  const [privacyDialogOpen, setPrivacyDialogOpen] = useState(false);
+  const [privacyVisibility, setPrivacyVisibility] = useState<NonNullable<EventData['visibility']>>('public');
+  const [privacyPasscode, setPrivacyPasscode] = useState('');
+  const [privacySealed, setPrivacySealed] = useState(false);
+  const [privacyError, setPrivacyError] = useState<string | null>(null);
+
   useEffect(() => {
     if (!eventKey) {
       navigate('/');
@@ -60,12 +73,44 @@ export default function EventPage() {
 
     let sse: EventSource | null = null;
     let sseErrorHandled = false;
+    let reconnectTimer: ReturnType<typeof setTimeout> | null = null;
     let cancelled = false;
 
+    const refreshGuesses = async (eventId: string) => {
+      const guessRes = await fetch(`/api/events/${eventId}/guesses`, { headers: viewerHeaders(viewerToken) });
+      if (guessRes.status === 403) {
+        // Hidden-guesses events only show guesses once guessing closes.
+        if (!cancelled) {
+          setGuessesHidden(true);
+          setGuesses([]);
+        }
+        return;
+      }
+      if (guessRes.ok) {
+        const guessData: (Guess | SealedGuess)[] = await guessRes.json();
+        if (cancelled) return;
+        setGuessesHidden(false);
+        // Sealed events list who guessed without the dates and weights until guessing closes.
+        if (guessData.some((g) => !('guessed_date' in g))) {
+          setSealedGuesses(guessData);
+          setGuesses([]);
+        } else {
+          setSealedGuesses([]);
+          setGuesses(guessData as Guess[]);
+        }
+      }
+    };
+
     const fetchData = async () => {
       try {
         // 1. Get Event
-        const evtRes = await fetch(`/api/events/by-key/${eventKey}`);
+        const evtRes = await fetch(`/api/events/by-key/${eventKey}`, { headers: viewerHeaders(viewerToken) });
+        if (evtRes.status === 401) {
+          // Passcode-protected: ask for the passcode (again, if the host changed it).
+          clearViewerToken(eventKey);
+          if (!cancelled) setPasscodeRequired(true);
+          return;
+        }
         if (!evtRes.ok) {
           // If the event was deleted (or never existed), send the user back home.
           navigate('/');
@@ -76,104 +121,159 @@ export default function EventPage() {
         setEvent(evtData);
 
         // 2. Get Guesses
-        const guessRes = await fetch(`/api/events/${evtData.id}/guesses`);
-        if (guessRes.ok) {
-          const guessData = await guessRes.json();
-          if (!cancelled) {
-            setGuesses(guessData);
-          }
-        }
+        await refreshGuesses(evtData.id);
 
         if (cancelled) return;
 
         // 3. SSE
-        sse = new EventSource(`/api/events/live?event_key=${encodeURIComponent(eventKey)}`);
-        sse.onmessage = (msg) => {
-          const parsed = JSON.parse(msg.data);
-          if (parsed?.type === 'guess' && parsed?.data?.guess) {
-            const newGuess: Guess = parsed.data.guess;
-            setGuesses((prev) => {
-              const idx = prev.findIndex((g) => g.invitee_id === newGuess.invitee_id);
-              if (idx === -1) return [...prev, newGuess];
-              const next = [...prev];
-              next[idx] = newGuess;
-              return next;
-            });
-            return;
-          }
+        connectLive(evtData.id);
+      } catch {
+        if (!cancelled) navigate('/');
+      }
+    };
 
-          if (parsed?.type === 'guess_deleted' && parsed?.data?.invitee_id) {
-            const inviteeId: string = parsed.data.invitee_id;
-            setGuesses((prev) => prev.filter((g) => g.invitee_id !== inviteeId));
-            return;
-          }
+    const connectLive = (eventId: string) => {
+      sseErrorHandled = false;
+      sse = new EventSource(withViewerToken(`/api/events/live?event_key=${encodeURIComponent(eventKey)}`, viewerToken));
+      sse.onmessage = (msg) => {
+        const parsed = JSON.parse(msg.data);
+        if (parsed?.type === 'server_restarting') {
+          // The server is being redeployed: reconnect once it is back and catch up on
+          // anything missed in between.
+          sse?.close();
+          const delay = Number(parsed?.data?.reconnect_after_ms) || 5000;
+          reconnectTimer = setTimeout(() => {
+            if (cancelled) return;
+            refreshGuesses(eventId).catch(() => {});
+            connectLive(eventId);
+          }, delay);
+          return;
+        }
 
-          if (parsed?.type === 'event_settings' && parsed?.data) {
-            setEvent((prev) => {
-              if (!prev) return prev;
-              return {
-                ...prev,
-                allow_guess_edits: Boolean(parsed.data.allow_guess_edits),
-              };
-            });
-          }
+        if (parsed?.type === 'guess' && parsed?.data?.guess) {
+          const newGuess: Guess = parsed.data.guess;
+          setGuesses((prev) => {
+            const idx = prev.findIndex((g) => g.invitee_id === newGuess.invitee_id);
+            if (idx === -1) return [...prev, newGuess];
+            const next = [...prev];
+            next[idx] = newGuess;
+            return next;
+          });
+          return;
+        }
 
-          if (parsed?.type === 'event_description' && parsed?.data) {
-            setEvent((prev) => {
-              if (!prev) return prev;
-              return {
-                ...prev,
-                description: parsed.data.description ?? null,
-              };
-            });
-          }
+        if (parsed?.type === 'guess_sealed' || parsed?.type === 'guess_sealed_edited') {
+          refreshGuesses(eventId).catch(() => {});
+          return;
+        }
 
-          if (parsed?.type === 'event_ended' && parsed?.data) {
-            setEndedAnnouncement(parsed.data as EventEndedAnnouncement);
-            setEvent((prev) => {
-              if (!prev) return prev;
-              return {
-                ...prev,
-                birth_date: parsed.data.birth_date,
-                birth_weight_kg: parsed.data.birth_weight_kg,
-                ended_at: parsed.data.ended_at,
-                allow_guess_edits: false,
-              };
-            });
-          }
-        };
+        if (parsed?.type === 'guesses_revealed' && parsed?.data?.guesses) {
+          setGuessesHidden(false);
+          setSealedGuesses([]);
+          setGuesses(parsed.data.guesses as Guess[]);
+          return;
+        }
 
-        sse.onerror = async () => {
-          if (cancelled) return;
-          if (sseErrorHandled) return;
-          sseErrorHandled = true;
-
-          // If the live stream fails, only redirect the user if the event is actually gone.
-          // In practice, SSE can fail for transient reasons (or be mocked/closed in tests).
-          try {
-            sse?.close();
-            const res = await fetch(`/api/events/by-key/${eventKey}`);
-            if (!res.ok && !cancelled) {
-              navigate('/');
-            }
-          } catch {
-            // Ignore transient network issues.
+        if (parsed?.type === 'guess_deleted' && parsed?.data?.invitee_id) {
+          const inviteeId: string = parsed.data.invitee_id;
+          setGuesses((prev) => prev.filter((g) => g.invitee_id !== inviteeId));
+          setSealedGuesses((prev) => prev.filter((g) => g.invitee_id !== inviteeId));
+          return;
+        }
+
+        if (parsed?.type === 'event_settings' && parsed?.data) {
+          setEvent((prev) => {
+            if (!prev) return prev;
+            return {
+              ...prev,
+              allow_guess_edits: Boolean(parsed.data.allow_guess_edits),
+              sealed_guesses: Boolean(parsed.data.sealed_guesses),
+            };
+          });
+          // Sealing or unsealing changes what the guess list may show.
+          refreshGuesses(eventId).catch(() => {});
+        }
+
+        if (parsed?.type === 'event_description' && parsed?.data) {
+          setEvent((prev) => {
+            if (!prev) return prev;
+            return {
+              ...prev,
+              description: parsed.data.description ?? null,
+            };
+          });
+        }
+
+        if (parsed?.type === 'event_ended' && parsed?.data) {
+          setEndedAnnouncement(parsed.data as EventEndedAnnouncement);
+          refreshGuesses(eventId).catch(() => {});
+          setEvent((prev) => {
+            if (!prev) return prev;
+            return {
+              ...prev,
+              birth_date: parsed.data.birth_date,
+              birth_weight_kg: parsed.data.birth_weight_kg,
+              ended_at: parsed.data.ended_at,
+              allow_guess_edits: false,
+            };
+          });
+        }
+      };
+
+      sse.onerror = async () => {
+        if (cancelled) return;
+        if (sseErrorHandled) return;
+        sseErrorHandled = true;
+
+        // If the live stream fails, only redirect the user if the event is actually gone.
+        // In practice, SSE can fail for transient reasons (or be mocked/closed in tests).
+        try {
+          sse?.close();
+          const res = await fetch(`/api/events/by-key/${eventKey}`, { headers: viewerHeaders(viewerToken) });
+          if (!res.ok && !cancelled) {
+            navigate('/');
           }
-        };
-      } catch {
-        if (!cancelled) navigate('/');
-      }
+        } catch {
+          // Ignore transient network issues.
+        }
+      };
     };
 
     fetchData();
 
     return () => {
       cancelled = true;
+      if (reconnectTimer) {
+        clearTimeout(reconnectTimer);
+      }
       if (sse) {
         sse.close();
       }
     };
-  }, [eventKey, navigate, t]);
+  }, [eventKey, navigate, t, viewerToken]);
+
+  const handleUnlock = async () => {
+    if (!eventKey) return;
+    setPasscodeError(null);
+    try {
+      const res = await fetch(`/api/events/by-key/${eventKey}/unlock`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ passcode: passcodeDraft }),
+      });
+      if (!res.ok) {
+        setPasscodeError(t('passcode.wrong'));
+        return;
+      }
+      const { token } = await res.json();
+      saveViewerToken(eventKey, token);
+      setPasscodeRequired(false);
+      setPasscodeDraft('');
+      setViewerToken(token);
+    } catch {
+      setPasscodeError(t('passcode.wrong'));
+    }
+  };
 
   const myInviteeId = event?.id ? localStorage.getItem(`guess_token_${event.id}`) : null;
   const myAdminKey = event?.id ? localStorage.getItem(`event_admin_key_${event.id}`) : null;
@@ -403,6 +503,41 @@ export default function EventPage() {
     }
   };
 
+  const handlePrivacyOpen = () => {
+    if (!event) return;
+    setPrivacyError(null);
+    setPrivacyVisibility(event.visibility ?? 'public');
+    setPrivacyPasscode(event.passcode ?? '');
+    setPrivacySealed(Boolean(event.sealed_guesses));
+    setPrivacyDialogOpen(true);
+  };
+
+  const handlePrivacySave = async () => {
+    if (!event) return;
+    if (!myAdminKey) return;
+    try {
+      const res = await fetch(`/api/events/${event.id}/settings`, {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json', Authorization: `Bearer ${myAdminKey}` },
+        body: JSON.stringify({
+          allow_guess_edits: allowGuessEdits,
+          visibility: privacyVisibility,
+          passcode: privacyPasscode,
+          sealed_guesses: privacySealed,
+        }),
+      });
+      if (!res.ok) {
+        setPrivacyError(t('admin.privacy_failed'));
+        return;
+      }
+      const updated: EventData = await res.json();
+      setEvent(updated);
+      setPrivacyDialogOpen(false);
+    } catch {
+      setPrivacyError(t('admin.privacy_failed'));
+    }
+  };
+
   const handleDeleteConfirm = async () => {
       if (!event) return;
       try {
@@ -422,6 +557,27 @@ export default function EventPage() {
       }
   };
 
+  const handleExport = async () => {
+    if (!event || !myAdminKey) return;
+    try {
+      const res = await fetch(`/api/events/${event.id}/export`, {
+        headers: { Authorization: `Bearer ${myAdminKey}` },
+      });
+      if (!res.ok) {
+        alert(t('admin.export_failed'));
+        return;
+      }
+      const url = URL.createObjectURL(await res.blob());
+      const link = document.createElement('a');
+      link.href = url;
+      link.download = `event-${event.id}.json`;
+      link.click();
+      URL.revokeObjectURL(url);
+    } catch {
+      alert(t('admin.export_failed'));
+    }
+  };
+
   const handleAdminDeleteGuess = async (g: Guess) => {
     if (!event) return;
     if (!myAdminKey) return;
@@ -504,6 +660,40 @@ export default function EventPage() {
     }
   };
 
+  if (passcodeRequired) {
+    return (
+      <Container maxWidth="sm">
+        <Paper className="paper-reveal paper-reveal-1" sx={{ p: 3, mt: 6, borderRadius: 4 }}>
+          <Typography variant="h6" gutterBottom>
+            {t('passcode.title')}
+          </Typography>
+          <Typography variant="body2" color="text.secondary" mb={2}>
+            {t('passcode.desc')}
+          </Typography>
+          <TextField
+            autoFocus
+            fullWidth
+            type="password"
+            label={t('passcode.field')}
+            value={passcodeDraft}
+            onChange={(e) => setPasscodeDraft(e.target.value)}
+            onKeyDown={(e) => {
+              if (e.key === 'Enter') handleUnlock();
+            }}
+          />
+          {passcodeError && (
+            <Typography variant="body2" color="error" mt={1}>
+              {passcodeError}
+            </Typography>
+          )}
+          <Button variant="contained" sx={{ mt: 2 }} onClick={handleUnlock} disabled={!passcodeDraft}>
+            {t('passcode.unlock')}
+          </Button>
+        </Paper>
+      </Container>
+    );
+  }
+
   if (!event) return <Typography className="paper-reveal paper-reveal-1" p={4}>{t('event_page.loading')}</Typography>;
 
   const computedClosestDateTop = (() => {
@@ -571,7 +761,22 @@ export default function EventPage() {
                   </Typography>
                 </Paper>
               ) : (
-                <GuessForm event={event} />
+                <GuessForm event={event} viewerToken={viewerToken} />
+              )}
+              {guessesHidden && (
+                <Typography variant="body2" color="text.secondary" textAlign="center">
+                  {t('event_page.guesses_hidden')}
+                </Typography>
+              )}
+              {sealedGuesses.length > 0 && (
+                <Paper sx={{ p: 2, borderRadius: 4 }}>
+                  <Typography variant="subtitle1" gutterBottom>
+                    {t('event_page.guesses_sealed', { count: sealedGuesses.length })}
+                  </Typography>
+                  <Typography variant="body2" color="text.secondary">
+                    {sealedGuesses.map((g) => g.display_name).join(', ')}
+                  </Typography>
+                </Paper>
               )}
               <GuessList
                 guesses={uniqueGuesses}
@@ -581,6 +786,30 @@ export default function EventPage() {
                 isAdmin={isClaimedAdmin && !hasEnded}
                 onDeleteGuess={handleAdminDeleteGuess}
               />
+              <Stack direction="row" spacing={1} justifyContent="center" flexWrap="wrap">
+                {uniqueGuesses.length > 0 && (
+                  <>
+                    <Button size="small" href={withViewerToken(`/api/events/${event.id}/guesses.csv`, viewerToken)}>
+                      {t('guess_list.download_csv')}
+                    </Button>
+                    <Button size="small" href={withViewerToken(`/api/events/${event.id}/guesses.xlsx`, viewerToken)}>
+                      {t('guess_list.download_xlsx')}
+                    </Button>
+                  </>
+                )}
+                <Button size="small" href={withViewerToken(`/api/events/${event.id}/qr.png?size=1024`, viewerToken)}>
+                  {t('guess_list.download_qr')}
+                </Button>
+                {!guessesHidden && sealedGuesses.length === 0 && (
+                  <Button
+                    size="small"
+                    href={withViewerToken(`/api/events/${event.id}/guesses.pdf?lang=${i18n.resolvedLanguage ?? 'en'}`, viewerToken)}
+                    target="_blank"
+                  >
+                    {t('guess_list.print_sheet')}
+                  </Button>
+                )}
+              </Stack>
               {hasEnded && (
                 <Paper sx={{ p: 2, borderRadius: 4 }}>
                   <Typography variant="h6" gutterBottom>
@@ -619,6 +848,15 @@ export default function EventPage() {
                   ) : (
                     <Typography variant="body2">-</Typography>
                   )}
+
+                  <Button
+                    size="small"
+                    sx={{ mt: 2 }}
+                    href={withViewerToken(`/api/events/${event.id}/certificate.pdf?lang=${i18n.resolvedLanguage ?? 'en'}`, viewerToken)}
+                    target="_blank"
+                  >
+                    {t('event_page.print_certificate')}
+                  </Button>
                 </Paper>
               )}
             </Stack>
@@ -649,6 +887,12 @@ export default function EventPage() {
                     {t('admin.set_answer')}
                   </Button>
                 )}
+                <Button variant="outlined" onClick={handlePrivacyOpen}>
+                  {t('admin.privacy_settings')}
+                </Button>
+                <Button variant="outlined" onClick={handleExport}>
+                  {t('admin.export_event')}
+                </Button>
                 <Button color="error" variant="outlined" onClick={handleDeleteClick}>
                   {t('admin.delete_event')}
                 </Button>
@@ -687,6 +931,50 @@ export default function EventPage() {
         </DialogActions>
       </Dialog>
 
+      <Dialog open={privacyDialogOpen} onClose={() => setPrivacyDialogOpen(false)}>
+        <DialogTitle>{t('admin.privacy_title')}</DialogTitle>
+        <DialogContent>
+          <TextField
+            select
+            margin="dense"
+            label={t('admin.field_visibility')}
+            fullWidth
+            value={privacyVisibility}
+            onChange={(e) => setPrivacyVisibility(e.target.value as NonNullable<EventData['visibility']>)}
+          >
+            <MenuItem value="public">{t('admin.visibility_public')}</MenuItem>
+            <MenuItem value="passcode">{t('admin.visibility_passcode')}</MenuItem>
+            <MenuItem value="hidden_guesses">{t('admin.visibility_hidden_guesses')}</MenuItem>
+          </TextField>
+          {privacyVisibility === 'passcode' && (
+            <TextField
+              margin="dense"
+              label={t('admin.field_passcode')}
+              fullWidth
+              value={privacyPasscode}
+              onChange={(e) => setPrivacyPasscode(e.target.value)}
+            />
+          )}
+          <FormControlLabel
+            control={<Switch checked={privacySealed} onChange={(e) => setPrivacySealed(e.target.checked)} />}
+            label={t('admin.field_sealed_guesses')}
+          />
+          {privacyError && (
+            <Typography variant="body2" color="error" mt={1}>
+              {privacyError}
+            </Typography>
+          )}
+        </DialogContent>
+        <DialogActions>
+          <Button onClick={() => setPrivacyDialogOpen(false)} color="inherit">
+            {t('admin.cancel')}
+          </Button>
+          <Button onClick={handlePrivacySave} variant="contained">
+            {t('guess_edit.save')}
+          </Button>
+        </DialogActions>
+      </Dialog>
+
       <Dialog open={descriptionDialogOpen} onClose={() => setDescriptionDialogOpen(false)}>
         <DialogTitle>{t('admin.edit_description_title')}</DialogTitle>
         <DialogContent>
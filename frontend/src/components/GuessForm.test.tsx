@@ -73,4 +73,84 @@ describe('GuessForm', () => {
 
     expect(await screen.findByText('You have already guessed! 🎉')).toBeInTheDocument();
   });
+
+  it('solves the event proof-of-work before submitting', async () => {
+    const user = userEvent.setup();
+    let sentSolution: string | undefined;
+
+    const event: EventData = {
+      id: 'event-1',
+      title: 'Event',
+      event_key: 'key',
+      due_date: '2030-01-01T12:00:00',
+      min_weight_kg: 2.0,
+      max_weight_kg: 4.0,
+      guess_pow_difficulty: 2,
+    };
+
+    server.use(
+      http.get('/api/events/event-1/guesses/challenge', () =>
+        HttpResponse.json({ challenge: 'v1.1.2.event-1.abc', difficulty: 2, expires_at: 1 }),
+      ),
+      http.post('/api/events/event-1/guesses', async ({ request }) => {
+        sentSolution = ((await request.json()) as { pow_solution?: string }).pow_solution;
+        return HttpResponse.json([
+          { id: 'invitee-1', event_id: 'event-1', display_name: 'Alice', color_hex: '#ff00aa' },
+          { id: 'guess-1', invitee_id: 'invitee-1', guessed_date: '2030-01-02T12:00:00', guessed_weight_kg: 3.2 },
+        ]);
+      }),
+    );
+
+    renderWithProviders(<GuessForm event={event} />);
+
+    await user.type(screen.getByLabelText('Your Name'), 'Alice');
+    await user.type(screen.getByLabelText('Birthdate guess'), '2030-01-02');
+    await user.type(screen.getByLabelText('Weight (kg)'), '3.2');
+    await user.click(screen.getByRole('button', { name: 'Submit Guess' }));
+
+    await waitFor(() => {
+      expect(sentSolution).toMatch(/^v1\.1\.2\.event-1\.abc:\d+$/);
+    });
+  });
+
+  it('sends a captcha token when the server requires one for guesses', async () => {
+    const user = userEvent.setup();
+    let sentToken: string | undefined;
+
+    const event: EventData = {
+      id: 'event-1',
+      title: 'Event',
+      event_key: 'key',
+      due_date: '2030-01-01T12:00:00',
+      min_weight_kg: 2.0,
+      max_weight_kg: 4.0,
+    };
+
+    server.use(
+      http.get('/api/captcha/config', () => HttpResponse.json({ provider: 'turnstile', on_guess: true })),
+      http.post('/api/events/event-1/guesses', async ({ request }) => {
+        sentToken = ((await request.json()) as { captcha_token?: string }).captcha_token;
+        return HttpResponse.json([
+          { id: 'invitee-1', event_id: 'event-1', display_name: 'Alice', color_hex: '#ff00aa' },
+          { id: 'guess-1', invitee_id: 'invitee-1', guessed_date: '2030-01-02T12:00:00', guessed_weight_kg: 3.2 },
+        ]);
+      }),
+    );
+
+    renderWithProviders(<GuessForm event={event} />);
+
+    expect(await screen.findByTestId('turnstile')).toBeInTheDocument();
+    await user.type(screen.getByLabelText('Your Name'), 'Alice');
+    await user.type(screen.getByLabelText('Birthdate guess'), '2030-01-02');
+    await user.type(screen.getByLabelText('Weight (kg)'), '3.2');
+
+    await waitFor(() => {
+      expect(screen.getByRole('button', { name: 'Submit Guess' })).toBeEnabled();
+    });
+    await user.click(screen.getByRole('button', { name: 'Submit Guess' }));
+
+    await waitFor(() => {
+      expect(sentToken).toBe('test-turnstile-token');
+    });
+  });
 });
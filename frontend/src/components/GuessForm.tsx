@@ -5,12 +5,16 @@ import dayjs, { Dayjs } from 'dayjs';
 import type { EventData } from './types';
 import { useTranslation } from 'react-i18next';
 import { EU_DATE_FORMAT } from '../utils/date';
+import { viewerHeaders } from '../utils/viewerToken';
+import { fetchPowSolution, useCaptchaConfig } from '../utils/captcha';
+import { Captcha } from './Captcha';
 
 interface GuessFormProps {
   event: EventData;
+  viewerToken?: string | null;
 }
 
-export function GuessForm({ event }: GuessFormProps) {
+export function GuessForm({ event, viewerToken }: GuessFormProps) {
   const { t } = useTranslation();
   const [name, setName] = useState('');
   const [weight, setWeight] = useState('');
@@ -30,6 +34,11 @@ export function GuessForm({ event }: GuessFormProps) {
       .join('')}`;
   });
   const [submitted, setSubmitted] = useState(false);
+  const captcha = useCaptchaConfig();
+  const captchaOnGuess = Boolean(captcha?.on_guess) && captcha?.provider !== 'none';
+  const [captchaToken, setCaptchaToken] = useState('');
+  const [captchaAttempt, setCaptchaAttempt] = useState(0);
+  const [solvingPow, setSolvingPow] = useState(false);
 
   const alreadyGuessed =
     submitted || (event?.id ? Boolean(localStorage.getItem(`guess_token_${event.id}`)) : false);
@@ -66,14 +75,28 @@ export function GuessForm({ event }: GuessFormProps) {
     try {
       const formattedDate = guessDate.format('YYYY-MM-DD') + 'T12:00:00';
 
+      let powSolution: string | undefined;
+      if ((event.guess_pow_difficulty ?? 0) > 0) {
+        setSolvingPow(true);
+        try {
+          powSolution = await fetchPowSolution(`/api/events/${event.id}/guesses/challenge`, {
+            headers: viewerHeaders(viewerToken),
+          });
+        } finally {
+          setSolvingPow(false);
+        }
+      }
+
       const res = await fetch(`/api/events/${event.id}/guesses`, {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', ...viewerHeaders(viewerToken) },
         body: JSON.stringify({
           display_name: name,
           guessed_date: formattedDate,
           guessed_weight_kg: w,
           color_hex: color,
+          ...(captchaOnGuess ? { captcha_token: captchaToken } : {}),
+          ...(powSolution ? { pow_solution: powSolution } : {}),
         }),
       });
 
@@ -102,10 +125,16 @@ export function GuessForm({ event }: GuessFormProps) {
       }
     } catch {
       setError(t('guess_form.alert_submit_fail'));
+    } finally {
+      if (captchaOnGuess) {
+        setCaptchaToken('');
+        setCaptchaAttempt((n) => n + 1);
+      }
     }
   };
 
-  const isDisabled = !name || !weight || !guessDate;
+  const isDisabled =
+    !name || !weight || !guessDate || (captchaOnGuess && !captchaToken) || solvingPow;
 
   if (alreadyGuessed) {
     return (
@@ -192,13 +221,16 @@ export function GuessForm({ event }: GuessFormProps) {
               style={{ width: '100%', height: 40, cursor: 'pointer', border: 'none' }}
             />
           </Box>
+          {captcha && captchaOnGuess && (
+            <Captcha key={captchaAttempt} config={captcha} onToken={setCaptchaToken} />
+          )}
           {error && (
             <Typography variant="body2" color="error">
               {error}
             </Typography>
           )}
           <Button variant="contained" onClick={handleSubmit} disabled={isDisabled}>
-            {t('guess_form.button_submit')}
+            {t(solvingPow ? 'captcha.solving' : 'guess_form.button_submit')}
           </Button>
         </Stack>
       </CardContent>
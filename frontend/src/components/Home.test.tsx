@@ -30,7 +30,7 @@ describe('Home', () => {
       { route: '/' },
     );
 
-    await user.type(screen.getByPlaceholderText('XXXXXX-XXXXXX-XXXXXX'), 'abc123');
+    await user.type(screen.getByPlaceholderText('XXXX-XXXX-XXXX'), 'abc123');
     await user.click(screen.getByRole('button', { name: 'Join' }));
 
     expect(await screen.findByText('EVENT')).toBeInTheDocument();
@@ -44,7 +44,7 @@ describe('Home', () => {
       description: string | null;
       due_date: string | null;
       guess_close_date: string | null;
-      turnstile_token: string;
+      captcha_token: string;
       min_weight_kg: number | null;
       max_weight_kg: number | null;
       allow_guess_edits: boolean;
@@ -96,4 +96,35 @@ describe('Home', () => {
 
     expect(await screen.findByText('EVENT')).toBeInTheDocument();
   });
+
+  it('solves a proof-of-work challenge when the server uses the pow provider', async () => {
+    const user = userEvent.setup();
+    let sentToken: string | undefined;
+
+    server.use(
+      http.get('/api/captcha/config', () => HttpResponse.json({ provider: 'pow', on_guess: false })),
+      http.get('/api/pow/challenge', () =>
+        HttpResponse.json({ challenge: 'v1.1.1.abc', difficulty: 1, expires_at: 1 }),
+      ),
+      http.post('/api/events', async ({ request }) => {
+        sentToken = ((await request.json()) as { captcha_token: string }).captcha_token;
+        return HttpResponse.json({ id: 'event-2', event_key: 'key-2', secret_key: 'secret-2' });
+      }),
+    );
+
+    renderWithProviders(<Home />, { route: '/' });
+
+    expect(screen.queryByTestId('turnstile')).not.toBeInTheDocument();
+    await user.type(screen.getByLabelText('Event Title'), 'Baby Pow');
+    await user.type(screen.getByLabelText('Expected Due Date'), '2030-01-01');
+
+    const createBtn = screen.getByRole('button', { name: 'Create Event' });
+    await waitFor(() => {
+      expect(createBtn).toBeEnabled();
+    });
+    await user.click(createBtn);
+
+    expect(await screen.findByText('Event Created! 🔑')).toBeInTheDocument();
+    expect(sentToken).toMatch(/^v1\.1\.1\.abc:\d+$/);
+  });
 });
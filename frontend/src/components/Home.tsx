@@ -3,14 +3,15 @@ import { useNavigate } from 'react-router-dom';
 import { Box, Button, Paper, TextField, Typography, Stack, Container, Collapse, Dialog, DialogTitle, DialogContent, DialogContentText, DialogActions, IconButton, FormControlLabel, Checkbox } from '@mui/material';
 import { DatePicker } from '@mui/x-date-pickers';
 import dayjs, { Dayjs } from 'dayjs';
-import { Turnstile } from '@marsidev/react-turnstile';
 import { useTranslation } from 'react-i18next';
 import { ContentCopy } from '@mui/icons-material';
 import { EU_DATE_FORMAT } from '../utils/date';
+import { useCaptchaConfig } from '../utils/captcha';
+import { Captcha } from './Captcha';
 
 export default function Home() {
   const navigate = useNavigate();
-  const { t } = useTranslation();
+  const { t, i18n } = useTranslation();
   const [title, setTitle] = useState('');
   const [description, setDescription] = useState('');
   const [dueDate, setDueDate] = useState<Dayjs | null>(null);
@@ -19,8 +20,13 @@ export default function Home() {
   const [maxWeightKg, setMaxWeightKg] = useState('5.2');
   const [showAdvanced, setShowAdvanced] = useState(false);
   const [allowGuessEdits, setAllowGuessEdits] = useState(false);
+  const [sealedGuesses, setSealedGuesses] = useState(false);
   const [joinKey, setJoinKey] = useState('');
-  const [turnstileToken, setTurnstileToken] = useState('');
+  const captcha = useCaptchaConfig();
+  const [captchaToken, setCaptchaToken] = useState('');
+  // Tokens are single-use, so every attempt remounts the widget for a fresh one.
+  const [captchaAttempt, setCaptchaAttempt] = useState(0);
+  const captchaReady = captcha !== null && (captcha.provider === 'none' || Boolean(captchaToken));
   const [createdEvent, setCreatedEvent] = useState<{key: string, secret: string} | null>(null);
 
   const handleCreate = async () => {
@@ -38,10 +44,12 @@ export default function Home() {
           description: description || null,
           due_date: formattedDate,
           guess_close_date: formattedCloseDate,
-          turnstile_token: turnstileToken,
+          captcha_token: captchaToken,
           min_weight_kg: minWeightKg ? parseFloat(minWeightKg) : null,
           max_weight_kg: maxWeightKg ? parseFloat(maxWeightKg) : null,
           allow_guess_edits: allowGuessEdits,
+          sealed_guesses: sealedGuesses,
+          language: i18n.resolvedLanguage,
         })
       });
       
@@ -56,6 +64,9 @@ export default function Home() {
       }
     } catch {
       alert(t('home.alert_create_error'));
+    } finally {
+      setCaptchaToken('');
+      setCaptchaAttempt((n) => n + 1);
     }
   };
 
@@ -66,7 +77,7 @@ export default function Home() {
   };
 
   const handleJoin = () => {
-    if (joinKey) navigate(`/event?key=${joinKey}`);
+    if (joinKey.trim()) navigate(`/event?key=${encodeURIComponent(joinKey.trim())}`);
   };
 
   return (
@@ -159,21 +170,25 @@ export default function Home() {
                   }
                   label={t('home.field_allow_guess_edits')}
                 />
+                <FormControlLabel
+                  control={
+                    <Checkbox
+                      checked={sealedGuesses}
+                      onChange={(e) => setSealedGuesses(e.target.checked)}
+                    />
+                  }
+                  label={t('home.field_sealed_guesses')}
+                />
             </Collapse>
           </Box>
 
-          <Box display="flex" justifyContent="center">
-            <Turnstile
-              siteKey={import.meta.env.VITE_TURNSTILE_SITE_KEY || ""}
-              onSuccess={(token) => setTurnstileToken(token)}
-            />
-          </Box>
+          {captcha && <Captcha key={captchaAttempt} config={captcha} onToken={setCaptchaToken} />}
           
           <Button 
             variant="contained" 
             size="large" 
             onClick={handleCreate}
-            disabled={!title || !turnstileToken || !dueDate}
+            disabled={!title || !captchaReady || !dueDate}
             sx={{ py: 1.5 }}
           >
             {t('home.button_create')}
@@ -188,7 +203,7 @@ export default function Home() {
             <TextField 
               size="small" 
               fullWidth 
-              placeholder="XXXXXX-XXXXXX-XXXXXX"
+              placeholder="XXXX-XXXX-XXXX"
               value={joinKey}
               onChange={(e) => setJoinKey(e.target.value)}
             />
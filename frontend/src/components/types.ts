@@ -11,6 +11,11 @@ export interface EventData {
   birth_date?: string;
   birth_weight_kg?: number;
   ended_at?: string;
+  visibility?: 'public' | 'passcode' | 'hidden_guesses';
+  passcode?: string | null;
+  sealed_guesses?: boolean;
+  /** Leading zero bits of the proof-of-work every guess must carry; 0 when none is required. */
+  guess_pow_difficulty?: number;
 }
 
 export interface Guess {
@@ -21,6 +26,13 @@ export interface Guess {
   guessed_weight_kg: number;
 }
 
+/** A guess of a sealed event before guessing closes: who guessed, but not what. */
+export interface SealedGuess {
+  invitee_id: string;
+  display_name: string;
+  color_hex: string;
+}
+
 export interface GuessSubmission {
   display_name: string;
   color_hex: string;
@@ -4,4 +4,7 @@ export const handlers = [
   http.get('/api/health', () => {
     return HttpResponse.text('ok');
   }),
+  http.get('/api/captcha/config', () => {
+    return HttpResponse.json({ provider: 'turnstile', site_key: null, on_guess: false });
+  }),
 ];
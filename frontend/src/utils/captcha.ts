@@ -0,0 +1,72 @@
+import { useEffect, useState } from 'react';
+
+// Bot protection the server expects, from GET /api/captcha/config.
+export type CaptchaProvider = 'turnstile' | 'hcaptcha' | 'pow' | 'none';
+
+export interface CaptchaConfig {
+  provider: CaptchaProvider;
+  site_key?: string | null;
+  on_guess: boolean;
+}
+
+// Used until the config has loaded, and when it can't be loaded.
+const FALLBACK: CaptchaConfig = { provider: 'turnstile', on_guess: false };
+
+export function useCaptchaConfig(): CaptchaConfig | null {
+  const [config, setConfig] = useState<CaptchaConfig | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    fetch('/api/captcha/config')
+      .then((res) => (res.ok ? res.json() : FALLBACK))
+      .catch(() => FALLBACK)
+      .then((data: CaptchaConfig) => {
+        if (!cancelled) setConfig(data);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, []);
+
+  return config;
+}
+
+interface Challenge {
+  challenge: string;
+  difficulty: number;
+}
+
+function leadingZeroBits(hash: Uint8Array): number {
+  let bits = 0;
+  for (const byte of hash) {
+    if (byte === 0) {
+      bits += 8;
+      continue;
+    }
+    return bits + Math.clz32(byte) - 24;
+  }
+  return bits;
+}
+
+// Finds `<challenge>:<counter>` whose SHA-256 starts with `difficulty` zero bits, hashing a
+// batch of counters at a time so the browser can run the digests in parallel.
+export async function solveChallenge(challenge: string, difficulty: number): Promise<string> {
+  const encoder = new TextEncoder();
+  const batch = 512;
+  for (let start = 0; ; start += batch) {
+    const candidates = Array.from({ length: batch }, (_, i) => `${challenge}:${start + i}`);
+    const hashes = await Promise.all(
+      candidates.map((candidate) => crypto.subtle.digest('SHA-256', encoder.encode(candidate))),
+    );
+    const found = hashes.findIndex((hash) => leadingZeroBits(new Uint8Array(hash)) >= difficulty);
+    if (found >= 0) return candidates[found];
+  }
+}
+
+// Fetches a proof-of-work challenge from `url` and solves it.
+export async function fetchPowSolution(url: string, init?: RequestInit): Promise<string> {
+  const res = await fetch(url, init);
+  if (!res.ok) throw new Error(`challenge request failed: ${res.status}`);
+  const { challenge, difficulty } = (await res.json()) as Challenge;
+  return solveChallenge(challenge, difficulty);
+}
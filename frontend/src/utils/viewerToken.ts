@@ -0,0 +1,25 @@
+// Viewer tokens of passcode-protected events, kept per event key so guests enter the
+// passcode once per browser.
+const storageKey = (eventKey: string) => `viewer_token_${eventKey}`;
+
+export function loadViewerToken(eventKey: string): string | null {
+  return localStorage.getItem(storageKey(eventKey));
+}
+
+export function saveViewerToken(eventKey: string, token: string) {
+  localStorage.setItem(storageKey(eventKey), token);
+}
+
+export function clearViewerToken(eventKey: string) {
+  localStorage.removeItem(storageKey(eventKey));
+}
+
+export function viewerHeaders(token: string | null | undefined): Record<string, string> {
+  return token ? { 'X-Viewer-Token': token } : {};
+}
+
+// Links and EventSource can't set headers, so they carry the token as a query parameter.
+export function withViewerToken(url: string, token: string | null | undefined): string {
+  if (!token) return url;
+  return `${url}${url.includes('?') ? '&' : '?'}token=${encodeURIComponent(token)}`;
+}
@@ -0,0 +1,3 @@
+DROP TABLE email_outbox;
+DROP TABLE email_subscriptions;
+ALTER TABLE events DROP COLUMN close_reminder_sent_at;
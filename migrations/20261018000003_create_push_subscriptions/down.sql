@@ -0,0 +1,2 @@
+DROP TABLE push_subscriptions;
+ALTER TABLE events DROP COLUMN push_reminder_sent_at;
@@ -0,0 +1 @@
+ALTER TABLE events DROP COLUMN guess_pow_difficulty;
@@ -0,0 +1 @@
+ALTER TABLE events ADD COLUMN guess_pow_difficulty INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+CREATE TABLE rate_limit_buckets (
+    policy VARCHAR NOT NULL,
+    key VARCHAR NOT NULL,
+    tokens DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (policy, key)
+);
+
+CREATE INDEX rate_limit_buckets_updated_at_idx ON rate_limit_buckets (updated_at);
@@ -0,0 +1 @@
+ALTER TABLE events ADD COLUMN embed_origins TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1 @@
+ALTER TABLE events ADD COLUMN language VARCHAR(8);
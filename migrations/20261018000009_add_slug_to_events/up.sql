@@ -0,0 +1 @@
+ALTER TABLE events ADD COLUMN slug VARCHAR(48) UNIQUE;
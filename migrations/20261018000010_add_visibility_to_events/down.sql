@@ -0,0 +1,2 @@
+ALTER TABLE events DROP COLUMN passcode;
+ALTER TABLE events DROP COLUMN visibility;
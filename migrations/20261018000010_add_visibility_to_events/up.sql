@@ -0,0 +1,3 @@
+ALTER TABLE events ADD COLUMN visibility VARCHAR(16) NOT NULL DEFAULT 'public'
+    CHECK (visibility IN ('public', 'passcode', 'hidden_guesses'));
+ALTER TABLE events ADD COLUMN passcode VARCHAR(64);
@@ -0,0 +1,2 @@
+ALTER TABLE events DROP COLUMN guesses_revealed_at;
+ALTER TABLE events DROP COLUMN sealed_guesses;
@@ -0,0 +1,2 @@
+ALTER TABLE events ADD COLUMN sealed_guesses BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE events ADD COLUMN guesses_revealed_at TIMESTAMP;
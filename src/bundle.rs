@@ -0,0 +1,340 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::WeightLimits,
+    event_keys::{self, KeyFormat},
+    models::{Event, Guess, Invitee, NewEvent},
+    schema::{events, guesses, invitees},
+    utils::generate_secret_key,
+    validation::{self, Invalid},
+    viewer::Visibility,
+};
+
+pub const BUNDLE_FORMAT: &str = "baby-birth-guessr/event";
+/// Bumped whenever a field changes meaning or becomes required; imports accept up to this.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A self-contained copy of an event: settings, answer, invitees and their guesses. Ids and
+/// keys are left out, so a bundle can be imported on any instance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub event: BundleEvent,
+    pub invitees: Vec<BundleInvitee>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleEvent {
+    pub title: String,
+    pub description: Option<String>,
+    pub due_date: Option<NaiveDateTime>,
+    pub guess_close_date: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub min_weight_kg: f64,
+    pub max_weight_kg: f64,
+    pub allow_guess_edits: bool,
+    #[serde(default)]
+    pub guess_pow_difficulty: u8,
+    #[serde(default)]
+    pub embed_origins: Vec<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// `public` when absent.
+    #[serde(default)]
+    pub visibility: String,
+    #[serde(default)]
+    pub passcode: Option<String>,
+    #[serde(default)]
+    pub sealed_guesses: bool,
+    /// Present once the host published the birth and ended the event.
+    pub answer: Option<BundleAnswer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleAnswer {
+    pub birth_date: NaiveDateTime,
+    pub birth_weight_kg: f64,
+    pub ended_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleInvitee {
+    pub display_name: String,
+    pub color_hex: String,
+    pub created_at: NaiveDateTime,
+    pub guesses: Vec<BundleGuess>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleGuess {
+    pub guessed_date: NaiveDateTime,
+    pub guessed_weight_kg: f64,
+    pub created_at: NaiveDateTime,
+}
+
+/// Settings of a bundle as they apply on this instance.
+#[derive(Debug)]
+pub struct ValidatedBundle {
+    pub min_weight_kg: f64,
+    pub max_weight_kg: f64,
+    pub embed_origins: Vec<String>,
+    pub language: Option<String>,
+    pub visibility: Visibility,
+    pub passcode: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// The bundle breaks a rule; the message is meant for the client.
+    Invalid(Invalid),
+    Db(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for ImportError {
+    fn from(e: diesel::result::Error) -> Self {
+        ImportError::Db(e)
+    }
+}
+
+impl EventBundle {
+    /// Collects `event` with its invitees and guesses.
+    pub fn export(conn: &mut PgConnection, event: &Event, now: NaiveDateTime) -> QueryResult<Self> {
+        let event_invitees = invitees::table
+            .filter(invitees::event_id.eq(event.id))
+            .order(invitees::created_at.asc())
+            .select(Invitee::as_select())
+            .load(conn)?;
+        let event_guesses = guesses::table
+            .inner_join(invitees::table)
+            .filter(invitees::event_id.eq(event.id))
+            .order(guesses::created_at.asc())
+            .select(Guess::as_select())
+            .load(conn)?;
+
+        let answer = match (event.birth_date, event.birth_weight_kg, event.ended_at) {
+            (Some(birth_date), Some(birth_weight_kg), Some(ended_at)) => Some(BundleAnswer {
+                birth_date,
+                birth_weight_kg,
+                ended_at,
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            exported_at: now,
+            event: BundleEvent {
+                title: event.title.clone(),
+                description: event.description.clone(),
+                due_date: event.due_date,
+                guess_close_date: event.guess_close_date,
+                created_at: event.created_at,
+                min_weight_kg: event.min_weight_kg,
+                max_weight_kg: event.max_weight_kg,
+                allow_guess_edits: event.allow_guess_edits,
+                guess_pow_difficulty: u8::try_from(event.guess_pow_difficulty).unwrap_or(0),
+                embed_origins: event.embed_origins.clone(),
+                language: event.language.clone(),
+                visibility: event.visibility.clone(),
+                passcode: event.passcode.clone(),
+                sealed_guesses: event.sealed_guesses,
+                answer,
+            },
+            invitees: event_invitees
+                .into_iter()
+                .map(|invitee| BundleInvitee {
+                    guesses: event_guesses
+                        .iter()
+                        .filter(|guess| guess.invitee_id == invitee.id)
+                        .map(|guess| BundleGuess {
+                            guessed_date: guess.guessed_date,
+                            guessed_weight_kg: guess.guessed_weight_kg,
+                            created_at: guess.created_at,
+                        })
+                        .collect(),
+                    display_name: invitee.display_name,
+                    color_hex: invitee.color_hex,
+                    created_at: invitee.created_at,
+                })
+                .collect(),
+        })
+    }
+
+    /// Checks the bundle against the rules for creating events and submitting guesses, and
+    /// returns the weight range, embed origins, language and visibility the event gets on this
+    /// instance.
+    ///
+    /// Events that are still open must have their due date and deadline ahead, exactly like a
+    /// new event; ended events are archives, so only the order of their dates is checked.
+    pub fn validate(
+        &self,
+        limits: &WeightLimits,
+        now: NaiveDateTime,
+    ) -> Result<ValidatedBundle, Invalid> {
+        if self.format != BUNDLE_FORMAT {
+            return Err(Invalid::new(
+                "invalid_bundle_format",
+                format!("format must be {:?}", BUNDLE_FORMAT),
+            ));
+        }
+        if self.version == 0 || self.version > BUNDLE_VERSION {
+            return Err(Invalid::new(
+                "unsupported_bundle_version",
+                format!(
+                    "Unsupported bundle version {} (this server reads up to {})",
+                    self.version, BUNDLE_VERSION
+                ),
+            ));
+        }
+
+        let event = &self.event;
+        validation::guess_pow_difficulty(event.guess_pow_difficulty)?;
+        let embed_origins = validation::embed_origins(&event.embed_origins)?;
+        let language = validation::event_language(event.language.as_deref())?;
+        let (visibility, passcode) =
+            validation::event_visibility(Some(&event.visibility), event.passcode.as_deref())?;
+        match &event.answer {
+            None => validation::event_dates(event.due_date, event.guess_close_date, now.date())?,
+            Some(answer) => {
+                if !answer.birth_weight_kg.is_finite() {
+                    return Err(Invalid::new(
+                        "birth_weight_not_finite",
+                        "answer.birth_weight_kg must be finite",
+                    ));
+                }
+                validation::event_date_order(event.due_date, event.guess_close_date)?
+            }
+        };
+
+        let (min_weight_kg, max_weight_kg) =
+            validation::weight_range(limits, Some(event.min_weight_kg), Some(event.max_weight_kg))?;
+        for invitee in &self.invitees {
+            for guess in &invitee.guesses {
+                if !validation::guessed_weight_in_range(
+                    guess.guessed_weight_kg,
+                    min_weight_kg,
+                    max_weight_kg,
+                ) {
+                    return Err(Invalid::new(
+                        "invalid_bundle_guess_weight",
+                        format!("Invalid guessed_weight_kg for {:?}", invitee.display_name),
+                    ));
+                }
+            }
+        }
+
+        Ok(ValidatedBundle {
+            min_weight_kg,
+            max_weight_kg,
+            embed_origins,
+            language,
+            visibility,
+            passcode,
+        })
+    }
+
+    /// Validates the bundle and recreates it as a new event with fresh ids and keys. Guess
+    /// and invitee timestamps are kept so ties in the results break the same way.
+    pub fn import(
+        &self,
+        conn: &mut PgConnection,
+        limits: &WeightLimits,
+        key_format: &KeyFormat,
+        now: NaiveDateTime,
+    ) -> Result<Event, ImportError> {
+        let ValidatedBundle {
+            min_weight_kg,
+            max_weight_kg,
+            embed_origins,
+            language,
+            visibility,
+            passcode,
+        } = self.validate(limits, now).map_err(ImportError::Invalid)?;
+        let event = &self.event;
+        let secret_key = generate_secret_key();
+
+        let imported = conn.transaction::<Event, diesel::result::Error, _>(|conn| {
+            let mut imported =
+                event_keys::insert_with_unique_key(conn, key_format, |conn, key| {
+                    diesel::insert_into(events::table)
+                        .values(&NewEvent {
+                            title: &event.title,
+                            description: event.description.as_deref(),
+                            due_date: event.due_date,
+                            guess_close_date: event.guess_close_date,
+                            event_key: key,
+                            secret_key: &secret_key,
+                            min_weight_kg,
+                            max_weight_kg,
+                            allow_guess_edits: event.allow_guess_edits && event.answer.is_none(),
+                            guess_pow_difficulty: i32::from(event.guess_pow_difficulty),
+                            language: language.as_deref(),
+                            // Slugs are chosen per instance and don't travel with the bundle.
+                            slug: None,
+                            visibility: visibility.as_str(),
+                            passcode: passcode.as_deref(),
+                            sealed_guesses: event.sealed_guesses,
+                        })
+                        .returning(Event::as_returning())
+                        .get_result(conn)
+                })?;
+
+            if !embed_origins.is_empty() {
+                imported = diesel::update(events::table.find(imported.id))
+                    .set(events::embed_origins.eq(&embed_origins))
+                    .returning(Event::as_returning())
+                    .get_result(conn)?;
+            }
+
+            if let Some(answer) = &event.answer {
+                // Retention counts from the import, not from when the original event ended.
+                imported = diesel::update(events::table.find(imported.id))
+                    .set((
+                        events::birth_date.eq(Some(answer.birth_date)),
+                        events::birth_weight_kg.eq(Some(answer.birth_weight_kg)),
+                        events::ended_at.eq(Some(answer.ended_at)),
+                        events::restored_at.eq(Some(now)),
+                    ))
+                    .returning(Event::as_returning())
+                    .get_result(conn)?;
+            }
+
+            for invitee in &self.invitees {
+                let invitee_id = diesel::insert_into(invitees::table)
+                    .values((
+                        invitees::event_id.eq(imported.id),
+                        invitees::display_name.eq(&invitee.display_name),
+                        invitees::color_hex.eq(&invitee.color_hex),
+                        invitees::created_at.eq(invitee.created_at),
+                    ))
+                    .returning(invitees::id)
+                    .get_result::<uuid::Uuid>(conn)?;
+
+                let rows: Vec<_> = invitee
+                    .guesses
+                    .iter()
+                    .map(|guess| {
+                        (
+                            guesses::invitee_id.eq(invitee_id),
+                            guesses::guessed_date.eq(guess.guessed_date),
+                            guesses::guessed_weight_kg.eq(guess.guessed_weight_kg),
+                            guesses::created_at.eq(guess.created_at),
+                        )
+                    })
+                    .collect();
+                diesel::insert_into(guesses::table)
+                    .values(&rows)
+                    .execute(conn)?;
+            }
+
+            Ok(imported)
+        })?;
+
+        Ok(imported)
+    }
+}
@@ -0,0 +1,194 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::pow::ProofOfWork;
+
+pub const TURNSTILE_VERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+pub const HCAPTCHA_VERIFY_URL: &str = "https://api.hcaptcha.com/siteverify";
+
+#[derive(Debug)]
+pub enum CaptchaError {
+    /// The token was checked and is not valid.
+    Rejected,
+    /// The verifier could not reach a decision (network error, bad response).
+    Unavailable(String),
+}
+
+/// Bot-protection check performed before accepting a new event (and optionally a guess).
+pub trait CaptchaVerifier: Send + Sync {
+    /// Short provider name exposed to the frontend (`turnstile`, `hcaptcha`, `pow`, `none`).
+    fn provider(&self) -> &'static str;
+
+    fn verify<'a>(
+        &'a self,
+        token: &'a str,
+        remote_ip: Option<IpAddr>,
+    ) -> BoxFuture<'a, Result<(), CaptchaError>>;
+}
+
+/// Accepts everything; for local development and tests.
+pub struct NoopVerifier;
+
+impl CaptchaVerifier for NoopVerifier {
+    fn provider(&self) -> &'static str {
+        "none"
+    }
+
+    fn verify<'a>(
+        &'a self,
+        _token: &'a str,
+        _remote_ip: Option<IpAddr>,
+    ) -> BoxFuture<'a, Result<(), CaptchaError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+}
+
+/// Turnstile and hCaptcha share the same `siteverify` form protocol.
+pub struct SiteVerifyVerifier {
+    provider: &'static str,
+    client: reqwest::Client,
+    verify_url: String,
+    secret: String,
+}
+
+impl SiteVerifyVerifier {
+    fn new(provider: &'static str, secret: String, verify_url: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("Failed to build captcha HTTP client");
+        Self {
+            provider,
+            client,
+            verify_url,
+            secret,
+        }
+    }
+
+    pub fn turnstile(secret: String, verify_url: Option<String>) -> Self {
+        Self::new(
+            "turnstile",
+            secret,
+            verify_url.unwrap_or_else(|| TURNSTILE_VERIFY_URL.to_string()),
+        )
+    }
+
+    pub fn hcaptcha(secret: String, verify_url: Option<String>) -> Self {
+        Self::new(
+            "hcaptcha",
+            secret,
+            verify_url.unwrap_or_else(|| HCAPTCHA_VERIFY_URL.to_string()),
+        )
+    }
+}
+
+impl CaptchaVerifier for SiteVerifyVerifier {
+    fn provider(&self) -> &'static str {
+        self.provider
+    }
+
+    fn verify<'a>(
+        &'a self,
+        token: &'a str,
+        remote_ip: Option<IpAddr>,
+    ) -> BoxFuture<'a, Result<(), CaptchaError>> {
+        Box::pin(async move {
+            let mut form = vec![("secret", self.secret.clone()), ("response", token.to_string())];
+            if let Some(ip) = remote_ip {
+                form.push(("remoteip", ip.to_string()));
+            }
+
+            let result = self
+                .client
+                .post(&self.verify_url)
+                .form(&form)
+                .send()
+                .await
+                .map_err(|e| CaptchaError::Unavailable(e.to_string()))?
+                .json::<SiteVerifyResponse>()
+                .await
+                .map_err(|e| CaptchaError::Unavailable(e.to_string()))?;
+
+            if result.success {
+                Ok(())
+            } else {
+                Err(CaptchaError::Rejected)
+            }
+        })
+    }
+}
+
+/// Self-hosted proof-of-work: the token is a solved challenge from `GET /api/pow/challenge`.
+pub struct PowVerifier {
+    pow: Arc<ProofOfWork>,
+}
+
+impl PowVerifier {
+    pub fn new(pow: Arc<ProofOfWork>) -> Self {
+        Self { pow }
+    }
+}
+
+impl CaptchaVerifier for PowVerifier {
+    fn provider(&self) -> &'static str {
+        "pow"
+    }
+
+    fn verify<'a>(
+        &'a self,
+        token: &'a str,
+        _remote_ip: Option<IpAddr>,
+    ) -> BoxFuture<'a, Result<(), CaptchaError>> {
+        Box::pin(async move { self.pow.verify(token).map_err(|_| CaptchaError::Rejected) })
+    }
+}
+
+#[derive(Serialize)]
+pub struct CaptchaConfig {
+    pub provider: &'static str,
+    /// Public site key the frontend renders the turnstile or hcaptcha widget with.
+    pub site_key: Option<String>,
+    pub on_guess: bool,
+}
+
+/// Which verifier to build, with its secret where the provider needs one.
+#[derive(Clone)]
+pub enum CaptchaProvider {
+    Turnstile { secret_key: String },
+    Hcaptcha { secret_key: String },
+    Pow,
+    None,
+}
+
+#[derive(Clone)]
+pub struct CaptchaSettings {
+    pub provider: CaptchaProvider,
+    /// Public site key of the turnstile or hcaptcha widget.
+    pub site_key: Option<String>,
+    /// Overrides the provider's `siteverify` URL.
+    pub verify_url: Option<String>,
+    /// Also require a captcha token on `submit_guess`.
+    pub on_guess: bool,
+}
+
+pub fn verifier(settings: &CaptchaSettings, pow: Arc<ProofOfWork>) -> Arc<dyn CaptchaVerifier> {
+    let verify_url = settings.verify_url.clone();
+    match &settings.provider {
+        CaptchaProvider::Turnstile { secret_key } => Arc::new(SiteVerifyVerifier::turnstile(
+            secret_key.clone(),
+            verify_url,
+        )),
+        CaptchaProvider::Hcaptcha { secret_key } => {
+            Arc::new(SiteVerifyVerifier::hcaptcha(secret_key.clone(), verify_url))
+        }
+        CaptchaProvider::Pow => Arc::new(PowVerifier::new(pow)),
+        CaptchaProvider::None => Arc::new(NoopVerifier),
+    }
+}
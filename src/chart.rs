@@ -0,0 +1,216 @@
+use chrono::{Duration, NaiveDateTime};
+use uuid::Uuid;
+
+use crate::{
+    i18n::Locale,
+    models::{Event, GraphPoint},
+    render::{self, FONT_FAMILY, RenderError, xml_escape},
+};
+
+pub const WIDTH: u32 = 800;
+pub const HEIGHT: u32 = 480;
+
+const LEFT: f64 = 100.0;
+const RIGHT: f64 = 48.0;
+const TOP: f64 = 56.0;
+const BOTTOM: f64 = 56.0;
+/// Fill for points whose stored color is not a plain hex color.
+const FALLBACK_COLOR: &str = "#8884d8";
+/// Like the frontend chart, a narrow range of dates is widened to five days.
+const MIN_SPAN_DAYS: i64 = 5;
+const MAX_X_TICKS: i64 = 7;
+
+/// Public URL of the PNG chart, for emails, webhooks and other places that can't run the
+/// frontend.
+pub fn png_url(base_url: &str, event_id: Uuid) -> String {
+    format!("{}/api/events/{}/chart.png", base_url, event_id)
+}
+
+/// Date-vs-weight scatter plot of the guesses, like the one on the event page: points in the
+/// guesser's color, the event's weight range, the due date and, once known, the answer.
+pub fn to_svg(event: &Event, points: &[GraphPoint], locale: Locale) -> String {
+    let answer = event.birth_date.zip(event.birth_weight_kg);
+
+    let (y_min, y_max) = weight_domain(event, answer.map(|(_, weight)| weight));
+    let (x_start, x_end) = date_domain(
+        points
+            .iter()
+            .map(|p| p.guessed_date)
+            .chain(event.due_date)
+            .chain(answer.map(|(date, _)| date)),
+    );
+
+    let plot_width = f64::from(WIDTH) - LEFT - RIGHT;
+    let plot_height = f64::from(HEIGHT) - TOP - BOTTOM;
+    let x_span = (x_end - x_start).num_seconds() as f64;
+    let x =
+        |date: NaiveDateTime| LEFT + (date - x_start).num_seconds() as f64 / x_span * plot_width;
+    let y = |weight: f64| TOP + (y_max - weight) / (y_max - y_min) * plot_height;
+    let (plot_left, plot_right) = (LEFT, LEFT + plot_width);
+    let (plot_top, plot_bottom) = (TOP, TOP + plot_height);
+
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="{FONT_FAMILY}, sans-serif">
+<rect width="{WIDTH}" height="{HEIGHT}" fill="#ffffff"/>
+<text x="{LEFT}" y="32" font-size="18" font-weight="bold" fill="#1e293b">{}</text>
+"##,
+        xml_escape(&event.title)
+    );
+
+    let days = (x_end - x_start).num_days();
+    let step = (days + MAX_X_TICKS - 1) / MAX_X_TICKS;
+    let mut tick = x_start;
+    while tick <= x_end {
+        let tx = x(tick);
+        svg.push_str(&format!(
+            r##"<line x1="{tx:.1}" y1="{plot_top}" x2="{tx:.1}" y2="{plot_bottom}" stroke="#e2e8f0" stroke-dasharray="3 3"/>
+<text x="{tx:.1}" y="{:.1}" font-size="12" fill="#475569" text-anchor="middle">{}</text>
+"##,
+            plot_bottom + 20.0,
+            locale.date(tick)
+        ));
+        tick += Duration::days(step.max(1));
+    }
+
+    let y_step = weight_step(y_max - y_min);
+    let mut weight = (y_min / y_step).ceil() * y_step;
+    while weight <= y_max + 1e-9 {
+        let ty = y(weight);
+        svg.push_str(&format!(
+            r##"<line x1="{plot_left}" y1="{ty:.1}" x2="{plot_right}" y2="{ty:.1}" stroke="#e2e8f0" stroke-dasharray="3 3"/>
+<text x="{:.1}" y="{:.1}" font-size="12" fill="#475569" text-anchor="end">{} kg</text>
+"##,
+            plot_left - 8.0,
+            ty + 4.0,
+            format_weight(weight)
+        ));
+        weight += y_step;
+    }
+
+    svg.push_str(&format!(
+        r##"<rect x="{plot_left}" y="{plot_top}" width="{plot_width}" height="{plot_height}" fill="none" stroke="#94a3b8"/>
+<text x="{:.1}" y="{:.1}" font-size="13" fill="#334155" text-anchor="middle">{}</text>
+<text transform="translate(18 {:.1}) rotate(-90)" font-size="13" fill="#334155" text-anchor="middle">{}</text>
+"##,
+        plot_left + plot_width / 2.0,
+        f64::from(HEIGHT) - 10.0,
+        xml_escape(locale.text("chart.guessed_date")),
+        plot_top + plot_height / 2.0,
+        xml_escape(locale.text("chart.guessed_weight")),
+    ));
+
+    if let Some(due_date) = event.due_date {
+        let dx = x(due_date);
+        svg.push_str(&format!(
+            r##"<line x1="{dx:.1}" y1="{plot_top}" x2="{dx:.1}" y2="{plot_bottom}" stroke="#6366f1" stroke-width="2" stroke-dasharray="6 4"/>
+<text x="{:.1}" y="{:.1}" font-size="12" fill="#6366f1">{}</text>
+"##,
+            dx + 4.0,
+            plot_top + 14.0,
+            xml_escape(locale.text("chart.due"))
+        ));
+    }
+
+    if points.is_empty() {
+        svg.push_str(&format!(
+            r##"<text x="{:.1}" y="{:.1}" font-size="16" fill="#64748b" text-anchor="middle">{}</text>
+"##,
+            plot_left + plot_width / 2.0,
+            plot_top + plot_height / 2.0,
+            xml_escape(locale.text("chart.no_guesses"))
+        ));
+    }
+    for point in points {
+        svg.push_str(&format!(
+            r##"<circle cx="{:.1}" cy="{:.1}" r="7" fill="{}" fill-opacity="0.8" stroke="#0f172a" stroke-opacity="0.3"/>
+"##,
+            x(point.guessed_date),
+            y(point.guessed_weight_kg),
+            point_color(&point.color_hex)
+        ));
+    }
+
+    if let Some((birth_date, birth_weight_kg)) = answer {
+        let (ax, ay) = (x(birth_date), y(birth_weight_kg));
+        svg.push_str(&format!(
+            r##"<line x1="{ax:.1}" y1="{plot_top}" x2="{ax:.1}" y2="{plot_bottom}" stroke="#16a34a" stroke-width="2"/>
+<line x1="{plot_left}" y1="{ay:.1}" x2="{plot_right}" y2="{ay:.1}" stroke="#16a34a" stroke-width="2"/>
+<circle cx="{ax:.1}" cy="{ay:.1}" r="9" fill="#ffffff" stroke="#16a34a" stroke-width="3"/>
+<text x="{:.1}" y="{:.1}" font-size="12" font-weight="bold" fill="#16a34a">{}</text>
+"##,
+            ax + 12.0,
+            ay - 12.0,
+            xml_escape(&locale.format(
+                "chart.born",
+                &[("weight", &locale.weight(birth_weight_kg))]
+            ))
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn to_png(
+    event: &Event,
+    points: &[GraphPoint],
+    locale: Locale,
+    scale: f32,
+) -> Result<Vec<u8>, RenderError> {
+    render::svg_to_png(&to_svg(event, points, locale), scale)
+}
+
+/// The event's weight range with a little padding, stretched to include the birth weight.
+fn weight_domain(event: &Event, birth_weight_kg: Option<f64>) -> (f64, f64) {
+    let mut min = event.min_weight_kg;
+    let mut max = event.max_weight_kg;
+    if let Some(weight) = birth_weight_kg.filter(|w| w.is_finite()) {
+        min = min.min(weight);
+        max = max.max(weight);
+    }
+    (min - 0.05, max + 0.05)
+}
+
+/// Midnight before the first date to midnight after the last, widened to [`MIN_SPAN_DAYS`]
+/// around the middle.
+fn date_domain(dates: impl Iterator<Item = NaiveDateTime>) -> (NaiveDateTime, NaiveDateTime) {
+    let days: Vec<_> = dates.map(|date| date.date()).collect();
+    let (Some(&first), Some(&last)) = (days.iter().min(), days.iter().max()) else {
+        let today = chrono::Utc::now().date_naive();
+        let start = today.and_hms_opt(0, 0, 0).unwrap_or_default();
+        return (start, start + Duration::days(MIN_SPAN_DAYS));
+    };
+
+    let span = (last - first).num_days() + 1;
+    let (first, last) = if span < MIN_SPAN_DAYS {
+        let first = first - Duration::days((MIN_SPAN_DAYS - span) / 2);
+        (first, first + Duration::days(MIN_SPAN_DAYS))
+    } else {
+        (first, last + Duration::days(1))
+    };
+    (
+        first.and_hms_opt(0, 0, 0).unwrap_or_default(),
+        last.and_hms_opt(0, 0, 0).unwrap_or_default(),
+    )
+}
+
+/// The smallest round gridline spacing that keeps the weight axis at ten lines or fewer.
+fn weight_step(span: f64) -> f64 {
+    [0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .find(|step| span / step <= 10.0)
+        .unwrap_or(20.0)
+}
+
+fn format_weight(weight: f64) -> String {
+    let text = format!("{:.2}", weight);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Guest-chosen colors are only trusted as `#rgb` or `#rrggbb`.
+fn point_color(color_hex: &str) -> &str {
+    let valid = color_hex.strip_prefix('#').is_some_and(|digits| {
+        matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if valid { color_hex } else { FALLBACK_COLOR }
+}
@@ -0,0 +1,149 @@
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+pub const DEFAULT_TRUSTED_PROXIES: &str = "127.0.0.0/8,::1/128";
+pub const DEFAULT_IPV6_PREFIX: u8 = 64;
+
+/// Resolves the real client address behind a chain of reverse proxies.
+///
+/// Forwarding headers are only honoured when the direct peer is a trusted proxy. The chain
+/// (`Forwarded` if present, otherwise `X-Forwarded-For`) is then walked right to left, and
+/// the first hop that is not itself a trusted proxy is the client.
+#[derive(Clone, Debug)]
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpNet>,
+    ipv6_prefix: u8,
+}
+
+impl Default for ClientIpResolver {
+    fn default() -> Self {
+        Self::new(
+            parse_networks(DEFAULT_TRUSTED_PROXIES).expect("default proxies are valid"),
+            DEFAULT_IPV6_PREFIX,
+        )
+    }
+}
+
+impl ClientIpResolver {
+    pub fn new(trusted_proxies: Vec<IpNet>, ipv6_prefix: u8) -> Self {
+        Self {
+            trusted_proxies,
+            ipv6_prefix: ipv6_prefix.min(128),
+        }
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    pub fn resolve(&self, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+        let peer_ip = canonical(peer.ip());
+        if !self.is_trusted(peer_ip) {
+            return peer_ip;
+        }
+
+        let chain = forwarded_chain(headers).unwrap_or_else(|| x_forwarded_for_chain(headers));
+
+        let mut client = peer_ip;
+        for hop in chain.iter().rev() {
+            // An unparseable hop ends the trustworthy part of the chain.
+            let Some(ip) = hop else {
+                break;
+            };
+            client = canonical(*ip);
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+        client
+    }
+
+    /// Key used for rate limiting: IPv6 clients are aggregated by network prefix,
+    /// since a single subscriber typically controls a whole /64.
+    pub fn rate_limit_key(&self, ip: IpAddr) -> IpAddr {
+        match canonical(ip) {
+            IpAddr::V4(v4) => IpAddr::V4(v4),
+            IpAddr::V6(v6) => {
+                let bits = u128::from(v6);
+                let mask = if self.ipv6_prefix == 0 {
+                    0
+                } else {
+                    u128::MAX << (128 - u32::from(self.ipv6_prefix))
+                };
+                IpAddr::V6(Ipv6Addr::from(bits & mask))
+            }
+        }
+    }
+}
+
+/// Parses a comma-separated list of CIDRs; bare addresses become host routes.
+pub fn parse_networks(list: &str) -> Result<Vec<IpNet>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map(|net| net.trunc())
+                .map_err(|_| format!("Invalid trusted proxy: {}", entry))
+        })
+        .collect()
+}
+
+/// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) are treated as IPv4.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+}
+
+fn x_forwarded_for_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_values(headers, "x-forwarded-for")
+        .map(|hop| parse_node(hop.trim()))
+        .collect()
+}
+
+/// Hops from the RFC 7239 `Forwarded` header, or `None` when the header is absent.
+fn forwarded_chain(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    if !headers.contains_key("forwarded") {
+        return None;
+    }
+
+    let chain = header_values(headers, "forwarded")
+        .map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                .and_then(|(_, value)| parse_node(value.trim().trim_matches('"')))
+        })
+        .collect();
+    Some(chain)
+}
+
+/// Parses `1.2.3.4`, `1.2.3.4:80`, `2001:db8::1` or `[2001:db8::1]:80`.
+/// Obfuscated identifiers and `unknown` yield `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
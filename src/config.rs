@@ -0,0 +1,799 @@
+use ipnet::IpNet;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::captcha::{CaptchaProvider, CaptchaSettings};
+use crate::client_ip;
+use crate::db::DbConfig;
+use crate::event_keys::{self, KeyFormat};
+use crate::layers::HttpConfig;
+use crate::notifications::{SmtpMailer, SmtpSettings};
+use crate::pow::{self, PowSettings};
+use crate::push::VapidKeys;
+use crate::rate_limit::{self, RateLimitConfig, RateLimitPolicy, StoreKind};
+
+const DEFAULT_HOST: &str = "0.0.0.0";
+const DEFAULT_PORT: u16 = 3000;
+const DEFAULT_RETENTION_DAYS: u32 = 365;
+const DEFAULT_DELETION_GRACE_DAYS: u32 = 30;
+const DEFAULT_BROADCAST_CAPACITY: usize = 100;
+/// Below the 10 seconds `podman stop` waits before killing the container.
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppEnv {
+    Development,
+    Production,
+    Test,
+}
+
+impl FromStr for AppEnv {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "development" | "dev" => Ok(AppEnv::Development),
+            "production" | "prod" => Ok(AppEnv::Production),
+            "test" => Ok(AppEnv::Test),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Bounds for an event's guessable weight range. Hosts may narrow the defaults but never
+/// leave the hard limits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightLimits {
+    pub default_min_kg: f64,
+    pub default_max_kg: f64,
+    pub hard_min_kg: f64,
+    pub hard_max_kg: f64,
+}
+
+impl Default for WeightLimits {
+    fn default() -> Self {
+        Self {
+            default_min_kg: 1.8,
+            default_max_kg: 5.2,
+            hard_min_kg: 1.0,
+            hard_max_kg: 8.0,
+        }
+    }
+}
+
+/// Application configuration, loaded once at startup by [`Config::load`].
+///
+/// Every setting has an environment variable and a key in the optional TOML file named by
+/// `CONFIG_FILE`; the environment wins over the file, and the file over the defaults.
+#[derive(Clone)]
+pub struct Config {
+    pub app_env: AppEnv,
+    pub database_url: String,
+    pub database: DbConfig,
+    pub host: String,
+    pub port: u16,
+    /// How long a graceful shutdown waits for open connections and background loops.
+    pub shutdown_timeout: Duration,
+    /// Public URL of the app without a trailing slash, used for links in emails and pushes.
+    pub public_base_url: Option<String>,
+    pub http: HttpConfig,
+    pub trusted_proxies: Vec<IpNet>,
+    pub ipv6_rate_limit_prefix: u8,
+    pub metrics_token: Option<String>,
+    /// SMTP relay for notification emails; without one emails are only logged.
+    pub smtp: Option<SmtpSettings>,
+    /// Web Push identity; push is disabled without one.
+    pub vapid_keys: Option<VapidKeys>,
+    pub pow: PowSettings,
+    /// HMAC key for viewer tokens; required in production. Without one a random key is used,
+    /// so tokens only work on the process that issued them.
+    pub viewer_token_secret: Option<String>,
+    pub captcha: CaptchaSettings,
+    pub rate_limits: RateLimitConfig,
+    pub weights: WeightLimits,
+    /// Days after an event ended (or, if it never did, after its last activity) before the
+    /// daily cleanup schedules it for deletion.
+    pub retention_days: u32,
+    /// Days a scheduled event stays restorable by its host before it is purged.
+    pub deletion_grace_days: u32,
+    /// Directory that receives an event bundle (see `bundle`) right before each purge.
+    pub purge_export_dir: Option<PathBuf>,
+    /// Live updates buffered per SSE subscriber before it starts missing them.
+    pub broadcast_capacity: usize,
+    /// Shape of the invite keys given to new and imported events.
+    pub event_keys: KeyFormat,
+    /// Lets webhooks and push endpoints reach loopback, private and other non-public
+    /// addresses. Off by default outside tests, since any host can register a webhook and any
+    /// guest a push endpoint.
+    pub webhook_allow_private_targets: bool,
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    fn single(problem: String) -> Self {
+        Self {
+            problems: vec![problem],
+        }
+    }
+}
+
+/// Flattens a TOML table into dotted keys; arrays become comma-separated lists.
+fn flatten(
+    prefix: &str,
+    table: toml::Table,
+    out: &mut BTreeMap<String, String>,
+) -> Result<(), String> {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(table) => flatten(&path, table, out)?,
+            toml::Value::Array(items) => {
+                let items = items
+                    .into_iter()
+                    .map(|item| {
+                        scalar(item).ok_or_else(|| format!("{}: expected a list of values", path))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                out.insert(path, items.join(","));
+            }
+            value => {
+                let value = scalar(value).ok_or_else(|| format!("{}: unsupported value", path))?;
+                out.insert(path, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scalar(value: toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+struct Sources<'a> {
+    file: BTreeMap<String, String>,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl Sources<'_> {
+    /// Raw value of a setting; the environment variable wins over the file key, and blank
+    /// values count as unset.
+    fn raw(&mut self, env: &str, key: &str) -> Option<String> {
+        let from_file = self.file.remove(key);
+        (self.env)(env)
+            .filter(|v| !v.trim().is_empty())
+            .or(from_file)
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn parse<T: FromStr>(&mut self, env: &str, key: &str, expected: &str) -> Option<T> {
+        let raw = self.raw(env, key)?;
+        match raw.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.problem(env, key, format!("expected {}, got {:?}", expected, raw));
+                None
+            }
+        }
+    }
+
+    fn flag(&mut self, env: &str, key: &str) -> Option<bool> {
+        let raw = self.raw(env, key)?;
+        match raw.as_str() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => {
+                self.problem(env, key, format!("expected true or false, got {:?}", raw));
+                None
+            }
+        }
+    }
+
+    fn problem(&mut self, env: &str, key: &str, message: impl fmt::Display) {
+        self.problems
+            .push(format!("{} ({}): {}", env, key, message));
+    }
+
+    fn policy(&mut self, default: RateLimitPolicy) -> RateLimitPolicy {
+        let name = default.name;
+        let upper = name.to_uppercase();
+        let burst_env = format!("RATE_LIMIT_{}_BURST", upper);
+        let burst_key = format!("rate_limits.{}.burst", name);
+        let rate_env = format!("RATE_LIMIT_{}_PER_MINUTE", upper);
+        let rate_key = format!("rate_limits.{}.per_minute", name);
+
+        let capacity = self
+            .parse::<u32>(&burst_env, &burst_key, "a whole number")
+            .unwrap_or(default.capacity);
+        if capacity == 0 {
+            self.problem(&burst_env, &burst_key, "must be at least 1");
+        }
+        let per_minute = self
+            .parse::<f64>(&rate_env, &rate_key, "a number")
+            .unwrap_or(default.refill_per_sec * 60.0);
+        if !(per_minute.is_finite() && per_minute > 0.0) {
+            self.problem(&rate_env, &rate_key, "must be greater than 0");
+        }
+
+        RateLimitPolicy {
+            name,
+            capacity,
+            refill_per_sec: per_minute / 60.0,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the environment and, when `CONFIG_FILE` is set, the TOML
+    /// file it names.
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match std::env::var("CONFIG_FILE").ok().filter(|p| !p.is_empty()) {
+            Some(path) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| ConfigError::single(format!("CONFIG_FILE {}: {}", path, e)))?,
+            ),
+            None => None,
+        };
+        Self::from_sources(file.as_deref(), &|name| std::env::var(name).ok())
+    }
+
+    /// Builds the configuration from TOML text and an environment lookup.
+    pub fn from_sources(
+        file: Option<&str>,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut flat = BTreeMap::new();
+        if let Some(text) = file {
+            let table = text
+                .parse::<toml::Table>()
+                .map_err(|e| ConfigError::single(format!("Config file: {}", e)))?;
+            flatten("", table, &mut flat)
+                .map_err(|e| ConfigError::single(format!("Config file: {}", e)))?;
+        }
+        let mut src = Sources {
+            file: flat,
+            env,
+            problems: Vec::new(),
+        };
+
+        let app_env = src
+            .parse("APP_ENV", "app_env", "development, production or test")
+            .unwrap_or(AppEnv::Production);
+
+        let database_url = src.raw("DATABASE_URL", "database.url");
+        if database_url.is_none() {
+            src.problem("DATABASE_URL", "database.url", "must be set");
+        }
+        let db_defaults = DbConfig::default();
+        let database = DbConfig {
+            max_size: src
+                .parse(
+                    "DB_POOL_MAX_SIZE",
+                    "database.pool_max_size",
+                    "a whole number",
+                )
+                .unwrap_or(db_defaults.max_size),
+            min_idle: src.parse(
+                "DB_POOL_MIN_IDLE",
+                "database.pool_min_idle",
+                "a whole number",
+            ),
+            connection_timeout: src
+                .parse(
+                    "DB_CONNECTION_TIMEOUT_SECS",
+                    "database.connection_timeout_secs",
+                    "a number of seconds",
+                )
+                .map(Duration::from_secs)
+                .unwrap_or(db_defaults.connection_timeout),
+            statement_timeout: src
+                .parse(
+                    "DB_STATEMENT_TIMEOUT_MS",
+                    "database.statement_timeout_ms",
+                    "a number of milliseconds",
+                )
+                .map(Duration::from_millis)
+                .unwrap_or(db_defaults.statement_timeout),
+        };
+        if database.max_size == 0 {
+            src.problem(
+                "DB_POOL_MAX_SIZE",
+                "database.pool_max_size",
+                "must be at least 1",
+            );
+        }
+        if database
+            .min_idle
+            .is_some_and(|idle| idle > database.max_size)
+        {
+            src.problem(
+                "DB_POOL_MIN_IDLE",
+                "database.pool_min_idle",
+                "must not exceed the pool size",
+            );
+        }
+        if database.connection_timeout.is_zero() {
+            src.problem(
+                "DB_CONNECTION_TIMEOUT_SECS",
+                "database.connection_timeout_secs",
+                "must be at least 1",
+            );
+        }
+
+        let host = src
+            .raw("HOST", "server.host")
+            .unwrap_or_else(|| DEFAULT_HOST.to_string());
+        let port = src
+            .parse("PORT", "server.port", "a port number")
+            .unwrap_or(DEFAULT_PORT);
+        let shutdown_timeout = Duration::from_secs(
+            src.parse(
+                "SHUTDOWN_TIMEOUT_SECS",
+                "server.shutdown_timeout_secs",
+                "a number of seconds",
+            )
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+        );
+        if shutdown_timeout.is_zero() {
+            src.problem(
+                "SHUTDOWN_TIMEOUT_SECS",
+                "server.shutdown_timeout_secs",
+                "must be at least 1",
+            );
+        }
+        let public_base_url = src
+            .raw("PUBLIC_BASE_URL", "server.public_base_url")
+            .map(|url| url.trim_end_matches('/').to_string());
+        if let Some(url) = &public_base_url
+            && !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            src.problem(
+                "PUBLIC_BASE_URL",
+                "server.public_base_url",
+                format!("expected an http(s) URL, got {:?}", url),
+            );
+        }
+
+        let http_defaults = HttpConfig::default();
+        let mut http = HttpConfig {
+            body_limit_bytes: src
+                .parse(
+                    "BODY_LIMIT_BYTES",
+                    "http.body_limit_bytes",
+                    "a number of bytes",
+                )
+                .unwrap_or(http_defaults.body_limit_bytes),
+            request_timeout: src
+                .parse(
+                    "REQUEST_TIMEOUT_SECS",
+                    "http.request_timeout_secs",
+                    "a number of seconds",
+                )
+                .map(Duration::from_secs)
+                .unwrap_or(http_defaults.request_timeout),
+            cors_allowed_origins: src
+                .raw("CORS_ALLOWED_ORIGINS", "http.cors_allowed_origins")
+                .map(|origins| {
+                    origins
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|o| !o.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }),
+        };
+        if let Some(origins) = &mut http.cors_allowed_origins
+            && origins.iter().any(|o| o == "*")
+        {
+            if origins.len() > 1 {
+                src.problem(
+                    "CORS_ALLOWED_ORIGINS",
+                    "http.cors_allowed_origins",
+                    "* allows every origin and can't be combined with a list",
+                );
+            }
+            origins.clear();
+        }
+        if http.request_timeout.is_zero() {
+            src.problem(
+                "REQUEST_TIMEOUT_SECS",
+                "http.request_timeout_secs",
+                "must be at least 1",
+            );
+        }
+
+        let trusted_proxies = src
+            .raw("TRUSTED_PROXIES", "http.trusted_proxies")
+            .unwrap_or_else(|| client_ip::DEFAULT_TRUSTED_PROXIES.to_string());
+        let trusted_proxies = match client_ip::parse_networks(&trusted_proxies) {
+            Ok(networks) => networks,
+            Err(e) => {
+                src.problem("TRUSTED_PROXIES", "http.trusted_proxies", e);
+                Vec::new()
+            }
+        };
+        let ipv6_rate_limit_prefix = src
+            .parse(
+                "IPV6_RATE_LIMIT_PREFIX",
+                "rate_limits.ipv6_prefix",
+                "a prefix length",
+            )
+            .unwrap_or(client_ip::DEFAULT_IPV6_PREFIX);
+        if ipv6_rate_limit_prefix > 128 {
+            src.problem(
+                "IPV6_RATE_LIMIT_PREFIX",
+                "rate_limits.ipv6_prefix",
+                "must be at most 128",
+            );
+        }
+
+        let metrics_token = src.raw("METRICS_TOKEN", "metrics.token");
+
+        let smtp_host = src.raw("SMTP_HOST", "smtp.host");
+        let starttls = src.flag("SMTP_STARTTLS", "smtp.starttls").unwrap_or(true);
+        let smtp_port = src
+            .parse("SMTP_PORT", "smtp.port", "a port number")
+            .unwrap_or(if starttls { 587 } else { 25 });
+        let smtp_username = src.raw("SMTP_USERNAME", "smtp.username");
+        let smtp_password = src.raw("SMTP_PASSWORD", "smtp.password");
+        let smtp_from = src
+            .raw("SMTP_FROM", "smtp.from")
+            .unwrap_or_else(|| "Baby Birth Guessr <noreply@localhost>".to_string());
+        let smtp_from_valid = smtp_from.parse::<lettre::message::Mailbox>();
+        if let Err(e) = &smtp_from_valid {
+            src.problem(
+                "SMTP_FROM",
+                "smtp.from",
+                format!("expected an address like \"Name <user@host>\": {}", e),
+            );
+        }
+        if smtp_username.is_some() != smtp_password.is_some() {
+            src.problem(
+                "SMTP_USERNAME",
+                "smtp.username",
+                "must be set together with SMTP_PASSWORD",
+            );
+        }
+        let smtp = smtp_host.map(|host| SmtpSettings {
+            host,
+            port: smtp_port,
+            username: smtp_username,
+            password: smtp_password,
+            from: smtp_from,
+            starttls,
+        });
+        if let Some(settings) = &smtp
+            && smtp_from_valid.is_ok()
+            && let Err(e) = SmtpMailer::new(settings.clone())
+        {
+            src.problem("SMTP_HOST", "smtp.host", e);
+        }
+
+        let vapid_private_key = src.raw("VAPID_PRIVATE_KEY", "push.vapid_private_key");
+        let vapid_subject = src
+            .raw("VAPID_SUBJECT", "push.vapid_subject")
+            .unwrap_or_else(|| "mailto:admin@localhost".to_string());
+        if !(vapid_subject.starts_with("mailto:") || vapid_subject.starts_with("https:")) {
+            src.problem(
+                "VAPID_SUBJECT",
+                "push.vapid_subject",
+                format!("expected a mailto: or https: URL, got {:?}", vapid_subject),
+            );
+        }
+        let vapid_keys = match vapid_private_key {
+            Some(key) => match VapidKeys::new(&key, &vapid_subject) {
+                Ok(keys) => Some(keys),
+                Err(e) => {
+                    src.problem("VAPID_PRIVATE_KEY", "push.vapid_private_key", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let pow = PowSettings {
+            secret: src.raw("POW_SECRET", "pow.secret"),
+            difficulty: src
+                .parse("POW_DIFFICULTY", "pow.difficulty", "a number of bits")
+                .unwrap_or(pow::DEFAULT_DIFFICULTY),
+        };
+        if pow.difficulty > pow::MAX_DIFFICULTY {
+            src.problem(
+                "POW_DIFFICULTY",
+                "pow.difficulty",
+                format!("must be at most {}", pow::MAX_DIFFICULTY),
+            );
+        }
+
+        let viewer_token_secret = src.raw("VIEWER_TOKEN_SECRET", "viewer_tokens.secret");
+        if viewer_token_secret.is_none() && app_env == AppEnv::Production {
+            src.problem(
+                "VIEWER_TOKEN_SECRET",
+                "viewer_tokens.secret",
+                "must be set in production",
+            );
+        }
+
+        let default_provider = if app_env == AppEnv::Test {
+            "none"
+        } else {
+            "turnstile"
+        };
+        let provider = src
+            .raw("CAPTCHA_PROVIDER", "captcha.provider")
+            .unwrap_or_else(|| default_provider.to_string());
+        let turnstile_secret = src.raw("TURNSTILE_SECRET_KEY", "captcha.turnstile_secret_key");
+        let hcaptcha_secret = src.raw("HCAPTCHA_SECRET_KEY", "captcha.hcaptcha_secret_key");
+        let site_key = src.raw("CAPTCHA_SITE_KEY", "captcha.site_key");
+        let provider = match provider.as_str() {
+            "turnstile" => match turnstile_secret {
+                Some(secret_key) => CaptchaProvider::Turnstile { secret_key },
+                None => {
+                    src.problem(
+                        "TURNSTILE_SECRET_KEY",
+                        "captcha.turnstile_secret_key",
+                        "must be set when the captcha provider is turnstile",
+                    );
+                    CaptchaProvider::None
+                }
+            },
+            "hcaptcha" => match hcaptcha_secret {
+                Some(secret_key) => {
+                    if site_key.is_none() {
+                        src.problem(
+                            "CAPTCHA_SITE_KEY",
+                            "captcha.site_key",
+                            "must be set when the captcha provider is hcaptcha",
+                        );
+                    }
+                    CaptchaProvider::Hcaptcha { secret_key }
+                }
+                None => {
+                    src.problem(
+                        "HCAPTCHA_SECRET_KEY",
+                        "captcha.hcaptcha_secret_key",
+                        "must be set when the captcha provider is hcaptcha",
+                    );
+                    CaptchaProvider::None
+                }
+            },
+            "pow" => CaptchaProvider::Pow,
+            "none" => CaptchaProvider::None,
+            other => {
+                src.problem(
+                    "CAPTCHA_PROVIDER",
+                    "captcha.provider",
+                    format!("expected turnstile, hcaptcha, pow or none, got {:?}", other),
+                );
+                CaptchaProvider::None
+            }
+        };
+        let captcha = CaptchaSettings {
+            provider,
+            site_key,
+            verify_url: src.raw("CAPTCHA_VERIFY_URL", "captcha.verify_url"),
+            on_guess: src
+                .flag("CAPTCHA_ON_GUESS", "captcha.on_guess")
+                .unwrap_or(false),
+        };
+
+        let store = src
+            .parse(
+                "RATE_LIMIT_STORE",
+                "rate_limits.store",
+                "memory or postgres",
+            )
+            .unwrap_or(StoreKind::Memory);
+        let rate_limits = RateLimitConfig {
+            store,
+            submit_guess: src.policy(rate_limit::SUBMIT_GUESS),
+            claim_event: src.policy(rate_limit::CLAIM_EVENT),
+            subscribe_push: src.policy(rate_limit::SUBSCRIBE_PUSH),
+            unlock_event: src.policy(rate_limit::UNLOCK_EVENT),
+        };
+
+        let weight_defaults = WeightLimits::default();
+        let weights = WeightLimits {
+            default_min_kg: src
+                .parse(
+                    "WEIGHT_DEFAULT_MIN_KG",
+                    "weights.default_min_kg",
+                    "a number",
+                )
+                .unwrap_or(weight_defaults.default_min_kg),
+            default_max_kg: src
+                .parse(
+                    "WEIGHT_DEFAULT_MAX_KG",
+                    "weights.default_max_kg",
+                    "a number",
+                )
+                .unwrap_or(weight_defaults.default_max_kg),
+            hard_min_kg: src
+                .parse("WEIGHT_HARD_MIN_KG", "weights.hard_min_kg", "a number")
+                .unwrap_or(weight_defaults.hard_min_kg),
+            hard_max_kg: src
+                .parse("WEIGHT_HARD_MAX_KG", "weights.hard_max_kg", "a number")
+                .unwrap_or(weight_defaults.hard_max_kg),
+        };
+        if !(weights.hard_min_kg > 0.0
+            && weights.hard_min_kg < weights.hard_max_kg
+            && weights.hard_max_kg.is_finite())
+        {
+            src.problem(
+                "WEIGHT_HARD_MIN_KG",
+                "weights.hard_min_kg",
+                "hard limits must satisfy 0 < hard_min_kg < hard_max_kg",
+            );
+        } else if !(weights.hard_min_kg <= weights.default_min_kg
+            && weights.default_min_kg < weights.default_max_kg
+            && weights.default_max_kg <= weights.hard_max_kg)
+        {
+            src.problem(
+                "WEIGHT_DEFAULT_MIN_KG",
+                "weights.default_min_kg",
+                "defaults must satisfy hard_min_kg <= default_min_kg < default_max_kg <= hard_max_kg",
+            );
+        }
+
+        let retention_days = src
+            .parse(
+                "EVENT_RETENTION_DAYS",
+                "events.retention_days",
+                "a number of days",
+            )
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        if retention_days == 0 {
+            src.problem(
+                "EVENT_RETENTION_DAYS",
+                "events.retention_days",
+                "must be at least 1",
+            );
+        }
+        let deletion_grace_days = src
+            .parse(
+                "EVENT_DELETION_GRACE_DAYS",
+                "events.deletion_grace_days",
+                "a number of days",
+            )
+            .unwrap_or(DEFAULT_DELETION_GRACE_DAYS);
+        if deletion_grace_days == 0 {
+            src.problem(
+                "EVENT_DELETION_GRACE_DAYS",
+                "events.deletion_grace_days",
+                "must be at least 1",
+            );
+        }
+        let purge_export_dir = src
+            .raw("EVENT_PURGE_EXPORT_DIR", "events.purge_export_dir")
+            .map(PathBuf::from);
+        let broadcast_capacity = src
+            .parse(
+                "BROADCAST_CAPACITY",
+                "events.broadcast_capacity",
+                "a whole number",
+            )
+            .unwrap_or(DEFAULT_BROADCAST_CAPACITY);
+        if broadcast_capacity == 0 {
+            src.problem(
+                "BROADCAST_CAPACITY",
+                "events.broadcast_capacity",
+                "must be at least 1",
+            );
+        }
+
+        let key_defaults = KeyFormat::default();
+        let event_keys = KeyFormat {
+            groups: src
+                .parse("EVENT_KEY_GROUPS", "event_keys.groups", "a whole number")
+                .unwrap_or(key_defaults.groups),
+            group_length: src
+                .parse(
+                    "EVENT_KEY_GROUP_LENGTH",
+                    "event_keys.group_length",
+                    "a whole number",
+                )
+                .unwrap_or(key_defaults.group_length),
+        };
+        if !(1..=event_keys::MAX_GROUPS).contains(&event_keys.groups) {
+            src.problem(
+                "EVENT_KEY_GROUPS",
+                "event_keys.groups",
+                format!("must be between 1 and {}", event_keys::MAX_GROUPS),
+            );
+        } else if !(1..=event_keys::MAX_GROUP_LENGTH).contains(&event_keys.group_length) {
+            src.problem(
+                "EVENT_KEY_GROUP_LENGTH",
+                "event_keys.group_length",
+                format!("must be between 1 and {}", event_keys::MAX_GROUP_LENGTH),
+            );
+        } else if event_keys.chars() < event_keys::MIN_KEY_CHARS {
+            src.problem(
+                "EVENT_KEY_GROUP_LENGTH",
+                "event_keys.group_length",
+                format!(
+                    "keys must have at least {} characters, got {} groups of {}",
+                    event_keys::MIN_KEY_CHARS,
+                    event_keys.groups,
+                    event_keys.group_length
+                ),
+            );
+        }
+
+        let webhook_allow_private_targets = src
+            .flag(
+                "WEBHOOK_ALLOW_PRIVATE_TARGETS",
+                "webhooks.allow_private_targets",
+            )
+            .unwrap_or(app_env == AppEnv::Test);
+
+        let unknown: Vec<String> = src.file.keys().cloned().collect();
+        for key in unknown {
+            src.problems
+                .push(format!("Config file: unknown setting {:?}", key));
+        }
+
+        if !src.problems.is_empty() {
+            return Err(ConfigError {
+                problems: src.problems,
+            });
+        }
+
+        Ok(Self {
+            app_env,
+            database_url: database_url.unwrap_or_default(),
+            database,
+            host,
+            port,
+            shutdown_timeout,
+            public_base_url,
+            http,
+            trusted_proxies,
+            ipv6_rate_limit_prefix,
+            metrics_token,
+            smtp,
+            vapid_keys,
+            pow,
+            viewer_token_secret,
+            captcha,
+            rate_limits,
+            weights,
+            retention_days,
+            deletion_grace_days,
+            purge_export_dir,
+            broadcast_capacity,
+            event_keys,
+            webhook_allow_private_targets,
+        })
+    }
+}
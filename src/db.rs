@@ -0,0 +1,96 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection};
+use std::fmt;
+use std::time::Duration;
+
+use crate::types::DbPool;
+
+const DEFAULT_POOL_MAX_SIZE: u32 = 10;
+const DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_STATEMENT_TIMEOUT_MS: u64 = 30_000;
+
+/// Pool sizing and timeouts, part of [`crate::config::Config`].
+#[derive(Clone, Debug)]
+pub struct DbConfig {
+    pub max_size: u32,
+    pub min_idle: Option<u32>,
+    /// How long a request waits for a free connection before failing.
+    pub connection_timeout: Duration,
+    /// Server-side `statement_timeout` set on every connection; zero disables it.
+    pub statement_timeout: Duration,
+}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_POOL_MAX_SIZE,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(DEFAULT_CONNECTION_TIMEOUT_SECS),
+            statement_timeout: Duration::from_millis(DEFAULT_STATEMENT_TIMEOUT_MS),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StatementTimeout(Duration);
+
+impl CustomizeConnection<PgConnection, r2d2::Error> for StatementTimeout {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), r2d2::Error> {
+        diesel::sql_query(format!("SET statement_timeout = {}", self.0.as_millis()))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+pub fn create_pool(database_url: &str, config: &DbConfig) -> DbPool {
+    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    r2d2::Pool::builder()
+        .max_size(config.max_size)
+        .min_idle(config.min_idle)
+        .connection_timeout(config.connection_timeout)
+        .connection_customizer(Box::new(StatementTimeout(config.statement_timeout)))
+        .build(manager)
+        .expect("Failed to create pool")
+}
+
+#[derive(Debug)]
+pub enum DbError {
+    /// No connection became available within the pool's connection timeout.
+    Pool(r2d2::PoolError),
+    Query(diesel::result::Error),
+    Panicked(String),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Pool(e) => write!(f, "Failed to get connection: {}", e),
+            DbError::Query(e) => write!(f, "Diesel error: {}", e),
+            DbError::Panicked(e) => write!(f, "Database task panicked: {}", e),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for DbError {
+    fn from(e: diesel::result::Error) -> Self {
+        DbError::Query(e)
+    }
+}
+
+/// Runs blocking Diesel work with a pooled connection on Tokio's blocking thread pool, so
+/// neither waiting for a connection nor the query itself stalls the async workers.
+pub async fn run<T, F>(pool: &DbPool, f: F) -> Result<T, DbError>
+where
+    F: FnOnce(&mut PgConnection) -> QueryResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(DbError::Pool)?;
+        f(&mut conn).map_err(DbError::Query)
+    })
+    .await
+    .map_err(|e| DbError::Panicked(e.to_string()))?
+}
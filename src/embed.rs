@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::Event,
+    og_image::{self, ShareCard},
+    render::xml_escape,
+    utils::effective_guess_close_date,
+};
+
+pub const DEFAULT_WIDTH: u32 = 400;
+pub const DEFAULT_HEIGHT: u32 = 240;
+pub const COMPACT_HEIGHT: u32 = 72;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Query parameters of `/embed/{key}`: `theme=light|dark`, `compact=1` and `lang=fi`.
+#[derive(Deserialize, Debug, Default)]
+pub struct EmbedQuery {
+    pub theme: Option<String>,
+    pub compact: Option<String>,
+    pub lang: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmbedOptions {
+    pub theme: Theme,
+    pub compact: bool,
+}
+
+impl From<&EmbedQuery> for EmbedOptions {
+    /// Unknown values fall back to the defaults rather than failing, so a typo in a pasted
+    /// snippet still shows the widget.
+    fn from(query: &EmbedQuery) -> Self {
+        Self {
+            theme: match query.theme.as_deref() {
+                Some("dark") => Theme::Dark,
+                _ => Theme::Light,
+            },
+            compact: matches!(query.compact.as_deref(), Some("1" | "true" | "yes")),
+        }
+    }
+}
+
+/// `Content-Security-Policy` of the widget: only its own nonce-tagged script and style run,
+/// and only the event's allowed origins (any site when it has none) may frame it.
+pub fn content_security_policy(event: &Event, nonce: &str) -> String {
+    let ancestors = if event.embed_origins.is_empty() {
+        "*".to_string()
+    } else {
+        event.embed_origins.join(" ")
+    };
+    format!(
+        "default-src 'none'; script-src 'nonce-{nonce}'; style-src 'nonce-{nonce}'; \
+         connect-src 'self'; base-uri 'none'; form-action 'none'; frame-ancestors {ancestors}"
+    )
+}
+
+/// The read-only widget, in the card's language: title, due date and guess count, or the
+/// result once the event ended. Its script follows the live stream and refreshes the count as
+/// guesses come and go, passing on `viewer_token` so a passcode event stays readable.
+pub fn render(
+    event: &Event,
+    card: &ShareCard,
+    options: EmbedOptions,
+    event_url: &str,
+    viewer_token: Option<&str>,
+    nonce: &str,
+) -> String {
+    let (background, text, muted, accent) = match options.theme {
+        Theme::Light => ("#ffffff", "#1e293b", "#64748b", "#b56576"),
+        Theme::Dark => ("#0f172a", "#f1f5f9", "#94a3b8", "#f4a3b4"),
+    };
+    let locale = card.locale;
+    let lang = locale.code();
+    let title = xml_escape(&event.title);
+    let count = locale.guesses(card.guesses);
+    let open_event = locale.text("share.open_event");
+
+    let body = if options.compact {
+        format!(
+            r#"<a class="compact" href="{url}" target="_blank" rel="noopener"><strong>{title}</strong> · <span id="guess-count">{count}</span></a>"#,
+            url = xml_escape(event_url),
+        )
+    } else {
+        let mut details = Vec::new();
+        match &card.result {
+            Some(result) => {
+                details.push(locale.format(
+                    "card.born",
+                    &[
+                        ("date", &locale.long_date(result.birth_date)),
+                        ("weight", &locale.weight(result.birth_weight_kg)),
+                    ],
+                ));
+                if let Some(name) = &result.closest_date {
+                    details.push(locale.format("card.closest_date", &[("name", name)]));
+                }
+                if let Some(name) = &result.closest_weight {
+                    details.push(locale.format("card.closest_weight", &[("name", name)]));
+                }
+            }
+            None => {
+                if let Some(due_date) = card.due_date {
+                    details
+                        .push(locale.format("card.due", &[("date", &locale.long_date(due_date))]));
+                }
+                if let Some(close) = effective_guess_close_date(event) {
+                    details.push(locale.format(
+                        "card.guessing_closes",
+                        &[("date", &locale.long_date(close))],
+                    ));
+                }
+            }
+        }
+        let details: String = details
+            .iter()
+            .map(|line| format!("<p>{}</p>", xml_escape(line)))
+            .collect();
+        format!(
+            r#"<p class="brand">Baby Birth Guessr</p>
+<h1>{title}</h1>
+<p class="count" id="guess-count">{count}</p>
+{details}
+<a class="open" href="{url}" target="_blank" rel="noopener">{open_event}</a>"#,
+            url = xml_escape(event_url),
+        )
+    };
+
+    let mut stream_url = format!("/api/events/live?event_key={}", event.event_key);
+    let mut guesses_url = format!("/api/events/{}/guesses", event.id);
+    if let Some(token) = viewer_token {
+        stream_url.push_str(&format!("&token={}", token));
+        guesses_url.push_str(&format!("?token={}", token));
+    }
+    // JSON string literals are valid JavaScript; `<\/` keeps them from closing the script tag.
+    let js_string = |value: &str| {
+        serde_json::to_string(value)
+            .unwrap_or_default()
+            .replace("</", "<\\/")
+    };
+
+    format!(
+        r#"<!doctype html>
+<html lang="{lang}">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <meta name="robots" content="noindex" />
+    <title>{title}</title>
+    <style nonce="{nonce}">
+      body {{ margin: 0; padding: 16px; background: {background}; color: {text}; font-family: system-ui, -apple-system, Segoe UI, Roboto, Helvetica, Arial, sans-serif; }}
+      h1 {{ margin: 4px 0 8px; font-size: 20px; }}
+      p {{ margin: 0 0 4px; color: {muted}; font-size: 14px; }}
+      .brand {{ color: {accent}; font-weight: bold; font-size: 12px; }}
+      .count {{ color: {text}; font-size: 28px; font-weight: bold; margin-bottom: 8px; }}
+      a {{ color: {accent}; }}
+      .open {{ display: inline-block; margin-top: 8px; font-size: 14px; }}
+      .compact {{ color: {text}; text-decoration: none; font-size: 15px; }}
+    </style>
+  </head>
+  <body>
+{body}
+    <script nonce="{nonce}">
+      (function () {{
+        var count = document.getElementById("guess-count");
+        function label(n) {{ return n === 1 ? {one} : {other}.replace("{{count}}", n); }}
+        function refresh() {{
+          fetch({guesses_url})
+            .then(function (res) {{ return res.ok ? res.json() : null; }})
+            .then(function (guesses) {{
+              if (Array.isArray(guesses)) {{ count.textContent = label(guesses.length); }}
+            }})
+            .catch(function () {{}});
+        }}
+        var source = new EventSource({stream_url});
+        source.onmessage = function (message) {{
+          var update = JSON.parse(message.data);
+          if (update.type === "guess" || update.type === "guess_sealed" || update.type === "guess_deleted") {{
+            refresh();
+          }} else if (update.type === "event_ended") {{
+            window.location.reload();
+          }}
+        }};
+      }})();
+    </script>
+  </body>
+</html>
+"#,
+        guesses_url = js_string(&guesses_url),
+        stream_url = js_string(&stream_url),
+        one = js_string(locale.text("guesses.one")),
+        other = js_string(locale.text("guesses.other")),
+    )
+}
+
+/// Finds the event key in a share (`/share/{key}`), widget (`/embed/{key}`) or event page
+/// (`/event?key=`) URL.
+pub fn key_from_url(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["share" | "embed", key] => Some((*key).to_string()),
+        ["event"] => url
+            .query_pairs()
+            .find(|(name, _)| name == "key")
+            .map(|(_, key)| key.into_owned()),
+        _ => None,
+    }
+}
+
+/// A `rich` oEmbed response that frames the widget.
+#[derive(Serialize, Debug)]
+pub struct OEmbed {
+    pub version: &'static str,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub provider_name: &'static str,
+    pub provider_url: String,
+    pub title: String,
+    pub html: String,
+    pub width: u32,
+    pub height: u32,
+    pub cache_age: u32,
+    pub thumbnail_url: String,
+    pub thumbnail_width: u32,
+    pub thumbnail_height: u32,
+}
+
+impl OEmbed {
+    /// Sizes the frame to fit `max_width` and `max_height`; heights too small for the full
+    /// widget get the compact one.
+    pub fn new(
+        event: &Event,
+        base_url: &str,
+        thumbnail_url: String,
+        max_width: Option<u32>,
+        max_height: Option<u32>,
+    ) -> Self {
+        let width = max_width.map_or(DEFAULT_WIDTH, |max| max.min(DEFAULT_WIDTH));
+        let (compact, height) = match max_height {
+            Some(max) if max < DEFAULT_HEIGHT => (true, max.min(COMPACT_HEIGHT)),
+            _ => (false, DEFAULT_HEIGHT),
+        };
+        let src = format!(
+            "{}/embed/{}{}",
+            base_url,
+            event.event_key,
+            if compact { "?compact=1" } else { "" }
+        );
+        let title = xml_escape(&event.title);
+
+        Self {
+            version: "1.0",
+            kind: "rich",
+            provider_name: "Baby Birth Guessr",
+            provider_url: base_url.to_string(),
+            title: event.title.clone(),
+            html: format!(
+                r#"<iframe src="{}" width="{width}" height="{height}" title="{title}" style="border:0" loading="lazy"></iframe>"#,
+                xml_escape(&src)
+            ),
+            width,
+            height,
+            cache_age: 300,
+            thumbnail_url,
+            thumbnail_width: og_image::WIDTH,
+            thumbnail_height: og_image::HEIGHT,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use rand::Rng;
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+use crate::schema::events;
+
+/// Letters and digits that can't be mistaken for one another when read out or handwritten:
+/// no 0/O and no 1/I.
+pub const KEY_ALPHABET: &[u8] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+/// Shortest key a format may produce: 40 bits, far beyond what guessing keys could find.
+pub const MIN_KEY_CHARS: usize = 8;
+pub const MAX_GROUPS: usize = 6;
+pub const MAX_GROUP_LENGTH: usize = 8;
+
+pub const MIN_SLUG_CHARS: usize = 3;
+pub const MAX_SLUG_CHARS: usize = 48;
+pub const SLUG_CONSTRAINT: &str = "events_slug_key";
+
+const EVENT_KEY_CONSTRAINT: &str = "events_event_key_key";
+/// Even short formats almost never collide twice in a row; more attempts would hide a format
+/// that is too small for the number of events.
+const MAX_INSERT_ATTEMPTS: usize = 5;
+/// Blocked words this long are also rejected inside longer words.
+const MIN_EMBEDDED_WORD_CHARS: usize = 5;
+
+/// Page and API names a slug could be confused with.
+const RESERVED_SLUGS: &[&str] = &[
+    "about", "admin", "api", "assets", "create", "embed", "event", "events", "health", "help",
+    "home", "import", "live", "login", "metrics", "new", "oembed", "privacy", "settings", "share",
+    "static", "terms", "www",
+];
+
+static BLOCKED_WORDS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    include_str!("../assets/slugs/blocked_words.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+});
+
+/// Shape of generated event keys: `groups` runs of `group_length` characters joined by
+/// dashes, e.g. `K7QM-3XRT-9HPA`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyFormat {
+    pub groups: usize,
+    pub group_length: usize,
+}
+
+impl Default for KeyFormat {
+    fn default() -> Self {
+        Self {
+            groups: 3,
+            group_length: 4,
+        }
+    }
+}
+
+impl KeyFormat {
+    pub fn chars(&self) -> usize {
+        self.groups * self.group_length
+    }
+
+    /// A random key in this format. Keys that happen to spell a blocked word are drawn again.
+    pub fn generate(&self) -> String {
+        let mut rng = rand::thread_rng();
+        loop {
+            let key = (0..self.groups)
+                .map(|_| {
+                    (0..self.group_length)
+                        .map(|_| KEY_ALPHABET[rng.gen_range(0..KEY_ALPHABET.len())] as char)
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("-");
+            if !contains_blocked_word(&key) {
+                return key;
+            }
+        }
+    }
+}
+
+/// The forms a typed key or slug is stored in: keys in upper case, slugs in lower case.
+/// Spaces count as dashes, so a key read out over the phone can be typed as heard.
+pub fn lookup_forms(input: &str) -> (String, String) {
+    let joined = input.split_whitespace().collect::<Vec<_>>().join("-");
+    (joined.to_ascii_uppercase(), joined.to_ascii_lowercase())
+}
+
+pub fn is_reserved_slug(slug: &str) -> bool {
+    RESERVED_SLUGS.contains(&slug)
+}
+
+/// Whether any dash-separated part of `text` is a blocked word, also when spelled with digits
+/// (`5h1t`) or split by dashes (`f-u-c-k`).
+pub fn contains_blocked_word(text: &str) -> bool {
+    let text: String = text
+        .chars()
+        .map(|c| match c.to_lowercase().next().unwrap_or(c) {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            '8' => 'b',
+            c => c,
+        })
+        .collect();
+    let compact = text.replace('-', "");
+
+    text.split('-')
+        .chain(std::iter::once(compact.as_str()))
+        .any(|part| {
+            BLOCKED_WORDS.iter().any(|word| {
+                part == *word
+                    || (word.chars().count() >= MIN_EMBEDDED_WORD_CHARS && part.contains(word))
+            })
+        })
+}
+
+pub fn is_unique_violation(error: &DieselError, constraint: &str) -> bool {
+    matches!(
+        error,
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info)
+            if info.constraint_name() == Some(constraint)
+    )
+}
+
+/// Runs `insert` with a freshly generated key, drawing a new one while keys collide. Each
+/// attempt gets its own savepoint so a collision doesn't abort an enclosing transaction.
+pub fn insert_with_unique_key<T>(
+    conn: &mut PgConnection,
+    format: &KeyFormat,
+    mut insert: impl FnMut(&mut PgConnection, &str) -> QueryResult<T>,
+) -> QueryResult<T> {
+    let mut attempt = 1;
+    loop {
+        let key = format.generate();
+        match conn.transaction(|conn| insert(conn, &key)) {
+            Err(e)
+                if attempt < MAX_INSERT_ATTEMPTS
+                    && is_unique_violation(&e, EVENT_KEY_CONSTRAINT) =>
+            {
+                tracing::warn!("Event key {} already taken, drawing another", key);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether `slug` can be given to `event_id`: no other event uses it, and it doesn't read as
+/// another event's key (lookups ignore case).
+pub fn slug_is_free(
+    conn: &mut PgConnection,
+    slug: &str,
+    event_id: Option<Uuid>,
+) -> QueryResult<bool> {
+    let mut taken = events::table
+        .filter(
+            events::slug
+                .eq(slug)
+                .or(events::event_key.eq(slug.to_ascii_uppercase())),
+        )
+        .select(events::id)
+        .into_boxed();
+    if let Some(event_id) = event_id {
+        taken = taken.filter(events::id.ne(event_id));
+    }
+    let taken: Option<Uuid> = taken.first(conn).optional()?;
+    Ok(taken.is_none())
+}
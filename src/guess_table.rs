@@ -0,0 +1,285 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rust_xlsxwriter::{Color, Format, FormatAlign, Workbook, XlsxError};
+use uuid::Uuid;
+
+use crate::{
+    models::{Event, GraphPoint},
+    schema::{events, guesses, invitees},
+};
+
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// One guess with its invitee, as listed on the event page and in downloads.
+#[derive(Clone, Debug)]
+pub struct GuessRow {
+    pub invitee_id: Uuid,
+    pub display_name: String,
+    pub color_hex: String,
+    pub guessed_date: NaiveDateTime,
+    pub guessed_weight_kg: f64,
+    pub submitted_at: NaiveDateTime,
+}
+
+impl GuessRow {
+    pub fn to_point(&self) -> GraphPoint {
+        GraphPoint {
+            invitee_id: self.invitee_id,
+            display_name: self.display_name.clone(),
+            color_hex: self.color_hex.clone(),
+            guessed_date: self.guessed_date,
+            guessed_weight_kg: self.guessed_weight_kg,
+        }
+    }
+}
+
+/// Every guess on a visible event, oldest submission first.
+pub fn load(conn: &mut PgConnection, event_id: Uuid) -> QueryResult<Vec<GuessRow>> {
+    let rows = invitees::table
+        .inner_join(guesses::table)
+        .filter(invitees::event_id.eq(event_id))
+        .filter(diesel::dsl::exists(
+            events::table
+                .find(event_id)
+                .filter(events::deleted_at.is_null()),
+        ))
+        .order((guesses::created_at.asc(), invitees::id.asc()))
+        .select((
+            invitees::id,
+            invitees::display_name,
+            invitees::color_hex,
+            guesses::guessed_date,
+            guesses::guessed_weight_kg,
+            guesses::created_at,
+        ))
+        .load::<(Uuid, String, String, NaiveDateTime, f64, NaiveDateTime)>(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                invitee_id,
+                display_name,
+                color_hex,
+                guessed_date,
+                guessed_weight_kg,
+                submitted_at,
+            )| {
+                GuessRow {
+                    invitee_id,
+                    display_name,
+                    color_hex,
+                    guessed_date,
+                    guessed_weight_kg,
+                    submitted_at,
+                }
+            },
+        )
+        .collect())
+}
+
+/// Indexes into `rows`, closest date guess first; ties go to the earlier submission.
+pub fn closest_by_date(rows: &[GuessRow], birth_date: NaiveDateTime) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by_key(|&i| {
+        (
+            (rows[i].guessed_date - birth_date).num_days().abs(),
+            rows[i].submitted_at,
+        )
+    });
+    order
+}
+
+/// Indexes into `rows`, closest weight guess first; ties go to the earlier submission.
+pub fn closest_by_weight(rows: &[GuessRow], birth_weight_kg: f64) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|&a, &b| {
+        let diff_a = (rows[a].guessed_weight_kg - birth_weight_kg).abs();
+        let diff_b = (rows[b].guessed_weight_kg - birth_weight_kg).abs();
+        diff_a
+            .partial_cmp(&diff_b)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| rows[a].submitted_at.cmp(&rows[b].submitted_at))
+    });
+    order
+}
+
+/// Placing and distance from the answer of one guess on an ended event.
+struct Standing {
+    date_rank: usize,
+    date_delta_days: i64,
+    weight_rank: usize,
+    weight_delta_kg: f64,
+}
+
+fn standings(event: &Event, rows: &[GuessRow]) -> Option<Vec<Standing>> {
+    let (birth_date, birth_weight_kg) = (event.birth_date?, event.birth_weight_kg?);
+
+    let mut date_rank = vec![0; rows.len()];
+    for (place, i) in closest_by_date(rows, birth_date).into_iter().enumerate() {
+        date_rank[i] = place + 1;
+    }
+    let mut weight_rank = vec![0; rows.len()];
+    for (place, i) in closest_by_weight(rows, birth_weight_kg)
+        .into_iter()
+        .enumerate()
+    {
+        weight_rank[i] = place + 1;
+    }
+
+    Some(
+        rows.iter()
+            .enumerate()
+            .map(|(i, row)| Standing {
+                date_rank: date_rank[i],
+                date_delta_days: (row.guessed_date - birth_date).num_days(),
+                weight_rank: weight_rank[i],
+                weight_delta_kg: ((row.guessed_weight_kg - birth_weight_kg) * 1000.0).round()
+                    / 1000.0,
+            })
+            .collect(),
+    )
+}
+
+const HEADERS: [&str; 5] = [
+    "Name",
+    "Color",
+    "Guessed date",
+    "Guessed weight (kg)",
+    "Submitted at (UTC)",
+];
+const STANDING_HEADERS: [&str; 4] = [
+    "Date rank",
+    "Date off by (days)",
+    "Weight rank",
+    "Weight off by (kg)",
+];
+
+/// Keeps spreadsheet apps from evaluating guest-supplied text as a formula.
+fn neutralize_formula(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// The guess list as CSV; ended events get rank and delta columns.
+pub fn to_csv(event: &Event, rows: &[GuessRow]) -> Result<Vec<u8>, csv::Error> {
+    let standings = standings(event, rows);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    let mut header: Vec<&str> = HEADERS.to_vec();
+    if standings.is_some() {
+        header.extend(STANDING_HEADERS);
+    }
+    writer.write_record(&header)?;
+
+    for (i, row) in rows.iter().enumerate() {
+        let mut record = vec![
+            neutralize_formula(&row.display_name),
+            neutralize_formula(&row.color_hex),
+            row.guessed_date.format("%Y-%m-%d %H:%M").to_string(),
+            format!("{:.3}", row.guessed_weight_kg),
+            row.submitted_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ];
+        if let Some(standings) = &standings {
+            let standing = &standings[i];
+            record.extend([
+                standing.date_rank.to_string(),
+                standing.date_delta_days.to_string(),
+                standing.weight_rank.to_string(),
+                format!("{:.3}", standing.weight_delta_kg),
+            ]);
+        }
+        writer.write_record(&record)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+fn parse_color(hex: &str) -> Option<Color> {
+    let digits = hex.strip_prefix('#')?;
+    if digits.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok().map(Color::RGB)
+}
+
+/// The guess list as an Excel workbook; ended events get rank and delta columns.
+pub fn to_xlsx(event: &Event, rows: &[GuessRow]) -> Result<Vec<u8>, XlsxError> {
+    let standings = standings(event, rows);
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Guesses")?;
+
+    let bold = Format::new().set_bold();
+    let date = Format::new()
+        .set_num_format("yyyy-mm-dd hh:mm")
+        .set_align(FormatAlign::Left);
+    let weight = Format::new().set_num_format("0.000");
+
+    let mut header: Vec<&str> = HEADERS.to_vec();
+    if standings.is_some() {
+        header.extend(STANDING_HEADERS);
+    }
+    sheet.write_row_with_format(0, 0, header, &bold)?;
+    sheet.set_freeze_panes(1, 0)?;
+
+    for (i, row) in rows.iter().enumerate() {
+        let r = u32::try_from(i + 1).unwrap_or(u32::MAX);
+        // Cells are written as text, so names need no formula escaping here.
+        sheet.write_string(r, 0, &row.display_name)?;
+        match parse_color(&row.color_hex) {
+            Some(color) => sheet.write_string_with_format(
+                r,
+                1,
+                &row.color_hex,
+                &Format::new().set_background_color(color),
+            )?,
+            None => sheet.write_string(r, 1, &row.color_hex)?,
+        };
+        sheet.write_datetime_with_format(r, 2, row.guessed_date, &date)?;
+        sheet.write_number_with_format(r, 3, row.guessed_weight_kg, &weight)?;
+        sheet.write_datetime_with_format(r, 4, row.submitted_at, &date)?;
+        if let Some(standings) = &standings {
+            let standing = &standings[i];
+            sheet.write_number(r, 5, standing.date_rank as f64)?;
+            sheet.write_number(r, 6, standing.date_delta_days as f64)?;
+            sheet.write_number(r, 7, standing.weight_rank as f64)?;
+            sheet.write_number_with_format(r, 8, standing.weight_delta_kg, &weight)?;
+        }
+    }
+    sheet.autofit();
+
+    workbook.save_to_buffer()
+}
+
+/// `attachment` header value with a file name derived from the event title.
+pub fn content_disposition(event: &Event, extension: &str) -> String {
+    format!(
+        "attachment; filename=\"{}\"",
+        file_name(event, "guesses", extension)
+    )
+}
+
+/// A download's file name: the event title in lower-case ASCII, then `-{kind}.{extension}`.
+pub fn file_name(event: &Event, kind: &str, extension: &str) -> String {
+    let mut slug = String::new();
+    for ch in event.title.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.chars().take(60).collect();
+    let slug = slug.trim_end_matches('-');
+    let slug = if slug.is_empty() { "event" } else { slug };
+
+    format!("{}-{}.{}", slug, kind, extension)
+}
@@ -1,25 +1,45 @@
 use axum::{
+    body::Bytes,
     extract::{ConnectInfo, Json, Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::Html,
     response::sse::{Event as SseEvent, KeepAlive, Sse},
+    response::{AppendHeaders, IntoResponse, Response},
 };
 use diesel::prelude::*;
 use futures::stream::Stream;
 use serde::Deserialize;
-use std::net::{IpAddr, SocketAddr};
+use std::net::SocketAddr;
+use subtle::ConstantTimeEq;
 use tokio_stream::StreamExt;
-use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::{BroadcastStream, errors::BroadcastStreamRecvError};
 use uuid::Uuid;
 
 use crate::{
+    bundle,
+    captcha::{CaptchaConfig, CaptchaError},
+    chart,
+    db::{self, DbError},
+    embed,
+    event_keys::{self, SLUG_CONSTRAINT},
+    guess_table,
+    i18n::Locale,
+    metrics::SubscriberGuard,
     models::{
-        Event, EventEndedUpdate, EventWithSecret, GraphPoint, Guess, GuessDeletedUpdate,
-        GuessUpdate, Invitee, LiveUpdate, NewEvent, NewGuess, NewInvitee,
+        Event, EventEndedUpdate, EventWithSecret, GraphPoint, Guess, GuessDeletedUpdate, GuessList,
+        GuessUpdate, Invitee, LiveUpdate, NewEvent, NewGuess, NewInvitee, SealedGuess, Webhook,
+        WebhookDelivery,
     },
+    notifications,
+    og_image::{self, ShareCard},
+    pow::{self, Challenge},
+    printable, push, qr, render,
     schema::events,
     types::AppState,
-    utils::{generate_event_key, generate_secret_key},
+    utils::{effective_guess_close_date, generate_secret_key, generate_token},
+    validation::{self, Invalid},
+    viewer::{self, Access, AccessDenied, Viewer, ViewerToken, Visibility},
+    webhooks,
 };
 
 // ... (health check remains same)
@@ -28,20 +48,188 @@ pub async fn health() -> &'static str {
     "ok"
 }
 
+pub async fn health_live() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+pub async fn health_ready(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<crate::health::ReadinessReport>) {
+    let report = crate::health::readiness(state.pool.clone(), &state.tasks).await;
+    let status = match report.status {
+        crate::health::Status::Ok => StatusCode::OK,
+        crate::health::Status::Error => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report))
+}
+
+/// Serves the metrics to scrapers holding `METRICS_TOKEN`; without a token the route is off.
+pub async fn get_metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<([(axum::http::HeaderName, &'static str); 1], String), StatusCode> {
+    let token = state
+        .metrics
+        .token
+        .as_deref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !bearer_secret(&headers).is_some_and(|given| secrets_match(token, given)) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok((
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(&state.pool),
+    ))
+}
+
+/// Error body of the JSON API: a stable `code` for clients and translations, and an English
+/// `error` message.
 #[derive(serde::Serialize)]
 pub struct ApiError {
+    code: &'static str,
     error: String,
 }
 
-fn api_error(status: StatusCode, msg: impl Into<String>) -> (StatusCode, Json<ApiError>) {
+fn api_error(
+    status: StatusCode,
+    code: &'static str,
+    msg: impl Into<String>,
+) -> (StatusCode, Json<ApiError>) {
     (
         status,
         Json(ApiError {
+            code,
             error: msg.into(),
         }),
     )
 }
 
+impl From<Invalid> for (StatusCode, Json<ApiError>) {
+    fn from(e: Invalid) -> Self {
+        api_error(StatusCode::BAD_REQUEST, e.code, e.message)
+    }
+}
+
+impl From<DbError> for StatusCode {
+    fn from(e: DbError) -> Self {
+        tracing::error!("{}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+impl From<DbError> for (StatusCode, Json<ApiError>) {
+    fn from(e: DbError) -> Self {
+        tracing::error!("{}", e);
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "database_error",
+            "Database error",
+        )
+    }
+}
+
+impl From<AccessDenied> for StatusCode {
+    fn from(e: AccessDenied) -> Self {
+        match e {
+            AccessDenied::PasscodeRequired => StatusCode::UNAUTHORIZED,
+            AccessDenied::GuessesHidden | AccessDenied::GuessesSealed => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+impl From<AccessDenied> for (StatusCode, Json<ApiError>) {
+    fn from(e: AccessDenied) -> Self {
+        api_error(e.into(), e.code(), e.to_string())
+    }
+}
+
+/// Loads an event by id, or `None` if it does not exist or is scheduled for deletion.
+async fn find_event(state: &AppState, event_id: Uuid) -> Result<Option<Event>, DbError> {
+    db::run(&state.pool, move |conn| {
+        events::table
+            .find(event_id)
+            .filter(events::deleted_at.is_null())
+            .first::<Event>(conn)
+            .optional()
+    })
+    .await
+}
+
+/// Loads an event by its invite key or vanity slug, in any letter case, or `None` if it does
+/// not exist or is scheduled for deletion.
+async fn find_event_by_key(state: &AppState, key: String) -> Result<Option<Event>, DbError> {
+    let (key, slug) = event_keys::lookup_forms(&key);
+    db::run(&state.pool, move |conn| {
+        events::table
+            .filter(events::event_key.eq(key).or(events::slug.eq(slug)))
+            .filter(events::deleted_at.is_null())
+            .first::<Event>(conn)
+            .optional()
+    })
+    .await
+}
+
+async fn find_invitee(state: &AppState, invitee_id: Uuid) -> Result<Option<Invitee>, DbError> {
+    use crate::schema::invitees;
+
+    db::run(&state.pool, move |conn| {
+        invitees::table
+            .find(invitee_id)
+            .first::<Invitee>(conn)
+            .optional()
+    })
+    .await
+}
+
+/// Loads an event and checks the bearer secret against it.
+async fn find_event_as_host(
+    state: &AppState,
+    event_id: Uuid,
+    headers: &HeaderMap,
+) -> Result<Event, StatusCode> {
+    let event = find_event(state, event_id)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    check_host(&event, headers)?;
+
+    Ok(event)
+}
+
+/// Compares a presented secret in constant time, so response timing doesn't reveal how much of
+/// it was right.
+fn secrets_match(expected: &str, given: &str) -> bool {
+    expected.as_bytes().ct_eq(given.as_bytes()).into()
+}
+
+fn check_host(event: &Event, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let secret = bearer_secret(headers).ok_or(StatusCode::FORBIDDEN)?;
+    if !secrets_match(&event.secret_key, secret) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+/// What this request may see of `event`: the host's secret opens everything, and a viewer
+/// token opens a passcode-protected event.
+fn access_for(state: &AppState, event: &Event, headers: &HeaderMap, token: &ViewerToken) -> Access {
+    let viewer = if check_host(event, headers).is_ok() {
+        Viewer::Host
+    } else if token
+        .0
+        .as_deref()
+        .is_some_and(|token| state.viewer_tokens.verify(event, token))
+    {
+        Viewer::Unlocked
+    } else {
+        Viewer::Guest
+    };
+    Access::new(event, viewer, chrono::Utc::now().naive_utc())
+}
+
 fn html_escape(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     for ch in input.chars() {
@@ -64,27 +252,57 @@ fn bearer_secret(headers: &HeaderMap) -> Option<&str> {
         .and_then(|s| s.strip_prefix("Bearer "))
 }
 
-fn client_ip(headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
-    if let Some(xff) = headers
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-    {
-        if let Some(first) = xff.split(',').next().map(|s| s.trim()) {
-            if let Ok(ip) = first.parse::<IpAddr>() {
-                return ip;
-            }
+fn captcha_error(state: &AppState, err: CaptchaError) -> (StatusCode, Json<ApiError>) {
+    let reason = match err {
+        CaptchaError::Rejected => "rejected",
+        CaptchaError::Unavailable(_) => "unavailable",
+    };
+    state
+        .metrics
+        .captcha_failures
+        .with_label_values(&[state.captcha.provider(), reason])
+        .inc();
+
+    match err {
+        CaptchaError::Rejected => api_error(
+            StatusCode::BAD_REQUEST,
+            "captcha_failed",
+            "Captcha verification failed",
+        ),
+        CaptchaError::Unavailable(e) => {
+            tracing::warn!("Captcha verification unavailable: {}", e);
+            api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "captcha_unavailable",
+                "Captcha verify failed",
+            )
         }
     }
-    peer.ip()
 }
 
-fn effective_guess_close_date(event: &Event) -> Option<chrono::NaiveDateTime> {
-    if let Some(close) = event.guess_close_date {
-        return Some(close);
+/// Headers [`request_base_url`] reads when no public base URL is configured; responses built
+/// from it name them in `Vary`.
+const BASE_URL_VARY: &str = "host, x-forwarded-host, x-forwarded-proto";
+
+/// The base URL for links in responses: the configured public base URL, otherwise the request's
+/// `Host`. `X-Forwarded-Host` and `X-Forwarded-Proto` are only honoured from trusted proxies.
+fn request_base_url(state: &AppState, headers: &HeaderMap, peer: SocketAddr) -> String {
+    if let Some(base_url) = &state.config.public_base_url {
+        return base_url.clone();
     }
 
-    let due = event.due_date?;
-    chrono::NaiveTime::from_hms_opt(23, 59, 59).map(|t| due.date().and_time(t))
+    let forwarded = |name: &str| {
+        headers
+            .get(name)
+            .filter(|_| state.client_ip.is_trusted(peer.ip()))
+            .and_then(|v| v.to_str().ok())
+    };
+    let host = forwarded("x-forwarded-host")
+        .or_else(|| headers.get("host").and_then(|v| v.to_str().ok()))
+        .unwrap_or("localhost");
+    let scheme = forwarded("x-forwarded-proto").unwrap_or("http");
+
+    format!("{}://{}", scheme, host)
 }
 
 pub async fn delete_event(
@@ -92,28 +310,150 @@ pub async fn delete_event(
     Path(event_id): Path<Uuid>,
     headers: HeaderMap,
 ) -> Result<StatusCode, StatusCode> {
-    use crate::schema::events::dsl::*;
+    find_event_as_host(&state, event_id, &headers).await?;
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    db::run(&state.pool, move |conn| {
+        diesel::delete(events::table.find(event_id)).execute(conn)
+    })
+    .await?;
+    state.metrics.events_deleted.inc();
 
-    let target_event = events
-        .find(event_id)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let secret = bearer_secret(&headers).ok_or(StatusCode::FORBIDDEN)?;
-    if target_event.secret_key != secret {
-        return Err(StatusCode::FORBIDDEN);
+/// Takes back an event that retention scheduled for deletion; its retention period starts over.
+pub async fn restore_event(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Event>, StatusCode> {
+    let event = db::run(&state.pool, move |conn| {
+        events::table.find(event_id).first::<Event>(conn).optional()
+    })
+    .await?
+    .ok_or(StatusCode::NOT_FOUND)?;
+    check_host(&event, &headers)?;
+
+    if event.deleted_at.is_none() {
+        return Ok(Json(event));
     }
 
-    diesel::delete(events.filter(id.eq(event_id)))
-        .execute(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let now = chrono::Utc::now().naive_utc();
+    let event = db::run(&state.pool, move |conn| {
+        diesel::update(events::table.find(event_id))
+            .set((
+                events::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                events::restored_at.eq(Some(now)),
+            ))
+            .returning(Event::as_returning())
+            .get_result(conn)
+    })
+    .await?;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(event))
+}
+
+/// Downloads the event as a JSON bundle that `import_event` can recreate.
+pub async fn export_event(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<
+    (
+        [(axum::http::HeaderName, String); 1],
+        Json<bundle::EventBundle>,
+    ),
+    StatusCode,
+> {
+    let event = find_event_as_host(&state, event_id, &headers).await?;
+    let now = chrono::Utc::now().naive_utc();
+    let bundle = db::run(&state.pool, move |conn| {
+        bundle::EventBundle::export(conn, &event, now)
+    })
+    .await?;
+
+    Ok((
+        [(
+            axum::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"event-{}.json\"", event_id),
+        )],
+        Json(bundle),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct ImportEventRequest {
+    pub bundle: bundle::EventBundle,
+    #[serde(default, alias = "captcha_token")]
+    pub turnstile_token: String,
+    pub host_email: Option<String>,
+}
+
+/// Recreates an exported event with fresh ids and keys; guarded like `create_event`.
+pub async fn import_event(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(payload): Json<ImportEventRequest>,
+) -> Result<Json<EventWithSecret>, (StatusCode, Json<ApiError>)> {
+    let ImportEventRequest {
+        bundle,
+        turnstile_token,
+        host_email,
+    } = payload;
+
+    let host_email = host_email
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+    if let Some(email) = &host_email
+        && !notifications::is_valid_email(email)
+    {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_host_email",
+            "Invalid host_email",
+        ));
+    }
+
+    state
+        .captcha
+        .verify(
+            &turnstile_token,
+            Some(state.client_ip.resolve(&headers, peer)),
+        )
+        .await
+        .map_err(|e| captcha_error(&state, e))?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let weights = state.config.weights.clone();
+    let key_format = state.config.event_keys;
+    let event = db::run(&state.pool, move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let event = match bundle.import(conn, &weights, &key_format, now) {
+                Ok(event) => event,
+                Err(bundle::ImportError::Invalid(e)) => return Ok(Err(e)),
+                Err(bundle::ImportError::Db(e)) => return Err(e),
+            };
+            if let Some(email) = &host_email {
+                notifications::subscribe(conn, event.id, None, email, notifications::ROLE_HOST)?;
+            }
+            Ok(Ok(event))
+        })
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("{}", e);
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "import_failed",
+            "Failed to import event",
+        )
+    })??;
+
+    state.metrics.events_created.inc();
+
+    let secret_key = event.secret_key.clone();
+    Ok(Json(EventWithSecret { event, secret_key }))
 }
 
 #[derive(Deserialize)]
@@ -122,27 +462,31 @@ pub struct CreateEventRequest {
     pub description: Option<String>,
     pub due_date: Option<chrono::NaiveDateTime>,
     pub guess_close_date: Option<chrono::NaiveDateTime>,
+    #[serde(default, alias = "captcha_token")]
     pub turnstile_token: String,
     pub min_weight_kg: Option<f64>,
     pub max_weight_kg: Option<f64>,
     pub allow_guess_edits: Option<bool>,
-}
-
-#[derive(Deserialize)]
-struct TurnstileVerifyResponse {
-    success: bool,
+    pub host_email: Option<String>,
+    pub guess_pow_difficulty: Option<u8>,
+    /// Language of share pages and emails, e.g. `fi`; English when absent.
+    pub language: Option<String>,
+    /// Vanity slug usable in place of the event key, e.g. `baby-virtanen`.
+    pub slug: Option<String>,
+    /// `public` (the default), `passcode` or `hidden_guesses`.
+    pub visibility: Option<String>,
+    /// What guests enter to view a `passcode` event.
+    pub passcode: Option<String>,
+    /// Hide the guessed dates and weights from guests until guessing closes.
+    pub sealed_guesses: Option<bool>,
 }
 
 pub async fn create_event(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     State(state): State<AppState>,
     Json(payload): Json<CreateEventRequest>,
 ) -> Result<Json<EventWithSecret>, (StatusCode, Json<ApiError>)> {
-    const DEFAULT_MIN_WEIGHT_KG: f64 = 1.8;
-    const DEFAULT_MAX_WEIGHT_KG: f64 = 5.2;
-    const HARD_MIN_WEIGHT_KG: f64 = 1.0;
-    const HARD_MAX_WEIGHT_KG: f64 = 8.0;
-
-    
     let CreateEventRequest {
         title,
         description,
@@ -152,214 +496,849 @@ pub async fn create_event(
         min_weight_kg,
         max_weight_kg,
         allow_guess_edits,
+        host_email,
+        guess_pow_difficulty,
+        language,
+        slug,
+        visibility,
+        passcode,
+        sealed_guesses,
     } = payload;
 
-    if std::env::var("APP_ENV").ok().as_deref() != Some("test") {
-        let secret =
-            std::env::var("TURNSTILE_SECRET_KEY").map_err(|_| {
-                api_error(StatusCode::INTERNAL_SERVER_ERROR, "Missing TURNSTILE_SECRET_KEY")
-            })?;
-
-        let client = reqwest::Client::new();
-        let verify_result = client
-            .post("https://challenges.cloudflare.com/turnstile/v0/siteverify")
-            .form(&[
-                ("secret", secret),
-                ("response", turnstile_token.clone()),
-            ])
-            .send()
-            .await
-            .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Turnstile verify failed"))?
-            .json::<TurnstileVerifyResponse>()
-            .await
-            .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Turnstile verify failed"))?;
-
-        if !verify_result.success {
-            return Err(api_error(StatusCode::BAD_REQUEST, "Turnstile verification failed"));
-        }
+    let guess_pow_difficulty = validation::guess_pow_difficulty(guess_pow_difficulty.unwrap_or(0))?;
+    let language = validation::event_language(language.as_deref())?;
+    let slug = validation::event_slug(slug.as_deref())?;
+    let (visibility, passcode) =
+        validation::event_visibility(visibility.as_deref(), passcode.as_deref())?;
+
+    let host_email = host_email
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+    if let Some(email) = &host_email
+        && !notifications::is_valid_email(email)
+    {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_host_email",
+            "Invalid host_email",
+        ));
     }
 
+    state
+        .captcha
+        .verify(
+            &turnstile_token,
+            Some(state.client_ip.resolve(&headers, peer)),
+        )
+        .await
+        .map_err(|e| captcha_error(&state, e))?;
+
     let now = chrono::Utc::now().naive_utc();
     let today = now.date();
 
-    let due_date = due_date.ok_or_else(|| api_error(StatusCode::BAD_REQUEST, "due_date is required"))?;
-    if due_date.date() <= today {
+    let due_date = validation::event_dates(due_date, guess_close_date, today)?;
+
+    if let Some(slug) = slug.clone()
+        && !db::run(&state.pool, move |conn| {
+            event_keys::slug_is_free(conn, &slug, None)
+        })
+        .await?
+    {
         return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "due_date must be after today",
+            StatusCode::CONFLICT,
+            "slug_taken",
+            "Slug is already taken",
         ));
     }
 
-    if let Some(close_date) = guess_close_date {
-        if close_date.date() <= today {
-            return Err(api_error(
-                StatusCode::BAD_REQUEST,
-                "guess_close_date must be after today",
-            ));
-        }
+    let secret_key = generate_secret_key();
 
-        if close_date.date() > due_date.date() {
-            return Err(api_error(
-                StatusCode::BAD_REQUEST,
-                "guess_close_date must be on or before due_date",
-            ));
+    let (min_weight_kg, max_weight_kg) =
+        validation::weight_range(&state.config.weights, min_weight_kg, max_weight_kg)?;
+    let allow_guess_edits = allow_guess_edits.unwrap_or(false);
+
+    let event_secret = secret_key.clone();
+    let key_format = state.config.event_keys;
+    let event = db::run(&state.pool, move |conn| {
+        conn.transaction::<Event, diesel::result::Error, _>(|conn| {
+            let event = event_keys::insert_with_unique_key(conn, &key_format, |conn, key| {
+                diesel::insert_into(events::table)
+                    .values(&NewEvent {
+                        title: &title,
+                        description: description.as_deref(),
+                        due_date: Some(due_date),
+                        guess_close_date,
+                        event_key: key,
+                        secret_key: &event_secret,
+                        min_weight_kg,
+                        max_weight_kg,
+                        allow_guess_edits,
+                        guess_pow_difficulty: i32::from(guess_pow_difficulty),
+                        language: language.as_deref(),
+                        slug: slug.as_deref(),
+                        visibility: visibility.as_str(),
+                        passcode: passcode.as_deref(),
+                        sealed_guesses: sealed_guesses.unwrap_or(false),
+                    })
+                    .returning(Event::as_returning())
+                    .get_result(conn)
+            })?;
+
+            if let Some(email) = &host_email {
+                notifications::subscribe(conn, event.id, None, email, notifications::ROLE_HOST)?;
+            }
+
+            Ok(event)
+        })
+    })
+    .await
+    .map_err(|e| match e {
+        // Taken by another host since the check above.
+        DbError::Query(e) if event_keys::is_unique_violation(&e, SLUG_CONSTRAINT) => {
+            api_error(StatusCode::CONFLICT, "slug_taken", "Slug is already taken")
         }
-    }
+        e => {
+            tracing::error!("{}", e);
+            api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "create_event_failed",
+                "Failed to create event",
+            )
+        }
+    })?;
 
-    let event_key = generate_event_key();
-    let secret_key = generate_secret_key();
+    state.metrics.events_created.inc();
 
-    let mut min_weight_kg = min_weight_kg.unwrap_or(DEFAULT_MIN_WEIGHT_KG);
-    let mut max_weight_kg = max_weight_kg.unwrap_or(DEFAULT_MAX_WEIGHT_KG);
-    let allow_guess_edits = allow_guess_edits.unwrap_or(false);
+    // Construct response with explicit secret key
+    let response = EventWithSecret { event, secret_key };
 
-    if !min_weight_kg.is_finite() || !max_weight_kg.is_finite() {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "min_weight_kg and max_weight_kg must be finite",
-        ));
+    Ok(Json(response))
+}
+
+pub async fn get_event_guesses(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Json<GuessList>, StatusCode> {
+    // Unknown and deleted events simply have no guesses to show.
+    let Some(event) = find_event(&state, event_id_param).await? else {
+        return Ok(Json(GuessList::Revealed(Vec::new())));
+    };
+    let access = access_for(&state, &event, &headers, &token);
+    if access != Access::Sealed {
+        access.guesses()?;
     }
+    let rows = db::run(&state.pool, move |conn| {
+        guess_table::load(conn, event_id_param)
+    })
+    .await?;
+    let points = rows.iter().map(guess_table::GuessRow::to_point);
+
+    // Sealed guesses list who guessed, but not what.
+    Ok(Json(match access {
+        Access::Sealed => GuessList::Sealed(points.map(SealedGuess::from).collect()),
+        _ => GuessList::Revealed(points.collect()),
+    }))
+}
 
-    min_weight_kg = min_weight_kg.clamp(HARD_MIN_WEIGHT_KG, HARD_MAX_WEIGHT_KG);
-    max_weight_kg = max_weight_kg.clamp(HARD_MIN_WEIGHT_KG, HARD_MAX_WEIGHT_KG);
+/// Loads the event and its guesses for a download or the chart, if the request may see them.
+async fn guess_table_for(
+    state: &AppState,
+    event_id: Uuid,
+    headers: &HeaderMap,
+    token: &ViewerToken,
+) -> Result<(Event, Vec<guess_table::GuessRow>), StatusCode> {
+    let event = find_event(state, event_id)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    access_for(state, &event, headers, token).guesses()?;
+    let rows = db::run(&state.pool, move |conn| guess_table::load(conn, event_id)).await?;
+    Ok((event, rows))
+}
 
-    if max_weight_kg <= min_weight_kg {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "max_weight_kg must be greater than min_weight_kg",
-        ));
-    }
+pub async fn get_event_guesses_csv(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<([(axum::http::HeaderName, String); 2], Vec<u8>), StatusCode> {
+    let (event, rows) = guess_table_for(&state, event_id_param, &headers, &token).await?;
+    let csv = guess_table::to_csv(&event, &rows).map_err(|e| {
+        tracing::error!("Failed to write guesses CSV: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                guess_table::CSV_CONTENT_TYPE.to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                guess_table::content_disposition(&event, "csv"),
+            ),
+        ],
+        csv,
+    ))
+}
 
-    let new_event = NewEvent {
-        title: &title,
-        description: description.as_deref(),
-        due_date: Some(due_date),
-        guess_close_date,
-        event_key: &event_key,
-        secret_key: &secret_key,
-        min_weight_kg,
-        max_weight_kg,
-        allow_guess_edits,
+pub async fn get_event_guesses_xlsx(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<([(axum::http::HeaderName, String); 2], Vec<u8>), StatusCode> {
+    let (event, rows) = guess_table_for(&state, event_id_param, &headers, &token).await?;
+    let xlsx = guess_table::to_xlsx(&event, &rows).map_err(|e| {
+        tracing::error!("Failed to write guesses workbook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                guess_table::XLSX_CONTENT_TYPE.to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                guess_table::content_disposition(&event, "xlsx"),
+            ),
+        ],
+        xlsx,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct ChartQuery {
+    /// Pixel density of the PNG, 1 to 3.
+    pub scale: Option<f32>,
+    pub lang: Option<String>,
+}
+
+/// The chart labels' language: `lang` if given, otherwise negotiated for the reader.
+fn chart_locale(query: &ChartQuery, headers: &HeaderMap, event: &Event) -> Locale {
+    query
+        .lang
+        .as_deref()
+        .and_then(Locale::from_code)
+        .unwrap_or_else(|| Locale::negotiate(headers, event))
+}
+
+pub async fn get_event_chart_svg(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Query(query): Query<ChartQuery>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<([(axum::http::HeaderName, String); 3], String), StatusCode> {
+    let (event, rows) = guess_table_for(&state, event_id_param, &headers, &token).await?;
+    let locale = chart_locale(&query, &headers, &event);
+    let points: Vec<GraphPoint> = rows.iter().map(guess_table::GuessRow::to_point).collect();
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                render::SVG_CONTENT_TYPE.to_string(),
+            ),
+            (axum::http::header::CACHE_CONTROL, cache_control(&event, 60)),
+            (axum::http::header::VARY, "accept-language".to_string()),
+        ],
+        chart::to_svg(&event, &points, locale),
+    ))
+}
+
+pub async fn get_event_chart_png(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Query(query): Query<ChartQuery>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<([(axum::http::HeaderName, String); 3], Vec<u8>), StatusCode> {
+    let (event, rows) = guess_table_for(&state, event_id_param, &headers, &token).await?;
+    let locale = chart_locale(&query, &headers, &event);
+    let cache_control = cache_control(&event, 60);
+    let points: Vec<GraphPoint> = rows.iter().map(guess_table::GuessRow::to_point).collect();
+    let scale = query
+        .scale
+        .filter(|scale| scale.is_finite())
+        .unwrap_or(1.0)
+        .clamp(1.0, 3.0);
+
+    let png = tokio::task::spawn_blocking(move || chart::to_png(&event, &points, locale, scale))
+        .await
+        .map_err(|e| {
+            tracing::error!("Chart task panicked: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map_err(|e| {
+            tracing::error!("Failed to render chart: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                render::PNG_CONTENT_TYPE.to_string(),
+            ),
+            (axum::http::header::CACHE_CONTROL, cache_control),
+            (axum::http::header::VARY, "accept-language".to_string()),
+        ],
+        png,
+    ))
+}
+
+/// Shared caches may keep what anyone can see, but a passcode-protected event only in the
+/// browser that unlocked it.
+fn cache_control(event: &Event, max_age_secs: u32) -> String {
+    let scope = match Visibility::of(event) {
+        Visibility::Passcode => "private",
+        Visibility::Public | Visibility::HiddenGuesses => "public",
     };
+    format!("{}, max-age={}", scope, max_age_secs)
+}
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "DB connection failed"))?;
+/// The event's public share link under this request's base URL; a slug, when set, makes it
+/// shorter and easier to type from print.
+fn share_url(base_url: &str, event: &Event) -> String {
+    format!(
+        "{}/share/{}",
+        base_url,
+        event.slug.as_deref().unwrap_or(&event.event_key)
+    )
+}
 
-    let event = diesel::insert_into(events::table)
-        .values(&new_event)
-        .returning(Event::as_returning())
-        .get_result(&mut conn)
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create event"))?;
+/// Loads the event and reads the QR code options, for both QR code formats. The headers are
+/// the caching ones: a link taken from the request varies with the headers it came from.
+async fn qr_code_for(
+    state: &AppState,
+    event_id: Uuid,
+    query: &qr::QrQuery,
+    headers: &HeaderMap,
+    peer: SocketAddr,
+    token: &ViewerToken,
+) -> Result<
+    (String, qr::QrOptions, Vec<(axum::http::HeaderName, String)>),
+    (StatusCode, Json<ApiError>),
+> {
+    let options = qr::QrOptions::try_from(query)?;
+    let event = find_event(state, event_id)
+        .await?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "event_not_found", "Event not found"))?;
+    access_for(state, &event, headers, token).event()?;
+
+    let mut caching = vec![(
+        axum::http::header::CACHE_CONTROL,
+        cache_control(&event, 300),
+    )];
+    if state.config.public_base_url.is_none() {
+        caching.push((axum::http::header::VARY, BASE_URL_VARY.to_string()));
+    }
+    Ok((
+        share_url(&request_base_url(state, headers, peer), &event),
+        options,
+        caching,
+    ))
+}
 
-    // Construct response with explicit secret key
-    let response = EventWithSecret { event, secret_key };
+fn qr_error(e: qr::QrError) -> (StatusCode, Json<ApiError>) {
+    tracing::error!("{}", e);
+    api_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "qr_render_failed",
+        "Failed to render QR code",
+    )
+}
 
-    Ok(Json(response))
+/// QR code of the event's share link, for printed invitations.
+pub async fn get_event_qr_svg(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Query(query): Query<qr::QrQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    let (url, options, caching) =
+        qr_code_for(&state, event_id_param, &query, &headers, peer, &token).await?;
+
+    Ok((
+        [(
+            axum::http::header::CONTENT_TYPE,
+            render::SVG_CONTENT_TYPE.to_string(),
+        )],
+        AppendHeaders(caching),
+        qr::to_svg(&url, options).map_err(qr_error)?,
+    )
+        .into_response())
 }
 
-pub async fn get_event_guesses(
+pub async fn get_event_qr_png(
     State(state): State<AppState>,
     Path(event_id_param): Path<Uuid>,
-) -> Result<Json<Vec<GraphPoint>>, StatusCode> {
-    use crate::schema::{guesses, invitees};
+    Query(query): Query<qr::QrQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    let (url, options, caching) =
+        qr_code_for(&state, event_id_param, &query, &headers, peer, &token).await?;
+
+    let png = tokio::task::spawn_blocking(move || qr::to_png(&url, options))
+        .await
+        .map_err(|e| {
+            tracing::error!("QR code task panicked: {}", e);
+            api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "qr_render_failed",
+                "Failed to render QR code",
+            )
+        })?
+        .map_err(qr_error)?;
+
+    Ok((
+        [(
+            axum::http::header::CONTENT_TYPE,
+            render::PNG_CONTENT_TYPE.to_string(),
+        )],
+        AppendHeaders(caching),
+        png,
+    )
+        .into_response())
+}
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let results = invitees::table
-        .inner_join(guesses::table)
-        .filter(invitees::event_id.eq(event_id_param))
-        .select((
-            invitees::id,
-            invitees::display_name,
-            invitees::color_hex,
-            guesses::guessed_date,
-            guesses::guessed_weight_kg,
-        ))
-        .load::<(Uuid, String, String, chrono::NaiveDateTime, f64)>(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let points = results
-        .into_iter()
-        .map(|(invitee_id, name, color, date, weight)| GraphPoint {
-            invitee_id,
-            display_name: name,
-            color_hex: color,
-            guessed_date: date,
-            guessed_weight_kg: weight,
-        })
-        .collect();
+/// Loads what both printables need: the event, its guesses, the reader's language and the
+/// share link for the QR code.
+async fn printable_for(
+    state: &AppState,
+    event_id: Uuid,
+    query: &LangQuery,
+    headers: &HeaderMap,
+    peer: SocketAddr,
+    token: &ViewerToken,
+) -> Result<(Event, Vec<guess_table::GuessRow>, Locale, String), (StatusCode, Json<ApiError>)> {
+    let event = find_event(state, event_id)
+        .await?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "event_not_found", "Event not found"))?;
+    access_for(state, &event, headers, token).guesses()?;
+    let rows = db::run(&state.pool, move |conn| guess_table::load(conn, event_id)).await?;
+    let locale = query
+        .lang
+        .as_deref()
+        .and_then(Locale::from_code)
+        .unwrap_or_else(|| Locale::negotiate(headers, &event));
+    let url = share_url(&request_base_url(state, headers, peer), &event);
+    Ok((event, rows, locale, url))
+}
 
-    Ok(Json(points))
+fn pdf_response(
+    event: &Event,
+    kind: &str,
+    locale: Locale,
+    pdf: Result<Vec<u8>, printable::PdfError>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    let pdf = pdf.map_err(|e| match e {
+        printable::PdfError::NotEnded => {
+            api_error(StatusCode::CONFLICT, "event_not_ended", e.to_string())
+        }
+        printable::PdfError::Qr(_) => {
+            tracing::error!("Failed to write PDF: {}", e);
+            api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "pdf_render_failed",
+                "Failed to render PDF",
+            )
+        }
+    })?;
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                printable::PDF_CONTENT_TYPE.to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                printable::content_disposition(event, kind),
+            ),
+            (
+                axum::http::header::CONTENT_LANGUAGE,
+                locale.code().to_string(),
+            ),
+            (axum::http::header::VARY, "accept-language".to_string()),
+        ],
+        pdf,
+    )
+        .into_response())
 }
 
+/// Printable guess sheet for in-person baby showers, with blank rows while the event is open.
+pub async fn get_event_guess_sheet(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Query(query): Query<LangQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    let (event, rows, locale, url) =
+        printable_for(&state, event_id_param, &query, &headers, peer, &token).await?;
+    let pdf = printable::guess_sheet(&event, &rows, locale, &url);
+    pdf_response(&event, "guesses", locale, pdf)
+}
+
+/// Winner certificate of an ended event; `409` until the answer is set.
+pub async fn get_event_certificate(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Query(query): Query<LangQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    let (event, rows, locale, url) =
+        printable_for(&state, event_id_param, &query, &headers, peer, &token).await?;
+    let pdf = printable::certificate(&event, &rows, locale, &url);
+    pdf_response(&event, "certificate", locale, pdf)
+}
+
+/// The event behind a key; `401` for a passcode-protected event until it is unlocked.
 pub async fn get_event_by_key(
     State(state): State<AppState>,
     Path(key): Path<String>,
+    headers: HeaderMap,
+    token: ViewerToken,
 ) -> Result<Json<Event>, StatusCode> {
-    use crate::schema::events::dsl::*;
-
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let event = events
-        .filter(event_key.eq(key))
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let event = find_event_by_key(&state, key)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    access_for(&state, &event, &headers, &token).event()?;
 
     Ok(Json(event))
 }
 
-pub async fn share_event_preview(
+#[derive(Deserialize)]
+pub struct UnlockEventRequest {
+    #[serde(default)]
+    pub passcode: String,
+}
+
+/// Exchanges the passcode of a passcode-protected event for a viewer token. The host's secret
+/// works in place of the passcode, so the host's own links and downloads open too.
+pub async fn unlock_event(
     State(state): State<AppState>,
     Path(key): Path<String>,
     headers: HeaderMap,
-) -> Result<Html<String>, StatusCode> {
-    use crate::schema::events::dsl::{event_key, events};
+    Json(payload): Json<UnlockEventRequest>,
+) -> Result<Json<viewer::IssuedToken>, (StatusCode, Json<ApiError>)> {
+    let event = find_event_by_key(&state, key)
+        .await?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "event_not_found", "Event not found"))?;
+    if Visibility::of(&event) != Visibility::Passcode {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "not_passcode_protected",
+            "Event is not passcode-protected",
+        ));
+    }
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let passcode_matches = event
+        .passcode
+        .as_deref()
+        .is_some_and(|passcode| secrets_match(passcode, payload.passcode.trim()));
+    if !passcode_matches && check_host(&event, &headers).is_err() {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "wrong_passcode",
+            "Wrong passcode",
+        ));
+    }
 
-    let event = events
-        .filter(event_key.eq(&key))
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(state.viewer_tokens.issue(&event)))
+}
 
-    let host = headers
-        .get("x-forwarded-host")
-        .or_else(|| headers.get("host"))
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("localhost");
+/// The share card in the `lang` asked for, or else in the language negotiated for the reader.
+async fn share_card(
+    state: &AppState,
+    event: &Event,
+    headers: &HeaderMap,
+    lang: Option<&str>,
+) -> Result<ShareCard, StatusCode> {
+    let locale = lang
+        .and_then(Locale::from_code)
+        .unwrap_or_else(|| Locale::negotiate(headers, event));
+    let event_id = event.id;
+    let rows = db::run(&state.pool, move |conn| guess_table::load(conn, event_id)).await?;
+    Ok(ShareCard::new(event, &rows, locale))
+}
 
-    let scheme = headers
-        .get("x-forwarded-proto")
+/// Loads the event behind a key and its share card, if the request may see the event.
+async fn share_card_for(
+    state: &AppState,
+    key: String,
+    headers: &HeaderMap,
+    token: &ViewerToken,
+    lang: Option<&str>,
+) -> Result<(Event, ShareCard), StatusCode> {
+    let event = find_event_by_key(state, key)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    access_for(state, &event, headers, token).event()?;
+    let card = share_card(state, &event, headers, lang).await?;
+    Ok((event, card))
+}
+
+#[derive(Deserialize)]
+pub struct LangQuery {
+    pub lang: Option<String>,
+}
+
+/// Open Graph image for `/share/{key}`. Each event's card is rendered once per state and
+/// language and kept in memory; the ETag lets clients revalidate without downloading it again.
+pub async fn share_event_image(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<LangQuery>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, StatusCode> {
+    use axum::http::header;
+
+    let (event, card) =
+        share_card_for(&state, key, &headers, &token, query.lang.as_deref()).await?;
+    let locale = card.locale;
+    let fingerprint = card.fingerprint();
+    let etag = format!("\"{}\"", fingerprint);
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, cache_control(&event, 300)),
+    ];
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("http");
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
 
-    let base_url = format!("{}://{}", scheme, host);
-    let event_url = format!("{}/event?key={}", base_url, key);
-    let share_url = format!("{}/share/{}", base_url, key);
-    let og_image = format!("{}/vite.svg", base_url);
+    let png = match state.share_images.get(event.id, locale, &fingerprint) {
+        Some(png) => png,
+        None => {
+            let png = tokio::task::spawn_blocking(move || card.render())
+                .await
+                .map_err(|e| {
+                    tracing::error!("Share image task panicked: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .map_err(|e| {
+                    tracing::error!("Failed to render share image: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            let png = Bytes::from(png);
+            state
+                .share_images
+                .insert(event.id, locale, fingerprint, png.clone());
+            png
+        }
+    };
 
-    let og_title = html_escape(&event.title);
-    let og_description = html_escape(
-        event
-            .description
-            .as_deref()
-            .unwrap_or("Join the guessing game."),
+    Ok((
+        cache_headers,
+        [(header::CONTENT_TYPE, render::PNG_CONTENT_TYPE)],
+        png,
+    )
+        .into_response())
+}
+
+/// Read-only widget for other sites to frame; who may frame it is set per event.
+pub async fn embed_event(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<embed::EmbedQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, StatusCode> {
+    use axum::http::header;
+
+    let (event, card) =
+        share_card_for(&state, key.clone(), &headers, &token, query.lang.as_deref()).await?;
+    let event_url = format!(
+        "{}/event?key={}",
+        request_base_url(&state, &headers, peer),
+        key
+    );
+    // Only a token that checks out is handed on; issued tokens are URL-safe.
+    let viewer_token = token
+        .0
+        .as_deref()
+        .filter(|token| state.viewer_tokens.verify(&event, token));
+    let nonce = generate_token();
+    let html = embed::render(
+        &event,
+        &card,
+        (&query).into(),
+        &event_url,
+        viewer_token,
+        &nonce,
+    );
+
+    Ok((
+        [
+            (
+                header::CONTENT_SECURITY_POLICY,
+                embed::content_security_policy(&event, &nonce),
+            ),
+            (header::CACHE_CONTROL, "no-cache".to_string()),
+            (header::VARY, "accept-language".to_string()),
+        ],
+        Html(html),
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct OEmbedQuery {
+    pub url: String,
+    pub maxwidth: Option<u32>,
+    pub maxheight: Option<u32>,
+    pub format: Option<String>,
+}
+
+/// oEmbed provider for share, widget and event page URLs. Only JSON is offered; the spec
+/// asks for 501 when another format is requested.
+pub async fn oembed(
+    State(state): State<AppState>,
+    Query(query): Query<OEmbedQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Json<embed::OEmbed>, StatusCode> {
+    if query
+        .format
+        .as_deref()
+        .is_some_and(|format| format != "json")
+    {
+        return Err(StatusCode::NOT_IMPLEMENTED);
+    }
+    let key = embed::key_from_url(&query.url).ok_or(StatusCode::NOT_FOUND)?;
+    let (event, card) = share_card_for(&state, key, &headers, &token, None).await?;
+
+    let base_url = request_base_url(&state, &headers, peer);
+    let thumbnail_url = format!(
+        "{}/share/{}/og.png?v={}&lang={}",
+        base_url,
+        event.event_key,
+        card.fingerprint(),
+        card.locale.code()
     );
 
+    Ok(Json(embed::OEmbed::new(
+        &event,
+        &base_url,
+        thumbnail_url,
+        query.maxwidth,
+        query.maxheight,
+    )))
+}
+
+/// Link preview page in the reader's language (see [`Locale::negotiate`]); browsers are sent
+/// on to the event page. Passcode-protected events get a generic preview without the title
+/// or share card, since crawlers never have the passcode.
+pub async fn share_event_preview(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Response, StatusCode> {
+    let event = find_event_by_key(&state, key.clone())
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let card = match access_for(&state, &event, &headers, &token) {
+        Access::Locked => None,
+        Access::Full | Access::Sealed | Access::EventOnly => {
+            Some(share_card(&state, &event, &headers, None).await?)
+        }
+    };
+    let locale = card
+        .as_ref()
+        .map_or_else(|| Locale::negotiate(&headers, &event), |card| card.locale);
+    let lang = locale.code();
+
+    let base_url = request_base_url(&state, &headers, peer);
+    let event_url = format!("{}/event?key={}", base_url, key);
+    let share_url = share_url(&base_url, &event);
+
+    let (og_title, og_description) = match card {
+        Some(_) => (
+            html_escape(&event.title),
+            html_escape(
+                event
+                    .description
+                    .as_deref()
+                    .unwrap_or(locale.text("share.default_description")),
+            ),
+        ),
+        None => (
+            html_escape(locale.text("share.private_title")),
+            html_escape(locale.text("share.private_description")),
+        ),
+    };
+    let opening = locale.text("share.opening");
+    let redirect_hint = locale.text("share.redirect_hint");
+    let open_event = locale.text("share.open_event");
+    let noscript = locale.text("share.noscript");
+
+    let (og_width, og_height) = (og_image::WIDTH, og_image::HEIGHT);
+    let image_meta = match &card {
+        Some(card) => {
+            // Crawlers cache images by URL, so the version makes them refetch once the card
+            // changes.
+            let og_image = format!(
+                "{}/share/{}/og.png?v={}&amp;lang={}",
+                base_url,
+                key,
+                card.fingerprint(),
+                lang
+            );
+            format!(
+                r#"
+    <meta property="og:image" content="{og_image}" />
+    <meta property="og:image:type" content="image/png" />
+    <meta property="og:image:width" content="{og_width}" />
+    <meta property="og:image:height" content="{og_height}" />
+    <meta name="twitter:card" content="summary_large_image" />
+    <meta name="twitter:image" content="{og_image}" />"#
+            )
+        }
+        None => "\n    <meta name=\"twitter:card\" content=\"summary\" />".to_string(),
+    };
+    // Left out when the Host header doesn't make a valid URL, and for private events, whose
+    // widget needs the passcode too.
+    let oembed_link = reqwest::Url::parse_with_params(
+        &format!("{}/oembed", base_url),
+        [("url", share_url.as_str()), ("format", "json")],
+    )
+    .ok()
+    .filter(|_| card.is_some())
+    .map(|url| {
+        format!(
+            "\n    <link rel=\"alternate\" type=\"application/json+oembed\" href=\"{}\" title=\"{}\" />",
+            html_escape(url.as_str()),
+            og_title
+        )
+    })
+    .unwrap_or_default();
+
     let html = format!(
         r#"<!doctype html>
-<html lang="en">
+<html lang="{lang}">
   <head>
     <meta charset="utf-8" />
     <meta name="viewport" content="width=device-width, initial-scale=1" />
@@ -367,9 +1346,7 @@ pub async fn share_event_preview(
     <meta property="og:type" content="website" />
     <meta property="og:title" content="{og_title}" />
     <meta property="og:description" content="{og_description}" />
-    <meta property="og:url" content="{share_url}" />
-    <meta property="og:image" content="{og_image}" />
-    <meta name="twitter:card" content="summary" />
+    <meta property="og:url" content="{share_url}" />{image_meta}{oembed_link}
     <meta name="twitter:title" content="{og_title}" />
     <meta name="twitter:description" content="{og_description}" />
     <meta http-equiv="refresh" content="0; url={event_url}" />
@@ -379,20 +1356,27 @@ pub async fn share_event_preview(
   </head>
   <body>
     <main style="font-family: system-ui, -apple-system, Segoe UI, Roboto, Helvetica, Arial, sans-serif; padding: 24px; max-width: 720px; margin: 0 auto;">
-      <h1 style="margin: 0 0 8px; font-size: 20px;">Opening event…</h1>
-      <p style="margin: 0 0 16px; color: #444;">If you aren't redirected automatically, click the link below.</p>
+      <h1 style="margin: 0 0 8px; font-size: 20px;">{opening}</h1>
+      <p style="margin: 0 0 16px; color: #444;">{redirect_hint}</p>
       <p style="margin: 0;">
-        <a href="{event_url}" style="font-size: 16px;">Open event</a>
+        <a href="{event_url}" style="font-size: 16px;">{open_event}</a>
       </p>
       <noscript>
-        <p style="margin-top: 16px; color: #444;">JavaScript is disabled in your browser. Use the link above to open the event.</p>
+        <p style="margin-top: 16px; color: #444;">{noscript}</p>
       </noscript>
     </main>
   </body>
 </html>\n"#
     );
 
-    Ok(Html(html))
+    Ok((
+        [
+            (axum::http::header::CONTENT_LANGUAGE, lang),
+            (axum::http::header::VARY, "accept-language"),
+        ],
+        Html(html),
+    )
+        .into_response())
 }
 
 #[derive(Deserialize)]
@@ -401,59 +1385,117 @@ pub struct SubmitGuessRequest {
     pub guessed_date: chrono::NaiveDateTime,
     pub guessed_weight_kg: f64,
     pub color_hex: String,
+    /// Opt-in address for the results email.
+    pub email: Option<String>,
+    /// Required when guesses are captcha-protected.
+    pub captcha_token: Option<String>,
+    /// Solved challenge from `GET /api/events/{id}/guesses/challenge`, when the event requires one.
+    pub pow_solution: Option<String>,
 }
 
 pub async fn submit_guess(
     ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
+    token: ViewerToken,
     State(state): State<AppState>,
     Path(event_id_param): Path<Uuid>,
     Json(payload): Json<SubmitGuessRequest>,
 ) -> Result<Json<(Invitee, Guess)>, (StatusCode, Json<ApiError>)> {
     use crate::schema::{guesses, invitees};
 
-    let ip = client_ip(&headers, peer);
-    if !state.rate_limiter.allow(ip, 10.0 / 60.0, 5.0).await {
-        return Err(api_error(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded"));
-    }
+    let ip = state.client_ip.resolve(&headers, peer);
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "DB connection failed"))?;
+    if state.config.captcha.on_guess {
+        state
+            .captcha
+            .verify(
+                payload.captcha_token.as_deref().unwrap_or_default(),
+                Some(ip),
+            )
+            .await
+            .map_err(|e| captcha_error(&state, e))?;
+    }
 
     // Check if guesses are still allowed
-    let event = events::table
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+    let event = find_event(&state, event_id_param)
+        .await?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "event_not_found", "Event not found"))?;
+    access_for(&state, &event, &headers, &token).event()?;
 
     if event.ended_at.is_some() {
-        return Err(api_error(StatusCode::FORBIDDEN, "Event has ended"));
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "event_ended",
+            "Event has ended",
+        ));
     }
 
-    if !payload.guessed_weight_kg.is_finite()
-        || payload.guessed_weight_kg < event.min_weight_kg
-        || payload.guessed_weight_kg > event.max_weight_kg
-    {
-        return Err(api_error(StatusCode::BAD_REQUEST, "Invalid guessed_weight_kg"));
+    if !validation::guessed_weight_in_range(
+        payload.guessed_weight_kg,
+        event.min_weight_kg,
+        event.max_weight_kg,
+    ) {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_guessed_weight",
+            "Invalid guessed_weight_kg",
+        ));
     }
 
     let now = chrono::Utc::now().naive_utc();
     let close_date = effective_guess_close_date(&event);
 
-    if let Some(close) = close_date {
-        if now > close {
-            return Err(api_error(
-                StatusCode::FORBIDDEN,
-                "Guessing is closed for this event",
-            ));
-        }
+    if let Some(close) = close_date
+        && now > close
+    {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "guessing_closed",
+            "Guessing is closed for this event",
+        ));
+    }
+
+    let email = payload
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(str::to_string);
+    if let Some(email) = &email
+        && !notifications::is_valid_email(email)
+    {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_email",
+            "Invalid email",
+        ));
+    }
+
+    // Checked last, so a guess that would be refused anyway doesn't spend the challenge.
+    if event.guess_pow_difficulty > 0 {
+        let solution = payload.pow_solution.as_deref().ok_or_else(|| {
+            api_error(
+                StatusCode::BAD_REQUEST,
+                "proof_of_work_required",
+                "Proof of work required",
+            )
+        })?;
+        let difficulty = u8::try_from(event.guess_pow_difficulty).unwrap_or(pow::MAX_DIFFICULTY);
+        state
+            .pow
+            .verify_scoped(&event.id.to_string(), difficulty, solution)
+            .map_err(|_| {
+                api_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_proof_of_work",
+                    "Invalid proof of work",
+                )
+            })?;
     }
 
-    // 1. Save to DB
-    let (invitee, guess) = conn
-        .transaction::<(Invitee, Guess), diesel::result::Error, _>(|conn| {
+    // 1. Save to DB, queueing the webhook deliveries with the guess
+    let (invitee, guess, update) = db::run(&state.pool, move |conn| {
+        conn.transaction::<(Invitee, Guess, LiveUpdate), diesel::result::Error, _>(|conn| {
             let new_invitee = NewInvitee {
                 event_id: event_id_param,
                 display_name: &payload.display_name,
@@ -476,23 +1518,46 @@ pub async fn submit_guess(
                 .returning(Guess::as_returning())
                 .get_result(conn)?;
 
-            Ok((invitee, guess))
+            if let Some(email) = &email {
+                notifications::subscribe(
+                    conn,
+                    event_id_param,
+                    Some(invitee.id),
+                    email,
+                    notifications::ROLE_GUEST,
+                )?;
+            }
+
+            let update = LiveUpdate::Guess(GuessUpdate {
+                event_id: event_id_param,
+                guess: GraphPoint {
+                    invitee_id: invitee.id,
+                    display_name: invitee.display_name.clone(),
+                    color_hex: invitee.color_hex.clone(),
+                    guessed_date: guess.guessed_date,
+                    guessed_weight_kg: guess.guessed_weight_kg,
+                },
+                edited: false,
+            });
+            webhooks::enqueue_update(conn, &update)?;
+
+            Ok((invitee, guess, update))
         })
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save guess"))?;
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("{}", e);
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "save_guess_failed",
+            "Failed to save guess",
+        )
+    })?;
 
     // 2. Broadcast event
-    let update = GuessUpdate {
-        event_id: event_id_param,
-        guess: GraphPoint {
-            invitee_id: invitee.id,
-            display_name: invitee.display_name.clone(),
-            color_hex: invitee.color_hex.clone(),
-            guessed_date: guess.guessed_date,
-            guessed_weight_kg: guess.guessed_weight_kg,
-        },
-    };
     // We ignore errors here (e.g. if no one is listening)
-    let _ = state.tx.send(LiveUpdate::Guess(update));
+    state.metrics.guesses_submitted.inc();
+    let _ = state.tx.send(update);
 
     Ok(Json((invitee, guess)))
 }
@@ -510,49 +1575,43 @@ pub async fn update_guess(
     Path((event_id_param, invitee_id_param)): Path<(Uuid, Uuid)>,
     Json(payload): Json<UpdateGuessRequest>,
 ) -> Result<Json<GraphPoint>, StatusCode> {
-    use crate::schema::{events, guesses, invitees};
-
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    use crate::schema::{guesses, invitees};
 
-    let event = events::table
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let event = find_event(&state, event_id_param)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
     if !event.allow_guess_edits {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    if !payload.guessed_weight_kg.is_finite()
-        || payload.guessed_weight_kg < event.min_weight_kg
-        || payload.guessed_weight_kg > event.max_weight_kg
-    {
+    if !validation::guessed_weight_in_range(
+        payload.guessed_weight_kg,
+        event.min_weight_kg,
+        event.max_weight_kg,
+    ) {
         return Err(StatusCode::BAD_REQUEST);
     }
 
     let now = chrono::Utc::now().naive_utc();
     let close_date = effective_guess_close_date(&event);
-    if let Some(close) = close_date {
-        if now > close {
-            return Err(StatusCode::FORBIDDEN);
-        }
+    if let Some(close) = close_date
+        && now > close
+    {
+        return Err(StatusCode::FORBIDDEN);
     }
 
     // Ensure invitee belongs to event
-    let target_invitee = invitees::table
-        .find(invitee_id_param)
-        .first::<Invitee>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let target_invitee = find_invitee(&state, invitee_id_param)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
     if target_invitee.event_id != event_id_param {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let updated = conn
-        .transaction::<GraphPoint, diesel::result::Error, _>(|conn| {
+    let updated = db::run(&state.pool, move |conn| {
+        conn.transaction::<GraphPoint, diesel::result::Error, _>(|conn| {
             diesel::update(invitees::table.find(invitee_id_param))
                 .set((
                     invitees::display_name.eq(&payload.display_name),
@@ -574,25 +1633,33 @@ pub async fn update_guess(
                 return Err(diesel::result::Error::NotFound);
             }
 
-            Ok(GraphPoint {
+            let updated = GraphPoint {
                 invitee_id: invitee_id_param,
                 display_name: payload.display_name.clone(),
                 color_hex: payload.color_hex.clone(),
                 guessed_date: payload.guessed_date,
                 guessed_weight_kg: payload.guessed_weight_kg,
-            })
+            };
+            webhooks::enqueue_update(
+                conn,
+                &LiveUpdate::Guess(GuessUpdate {
+                    event_id: event_id_param,
+                    guess: updated.clone(),
+                    edited: true,
+                }),
+            )?;
+            Ok(updated)
         })
-        .map_err(|e| match e {
-            diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+        .optional()
+    })
+    .await?
+    .ok_or(StatusCode::NOT_FOUND)?;
 
-    let update = GuessUpdate {
+    let _ = state.tx.send(LiveUpdate::Guess(GuessUpdate {
         event_id: event_id_param,
         guess: updated.clone(),
-    };
-
-    let _ = state.tx.send(LiveUpdate::Guess(update));
+        edited: true,
+    }));
 
     Ok(Json(updated))
 }
@@ -602,44 +1669,35 @@ pub async fn delete_guess(
     Path((event_id_param, invitee_id_param)): Path<(Uuid, Uuid)>,
     headers: HeaderMap,
 ) -> Result<StatusCode, StatusCode> {
-    use crate::schema::{events, invitees};
-
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let event = events::table
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    use crate::schema::invitees;
 
+    let event = find_event_as_host(&state, event_id_param, &headers).await?;
     if event.ended_at.is_some() {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let secret = bearer_secret(&headers).ok_or(StatusCode::FORBIDDEN)?;
-    if event.secret_key != secret {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
-    let target_invitee = invitees::table
-        .find(invitee_id_param)
-        .first::<Invitee>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let target_invitee = find_invitee(&state, invitee_id_param)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
     if target_invitee.event_id != event_id_param {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    diesel::delete(invitees::table.find(invitee_id_param))
-        .execute(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let _ = state.tx.send(LiveUpdate::GuessDeleted(GuessDeletedUpdate {
+    let update = LiveUpdate::GuessDeleted(GuessDeletedUpdate {
         event_id: event_id_param,
         invitee_id: invitee_id_param,
-    }));
+    });
+    let queued = update.clone();
+    db::run(&state.pool, move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(invitees::table.find(invitee_id_param)).execute(conn)?;
+            webhooks::enqueue_update(conn, &queued)
+        })
+    })
+    .await?;
+
+    let _ = state.tx.send(update);
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -657,31 +1715,25 @@ pub async fn update_event_description(
 ) -> Result<Json<Event>, StatusCode> {
     use crate::schema::events::dsl::*;
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let target_event = events
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    find_event_as_host(&state, event_id_param, &headers).await?;
 
-    let secret = bearer_secret(&headers).ok_or(StatusCode::FORBIDDEN)?;
-    if target_event.secret_key != secret {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
-    let updated_event = diesel::update(events.find(event_id_param))
-        .set(description.eq(payload.description.clone()))
-        .returning(Event::as_returning())
-        .get_result(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (updated_event, update) = db::run(&state.pool, move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated_event = diesel::update(events.find(event_id_param))
+                .set(description.eq(payload.description))
+                .returning(Event::as_returning())
+                .get_result::<Event>(conn)?;
+            let update = LiveUpdate::EventDescription {
+                event_id: event_id_param,
+                description: updated_event.description.clone(),
+            };
+            webhooks::enqueue_update(conn, &update)?;
+            Ok((updated_event, update))
+        })
+    })
+    .await?;
 
-    let _ = state.tx.send(LiveUpdate::EventDescription {
-        event_id: event_id_param,
-        description: updated_event.description.clone(),
-    });
+    let _ = state.tx.send(update);
 
     Ok(Json(updated_event))
 }
@@ -689,6 +1741,20 @@ pub async fn update_event_description(
 #[derive(Deserialize)]
 pub struct UpdateEventSettingsRequest {
     pub allow_guess_edits: bool,
+    pub guess_pow_difficulty: Option<u8>,
+    /// Replaces the sites allowed to frame `/embed/{key}`; left unchanged when absent.
+    pub embed_origins: Option<Vec<String>>,
+    /// Sets the event's language; an empty string clears it, absent leaves it unchanged.
+    pub language: Option<String>,
+    /// Sets the vanity slug; an empty string clears it, absent leaves it unchanged.
+    pub slug: Option<String>,
+    /// `public`, `passcode` or `hidden_guesses`; absent leaves it unchanged.
+    pub visibility: Option<String>,
+    /// Sets the passcode of a `passcode` event, which revokes the viewer tokens of the old one;
+    /// absent leaves it unchanged.
+    pub passcode: Option<String>,
+    /// Seals or unseals the guesses until guessing closes; absent leaves it unchanged.
+    pub sealed_guesses: Option<bool>,
 }
 
 pub async fn update_event_settings(
@@ -699,62 +1765,93 @@ pub async fn update_event_settings(
 ) -> Result<Json<Event>, StatusCode> {
     use crate::schema::events::dsl::*;
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let target_event = find_event_as_host(&state, event_id_param, &headers).await?;
 
-    let target_event = events
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
-
-    let secret = bearer_secret(&headers).ok_or(StatusCode::FORBIDDEN)?;
-    if target_event.secret_key != secret {
-        return Err(StatusCode::FORBIDDEN);
+    let new_difficulty = match payload.guess_pow_difficulty {
+        Some(d) if d > pow::MAX_EVENT_DIFFICULTY => return Err(StatusCode::BAD_REQUEST),
+        Some(d) => i32::from(d),
+        None => target_event.guess_pow_difficulty,
+    };
+    let new_embed_origins = match &payload.embed_origins {
+        Some(origins) => validation::embed_origins(origins).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => target_event.embed_origins,
+    };
+    let new_language = match payload.language.as_deref() {
+        Some(new) => validation::event_language(Some(new)).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => target_event.language,
+    };
+    let new_slug = match payload.slug.as_deref() {
+        Some(new) => validation::event_slug(Some(new)).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => target_event.slug,
+    };
+    let (new_visibility, new_passcode) = validation::event_visibility(
+        Some(
+            payload
+                .visibility
+                .as_deref()
+                .unwrap_or(&target_event.visibility),
+        ),
+        payload
+            .passcode
+            .as_deref()
+            .or(target_event.passcode.as_deref()),
+    )
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let new_sealed_guesses = payload
+        .sealed_guesses
+        .unwrap_or(target_event.sealed_guesses);
+    if let Some(wanted) = new_slug.clone()
+        && !db::run(&state.pool, move |conn| {
+            event_keys::slug_is_free(conn, &wanted, Some(event_id_param))
+        })
+        .await?
+    {
+        return Err(StatusCode::CONFLICT);
     }
 
-    let updated_event = diesel::update(events.find(event_id_param))
-        .set(allow_guess_edits.eq(payload.allow_guess_edits))
-        .returning(Event::as_returning())
-        .get_result(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (updated_event, update) = db::run(&state.pool, move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated_event = diesel::update(events.find(event_id_param))
+                .set((
+                    allow_guess_edits.eq(payload.allow_guess_edits),
+                    guess_pow_difficulty.eq(new_difficulty),
+                    embed_origins.eq(new_embed_origins),
+                    language.eq(new_language),
+                    slug.eq(new_slug),
+                    visibility.eq(new_visibility.as_str()),
+                    passcode.eq(new_passcode),
+                    sealed_guesses.eq(new_sealed_guesses),
+                ))
+                .returning(Event::as_returning())
+                .get_result::<Event>(conn)?;
+            let update = LiveUpdate::EventSettings {
+                event_id: event_id_param,
+                allow_guess_edits: updated_event.allow_guess_edits,
+                sealed_guesses: updated_event.sealed_guesses,
+            };
+            webhooks::enqueue_update(conn, &update)?;
+            Ok((updated_event, update))
+        })
+    })
+    .await
+    .map_err(|e| match e {
+        DbError::Query(e) if event_keys::is_unique_violation(&e, SLUG_CONSTRAINT) => {
+            StatusCode::CONFLICT
+        }
+        e => e.into(),
+    })?;
 
-    let _ = state.tx.send(LiveUpdate::EventSettings {
-        event_id: event_id_param,
-        allow_guess_edits: updated_event.allow_guess_edits,
-    });
+    let _ = state.tx.send(update);
 
     Ok(Json(updated_event))
 }
 
 pub async fn claim_event(
-    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     State(state): State<AppState>,
     Path(event_id_param): Path<Uuid>,
 ) -> Result<Json<Event>, StatusCode> {
-    use crate::schema::events::dsl::*;
-
-    let ip = client_ip(&headers, peer);
-    if !state.rate_limiter.allow(ip, 6.0 / 60.0, 3.0).await {
-        return Err(StatusCode::TOO_MANY_REQUESTS);
-    }
-
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let target_event = events
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
-
-    let secret = bearer_secret(&headers).ok_or(StatusCode::FORBIDDEN)?;
-    if target_event.secret_key != secret {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    let target_event = find_event_as_host(&state, event_id_param, &headers).await?;
 
     Ok(Json(target_event))
 }
@@ -768,180 +1865,493 @@ pub struct SetEventAnswerRequest {
 pub async fn set_event_answer(
     State(state): State<AppState>,
     Path(event_id_param): Path<Uuid>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(payload): Json<SetEventAnswerRequest>,
 ) -> Result<Json<EventEndedUpdate>, StatusCode> {
-    use crate::schema::{events, guesses, invitees};
-
     if !payload.birth_weight_kg.is_finite() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
     let now = chrono::Utc::now().naive_utc();
 
-    let target_event = events::table
-        .find(event_id_param)
-        .first::<Event>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
-
-    let secret = bearer_secret(&headers).ok_or(StatusCode::FORBIDDEN)?;
-    if target_event.secret_key != secret {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
+    let target_event = find_event_as_host(&state, event_id_param, &headers).await?;
     if target_event.ended_at.is_some() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let updated_event = diesel::update(events::table.find(event_id_param))
-        .set((
-            events::birth_date.eq(Some(payload.birth_date)),
-            events::birth_weight_kg.eq(Some(payload.birth_weight_kg)),
-            events::ended_at.eq(Some(now)),
-            events::allow_guess_edits.eq(false),
-        ))
-        .returning(Event::as_returning())
-        .get_result::<Event>(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let guess_rows = invitees::table
-        .inner_join(guesses::table)
-        .filter(invitees::event_id.eq(event_id_param))
-        .select((
-            invitees::id,
-            invitees::display_name,
-            invitees::color_hex,
-            guesses::guessed_date,
-            guesses::guessed_weight_kg,
-            guesses::created_at,
-        ))
-        .load::<(Uuid, String, String, chrono::NaiveDateTime, f64, chrono::NaiveDateTime)>(
-            &mut conn,
-        )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (updated_event, update) = db::run(&state.pool, move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated_event = diesel::update(events::table.find(event_id_param))
+                .set((
+                    events::birth_date.eq(Some(payload.birth_date)),
+                    events::birth_weight_kg.eq(Some(payload.birth_weight_kg)),
+                    events::ended_at.eq(Some(now)),
+                    events::allow_guess_edits.eq(false),
+                ))
+                .returning(Event::as_returning())
+                .get_result::<Event>(conn)?;
+
+            let guess_rows = guess_table::load(conn, event_id_param)?;
+            let top = |order: Vec<usize>| -> Vec<GraphPoint> {
+                order
+                    .into_iter()
+                    .take(5)
+                    .map(|i| guess_rows[i].to_point())
+                    .collect()
+            };
 
-    let mut date_sorted: Vec<(GraphPoint, i64, chrono::NaiveDateTime)> = guess_rows
-        .iter()
-        .map(|(invitee_id, name, color, date, weight, created)| {
-            let date_diff_days = (*date - payload.birth_date).num_days().abs();
-            (
-                GraphPoint {
-                    invitee_id: *invitee_id,
-                    display_name: name.clone(),
-                    color_hex: color.clone(),
-                    guessed_date: *date,
-                    guessed_weight_kg: *weight,
-                },
-                date_diff_days,
-                *created,
-            )
-        })
-        .collect();
+            let update = EventEndedUpdate {
+                event_id: event_id_param,
+                birth_date: payload.birth_date,
+                birth_weight_kg: payload.birth_weight_kg,
+                ended_at: updated_event.ended_at.unwrap_or(now),
+                closest_date_top: top(guess_table::closest_by_date(
+                    &guess_rows,
+                    payload.birth_date,
+                )),
+                closest_weight_top: top(guess_table::closest_by_weight(
+                    &guess_rows,
+                    payload.birth_weight_kg,
+                )),
+            };
+            webhooks::enqueue_update(conn, &LiveUpdate::EventEnded(update.clone()))?;
 
-    date_sorted.sort_by(|(_, days_a, created_a), (_, days_b, created_b)| {
-        days_a.cmp(days_b).then_with(|| created_a.cmp(created_b))
-    });
+            Ok((updated_event, update))
+        })
+    })
+    .await?;
 
-    let closest_date_top: Vec<GraphPoint> = date_sorted
-        .into_iter()
-        .take(5)
-        .map(|(gp, _, _)| gp)
-        .collect();
+    state.metrics.events_ended.inc();
+    let _ = state.tx.send(LiveUpdate::EventEnded(update.clone()));
 
-    let mut weight_sorted: Vec<(GraphPoint, f64, chrono::NaiveDateTime)> = guess_rows
-        .iter()
-        .map(|(invitee_id, name, color, date, weight, created)| {
-            let diff = (*weight - payload.birth_weight_kg).abs();
-            (
-                GraphPoint {
-                    invitee_id: *invitee_id,
-                    display_name: name.clone(),
-                    color_hex: color.clone(),
-                    guessed_date: *date,
-                    guessed_weight_kg: *weight,
-                },
-                diff,
-                *created,
-            )
-        })
-        .collect();
+    let base_url = request_base_url(&state, &headers, peer);
+    let ended = update.clone();
+    if let Err(e) = db::run(&state.pool, move |conn| {
+        notifications::enqueue_results(conn, &updated_event, &ended, &base_url)
+    })
+    .await
+    {
+        tracing::error!(
+            "Failed to queue results emails for {}: {}",
+            event_id_param,
+            e
+        );
+    }
 
-    weight_sorted.sort_by(|(_, diff_a, created_a), (_, diff_b, created_b)| {
-        diff_a
-            .partial_cmp(diff_b)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then_with(|| created_a.cmp(created_b))
-    });
+    Ok(Json(update))
+}
 
-    let closest_weight_top: Vec<GraphPoint> = weight_sorted
-        .into_iter()
-        .take(5)
-        .map(|(gp, _, _)| gp)
-        .collect();
+/// Asks before unsubscribing: link scanners and prefetchers follow GET links in emails, so
+/// only the form's POST (or a mail client's one-click POST) unsubscribes.
+pub async fn confirm_unsubscribe(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Html<&'static str>, StatusCode> {
+    let found = db::run(&state.pool, move |conn| {
+        notifications::subscription_exists(conn, &token)
+    })
+    .await?;
+    if !found {
+        return Err(StatusCode::NOT_FOUND);
+    }
 
-    let update = EventEndedUpdate {
-        event_id: event_id_param,
-        birth_date: payload.birth_date,
-        birth_weight_kg: payload.birth_weight_kg,
-        ended_at: updated_event.ended_at.unwrap_or(now),
-        closest_date_top,
-        closest_weight_top,
-    };
+    Ok(Html(
+        "<!doctype html>\n<html lang=\"en\"><head><meta charset=\"utf-8\" /><title>Unsubscribe</title></head>\
+         <body><form method=\"post\"><p>Stop receiving these emails?</p>\
+         <button type=\"submit\">Unsubscribe</button></form></body></html>\n",
+    ))
+}
 
-    let _ = state.tx.send(LiveUpdate::EventEnded(update.clone()));
+pub async fn unsubscribe_notifications(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Html<&'static str>, StatusCode> {
+    let found = db::run(&state.pool, move |conn| {
+        notifications::unsubscribe(conn, &token)
+    })
+    .await?;
+    if !found {
+        return Err(StatusCode::NOT_FOUND);
+    }
 
-    Ok(Json(update))
+    Ok(Html(
+        "<!doctype html>\n<html lang=\"en\"><head><meta charset=\"utf-8\" /><title>Unsubscribed</title></head>\
+         <body><p>You have been unsubscribed from these emails.</p></body></html>\n",
+    ))
 }
 
+/// How long live clients wait before reconnecting after a `server_restarting` message.
+const RESTART_RECONNECT_MS: u64 = 5_000;
+
 #[derive(Deserialize)]
 pub struct SseSubscribeQuery {
     pub event_key: String,
 }
 
+/// Live updates of one event. Passcode-protected events need a viewer token (`?token=`).
+/// Until guessing closes, guesses of a hidden-guesses event are left out and guesses of a
+/// sealed event only announce who guessed or changed their guess.
+///
+/// Access is decided when the stream opens, so a guest's stream ends after an
+/// `event_settings` update: the client reconnects and is checked against the new settings.
 pub async fn sse_subscribe(
     Query(params): Query<SseSubscribeQuery>,
     State(state): State<AppState>,
+    headers: HeaderMap,
+    token: ViewerToken,
 ) -> Result<Sse<impl Stream<Item = Result<SseEvent, axum::Error>>>, StatusCode> {
-    use crate::schema::events::dsl::*;
-
-    let mut conn = state
-        .pool
-        .get()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let event_id_param = events
-        .filter(event_key.eq(params.event_key))
-        .select(id)
-        .first::<Uuid>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let event = find_event_by_key(&state, params.event_key)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let access = access_for(&state, &event, &headers, &token);
+    access.event()?;
+    let is_host = check_host(&event, &headers).is_ok();
+    let event_id_param = event.id;
 
     let rx = state.tx.subscribe();
+    let subscriber = SubscriberGuard::new(state.metrics.clone());
 
+    // The event may end while the stream is open, which `event` doesn't show.
+    let mut ended = false;
     let stream = BroadcastStream::new(rx).filter_map(move |result| match result {
         Ok(update) => {
-            let matches_event = match &update {
-                LiveUpdate::Guess(g) => g.event_id == event_id_param,
-                LiveUpdate::GuessDeleted(g) => g.event_id == event_id_param,
-                LiveUpdate::EventSettings { event_id, .. } => *event_id == event_id_param,
-                LiveUpdate::EventDescription { event_id, .. } => *event_id == event_id_param,
-                LiveUpdate::EventEnded(e) => e.event_id == event_id_param,
-            };
-
-            if !matches_event {
+            if update.event_id() != event_id_param {
                 return None;
             }
+            ended |= matches!(
+                update,
+                LiveUpdate::EventEnded(_) | LiveUpdate::GuessesRevealed { .. }
+            );
+            let revealed =
+                ended || viewer::guesses_revealed(&event, chrono::Utc::now().naive_utc());
+            let is_guess = matches!(update, LiveUpdate::Guess(_) | LiveUpdate::GuessDeleted(_));
+            let update = match (access, update) {
+                (Access::EventOnly | Access::Sealed, update) if revealed => update,
+                (Access::EventOnly, _) if is_guess => return None,
+                (Access::Sealed, LiveUpdate::Guess(update)) if update.edited => {
+                    LiveUpdate::GuessSealedEdited {
+                        event_id: event_id_param,
+                        guess: update.guess.into(),
+                    }
+                }
+                (Access::Sealed, LiveUpdate::Guess(_)) => LiveUpdate::GuessSealed {
+                    event_id: event_id_param,
+                },
+                (_, update) => update,
+            };
+            let recheck = !is_host && matches!(update, LiveUpdate::EventSettings { .. });
 
             serde_json::to_string(&update)
                 .ok()
-                .map(|json| Ok(SseEvent::default().data(json)))
+                .map(|json| (Ok(SseEvent::default().data(json)), recheck))
         }
-        Err(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+            subscriber.metrics().sse_lagged.inc_by(skipped);
+            None
+        }
+    });
+    // A `None` marks where the stream ends for a new access check.
+    let stream = futures::StreamExt::flat_map(stream, |(message, recheck)| {
+        futures::stream::iter([Some(message)].into_iter().chain(recheck.then_some(None)))
     });
 
+    // On shutdown, tell the client to reconnect and end the stream so the server can drain.
+    let shutdown = state.shutdown.clone();
+    let restarting = futures::stream::once(async {
+        let message = serde_json::json!({
+            "type": "server_restarting",
+            "data": { "reconnect_after_ms": RESTART_RECONNECT_MS },
+        });
+        Some(Ok(SseEvent::default()
+            .retry(std::time::Duration::from_millis(RESTART_RECONNECT_MS))
+            .data(message.to_string())))
+    });
+    let stream = futures::StreamExt::take_until(stream, async move { shutdown.wait().await })
+        .chain(restarting);
+    let stream =
+        futures::StreamExt::take_while(stream, |message| futures::future::ready(message.is_some()));
+    let stream = futures::StreamExt::filter_map(stream, futures::future::ready);
+
     Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+}
+
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<Json<Webhook>, (StatusCode, Json<ApiError>)> {
+    find_event_as_host(&state, event_id_param, &headers)
+        .await
+        .map_err(|status| match status {
+            StatusCode::NOT_FOUND => api_error(status, "event_not_found", "Event not found"),
+            StatusCode::FORBIDDEN => api_error(status, "forbidden", "Forbidden"),
+            _ => api_error(status, "create_webhook_failed", "Failed to create webhook"),
+        })?;
+
+    let url = payload.url.trim().to_string();
+    let allow_private = state.config.webhook_allow_private_targets;
+    let registered = match webhooks::check_target(&url, allow_private).await {
+        Ok(()) => {
+            db::run(&state.pool, move |conn| {
+                Ok(webhooks::register(conn, event_id_param, &url))
+            })
+            .await?
+        }
+        Err(e) => Err(e),
+    };
+    let webhook = registered.map_err(|e| match e {
+        webhooks::RegisterError::InvalidUrl => api_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_webhook_url",
+            "url must be an absolute http(s) URL",
+        ),
+        webhooks::RegisterError::PrivateTarget => api_error(
+            StatusCode::BAD_REQUEST,
+            "private_webhook_target",
+            "url must point to a public address",
+        ),
+        webhooks::RegisterError::TooMany => api_error(
+            StatusCode::BAD_REQUEST,
+            "too_many_webhooks",
+            "Too many webhooks for this event",
+        ),
+        webhooks::RegisterError::Db(_) => api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "create_webhook_failed",
+            "Failed to create webhook",
+        ),
+    })?;
+
+    Ok(Json(webhook))
+}
+
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Webhook>>, StatusCode> {
+    use crate::schema::webhooks;
+
+    find_event_as_host(&state, event_id_param, &headers).await?;
+
+    let hooks = db::run(&state.pool, move |conn| {
+        webhooks::table
+            .filter(webhooks::event_id.eq(event_id_param))
+            .order(webhooks::created_at.asc())
+            .select(Webhook::as_select())
+            .load(conn)
+    })
+    .await?;
+
+    Ok(Json(hooks))
+}
+
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path((event_id_param, webhook_id_param)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    use crate::schema::webhooks;
+
+    find_event_as_host(&state, event_id_param, &headers).await?;
+
+    let deleted = db::run(&state.pool, move |conn| {
+        diesel::delete(
+            webhooks::table
+                .filter(webhooks::id.eq(webhook_id_param))
+                .filter(webhooks::event_id.eq(event_id_param)),
+        )
+        .execute(conn)
+    })
+    .await?;
+
+    if deleted == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Path((event_id_param, webhook_id_param)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<WebhookDelivery>>, StatusCode> {
+    use crate::schema::{webhook_deliveries, webhooks};
+
+    find_event_as_host(&state, event_id_param, &headers).await?;
+
+    let deliveries = db::run(&state.pool, move |conn| {
+        let webhook = webhooks::table
+            .find(webhook_id_param)
+            .select(Webhook::as_select())
+            .first(conn)
+            .optional()?;
+
+        match webhook {
+            Some(webhook) if webhook.event_id == event_id_param => webhook_deliveries::table
+                .filter(webhook_deliveries::webhook_id.eq(webhook_id_param))
+                .order(webhook_deliveries::created_at.desc())
+                .limit(100)
+                .select(WebhookDelivery::as_select())
+                .load(conn)
+                .map(Some),
+            _ => Ok(None),
+        }
+    })
+    .await?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(deliveries))
+}
+
+#[derive(serde::Serialize)]
+pub struct VapidPublicKeyResponse {
+    pub public_key: String,
+}
+
+pub async fn get_vapid_public_key(
+    State(state): State<AppState>,
+) -> Result<Json<VapidPublicKeyResponse>, StatusCode> {
+    let client = state.push.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(VapidPublicKeyResponse {
+        public_key: client.public_key(),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Mirrors the browser's `PushSubscription.toJSON()` shape.
+#[derive(Deserialize)]
+pub struct SubscribePushRequest {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+pub async fn subscribe_push(
+    headers: HeaderMap,
+    token: ViewerToken,
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Json(payload): Json<SubscribePushRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    if !push::is_valid_subscription(&payload.endpoint, &payload.keys.p256dh, &payload.keys.auth) {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_push_subscription",
+            "Invalid push subscription",
+        ));
+    }
+
+    let event = find_event(&state, event_id_param)
+        .await?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "event_not_found", "Event not found"))?;
+    access_for(&state, &event, &headers, &token).event()?;
+
+    let allow_private = state.config.webhook_allow_private_targets;
+    match webhooks::check_target(&payload.endpoint, allow_private).await {
+        Ok(()) => {}
+        Err(webhooks::RegisterError::PrivateTarget) => {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                "private_push_endpoint",
+                "endpoint must point to a public address",
+            ));
+        }
+        Err(_) => {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_push_subscription",
+                "Invalid push subscription",
+            ));
+        }
+    }
+
+    if event.ended_at.is_some() {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "event_ended",
+            "Event has ended",
+        ));
+    }
+
+    db::run(&state.pool, move |conn| {
+        push::subscribe(
+            conn,
+            event_id_param,
+            &payload.endpoint,
+            &payload.keys.p256dh,
+            &payload.keys.auth,
+        )
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("{}", e);
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "save_subscription_failed",
+            "Failed to save subscription",
+        )
+    })?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[derive(Deserialize)]
+pub struct UnsubscribePushRequest {
+    pub endpoint: String,
+}
+
+pub async fn unsubscribe_push(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    Json(payload): Json<UnsubscribePushRequest>,
+) -> Result<StatusCode, StatusCode> {
+    db::run(&state.pool, move |conn| {
+        push::unsubscribe(conn, event_id_param, &payload.endpoint)
+    })
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_captcha_config(State(state): State<AppState>) -> Json<CaptchaConfig> {
+    Json(CaptchaConfig {
+        provider: state.captcha.provider(),
+        site_key: state.config.captcha.site_key.clone(),
+        on_guess: state.config.captcha.on_guess,
+    })
+}
+
+pub async fn get_pow_challenge(State(state): State<AppState>) -> Json<Challenge> {
+    Json(state.pow.issue())
+}
+
+/// Issues a proof-of-work challenge bound to one event, at that event's difficulty.
+pub async fn get_guess_challenge(
+    State(state): State<AppState>,
+    Path(event_id_param): Path<Uuid>,
+    headers: HeaderMap,
+    token: ViewerToken,
+) -> Result<Json<Challenge>, StatusCode> {
+    let event = find_event(&state, event_id_param)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    access_for(&state, &event, &headers, &token).event()?;
+
+    let difficulty = u8::try_from(event.guess_pow_difficulty).unwrap_or(pow::MAX_DIFFICULTY);
+    Ok(Json(
+        state.pow.issue_scoped(&event.id.to_string(), difficulty),
+    ))
+}
@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::types::DbPool;
+
+/// Readiness must answer quickly even when the pool is exhausted or the database is down.
+const POOL_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Error,
+}
+
+struct TaskState {
+    max_silence: Duration,
+    /// Last beat, or the registration time before the first beat.
+    last_beat: Instant,
+    last_beat_at: Option<DateTime<Utc>>,
+    stopped: bool,
+}
+
+/// Liveness registry for background loops. Each loop holds a [`Heartbeat`] and beats after
+/// every iteration; readiness fails when a loop stops or stays silent for too long.
+#[derive(Default)]
+pub struct BackgroundTasks {
+    tasks: Mutex<BTreeMap<&'static str, TaskState>>,
+}
+
+impl BackgroundTasks {
+    /// Registers a loop expected to beat at least every `max_silence`.
+    /// The first beat is expected within `max_silence` as well.
+    pub fn register(self: &Arc<Self>, name: &'static str, max_silence: Duration) -> Heartbeat {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner()).insert(
+            name,
+            TaskState {
+                max_silence,
+                last_beat: Instant::now(),
+                last_beat_at: None,
+                stopped: false,
+            },
+        );
+        Heartbeat {
+            tasks: self.clone(),
+            name,
+        }
+    }
+
+    fn report(&self) -> ComponentReport {
+        let now = Instant::now();
+        let tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+
+        let tasks: BTreeMap<&'static str, TaskReport> = tasks
+            .iter()
+            .map(|(name, task)| {
+                let healthy =
+                    !task.stopped && now.duration_since(task.last_beat) <= task.max_silence;
+                let report = TaskReport {
+                    status: if healthy { Status::Ok } else { Status::Error },
+                    last_beat_at: task.last_beat_at,
+                    stopped: task.stopped,
+                };
+                (*name, report)
+            })
+            .collect();
+
+        let status = if tasks.values().all(|t| t.status == Status::Ok) {
+            Status::Ok
+        } else {
+            Status::Error
+        };
+        ComponentReport {
+            status,
+            duration_ms: 0,
+            error: None,
+            tasks: Some(tasks),
+        }
+    }
+}
+
+/// Handle held by a background loop; dropping it marks the loop as stopped.
+pub struct Heartbeat {
+    tasks: Arc<BackgroundTasks>,
+    name: &'static str,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        let mut tasks = self.tasks.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(task) = tasks.get_mut(self.name) {
+            task.last_beat = Instant::now();
+            task.last_beat_at = Some(Utc::now());
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        let mut tasks = self.tasks.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(task) = tasks.get_mut(self.name) {
+            task.stopped = true;
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TaskReport {
+    pub status: Status,
+    pub last_beat_at: Option<DateTime<Utc>>,
+    pub stopped: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ComponentReport {
+    pub status: Status,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<BTreeMap<&'static str, TaskReport>>,
+}
+
+impl ComponentReport {
+    fn from_result(result: Result<(), String>, started: Instant) -> Self {
+        Self {
+            status: if result.is_ok() {
+                Status::Ok
+            } else {
+                Status::Error
+            },
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.err(),
+            tasks: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReadinessReport {
+    pub status: Status,
+    pub duration_ms: u64,
+    pub checks: BTreeMap<&'static str, ComponentReport>,
+}
+
+fn check_database(pool: &DbPool) -> (ComponentReport, ComponentReport) {
+    let started = Instant::now();
+    let mut conn = match pool.get_timeout(POOL_TIMEOUT) {
+        Ok(conn) => conn,
+        Err(e) => {
+            let error = format!("Failed to get connection: {}", e);
+            return (
+                ComponentReport::from_result(Err(error.clone()), started),
+                ComponentReport::from_result(Err(error), started),
+            );
+        }
+    };
+    let database = ComponentReport::from_result(
+        diesel::sql_query("SELECT 1")
+            .execute(&mut conn)
+            .map(|_| ())
+            .map_err(|e| format!("Diesel error: {}", e)),
+        started,
+    );
+
+    let started = Instant::now();
+    let migrations = ComponentReport::from_result(
+        match conn.has_pending_migration(crate::MIGRATIONS) {
+            Ok(false) => Ok(()),
+            Ok(true) => Err("Pending migrations".to_string()),
+            Err(e) => Err(format!("Failed to check migrations: {}", e)),
+        },
+        started,
+    );
+
+    (database, migrations)
+}
+
+/// Runs every readiness check: a database round trip, pending migrations and background tasks.
+pub async fn readiness(pool: DbPool, tasks: &BackgroundTasks) -> ReadinessReport {
+    let started = Instant::now();
+
+    let (database, migrations) = match tokio::task::spawn_blocking(move || check_database(&pool))
+        .await
+    {
+        Ok(reports) => reports,
+        Err(e) => {
+            let error = format!("Health check task panicked: {}", e);
+            (
+                ComponentReport::from_result(Err(error.clone()), started),
+                ComponentReport::from_result(Err(error), started),
+            )
+        }
+    };
+
+    let mut checks = BTreeMap::new();
+    checks.insert("database", database);
+    checks.insert("migrations", migrations);
+    checks.insert("background_tasks", tasks.report());
+
+    let status = if checks.values().all(|c| c.status == Status::Ok) {
+        Status::Ok
+    } else {
+        Status::Error
+    };
+    ReadinessReport {
+        status,
+        duration_ms: started.elapsed().as_millis() as u64,
+        checks,
+    }
+}
@@ -0,0 +1,317 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    http::{HeaderMap, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use chrono::NaiveDateTime;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::models::Event;
+
+/// Error bodies are a few hundred bytes; anything larger is passed through untouched.
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+const ERRORS_PREFIX: &str = "errors.";
+
+/// A language the server has a catalog for. English is the fallback for everything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    Fi,
+}
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: LazyLock<HashMap<Locale, Catalog>> = LazyLock::new(|| {
+    Locale::ALL
+        .into_iter()
+        .map(|locale| (locale, parse_catalog(locale.source())))
+        .collect()
+});
+
+/// Flattens nested TOML tables into dotted keys (`email.results.subject`).
+fn parse_catalog(source: &str) -> Catalog {
+    fn flatten(prefix: &str, table: toml::Table, out: &mut Catalog) {
+        for (key, value) in table {
+            let key = format!("{}{}", prefix, key);
+            match value {
+                toml::Value::String(text) => {
+                    out.insert(key, text);
+                }
+                toml::Value::Table(table) => flatten(&format!("{}.", key), table, out),
+                other => panic!("catalog entry {} must be a string, got {:?}", key, other),
+            }
+        }
+    }
+
+    let table = source
+        .parse::<toml::Table>()
+        .unwrap_or_else(|e| panic!("invalid translation catalog: {}", e));
+    let mut catalog = Catalog::new();
+    flatten("", table, &mut catalog);
+    catalog
+}
+
+/// Replaces `{name}` placeholders with their values.
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{}}}", name), value);
+    }
+    out
+}
+
+/// Matches `message` against an English template such as `Invalid embed origin {origin}` and
+/// returns the placeholder values.
+fn match_template<'a>(template: &'a str, message: &str) -> Option<Vec<(&'a str, String)>> {
+    let mut values = Vec::new();
+    let mut rest = message;
+    let mut template = template;
+
+    let mut pending: Option<&str> = None;
+    loop {
+        let (literal, after) = match template.find('{') {
+            Some(start) => (&template[..start], Some(&template[start..])),
+            None => (template, None),
+        };
+
+        match pending.take() {
+            None => rest = rest.strip_prefix(literal)?,
+            // The value runs to the first occurrence of the next literal, or to the end.
+            Some(name) if literal.is_empty() && after.is_none() => {
+                values.push((name, rest.to_string()));
+                rest = "";
+            }
+            Some(name) => {
+                let end = if after.is_none() {
+                    rest.strip_suffix(literal).map(str::len)?
+                } else {
+                    rest.find(literal)?
+                };
+                values.push((name, rest[..end].to_string()));
+                rest = &rest[end + literal.len()..];
+            }
+        }
+
+        let Some(after) = after else { break };
+        let close = after.find('}')?;
+        pending = Some(&after[1..close]);
+        template = &after[close + 1..];
+    }
+
+    rest.is_empty().then_some(values)
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Fi];
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::En => include_str!("../assets/locales/en.toml"),
+            Locale::Fi => include_str!("../assets/locales/fi.toml"),
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fi => "fi",
+        }
+    }
+
+    /// Accepts a language tag (`fi`, `fi-FI`, `EN_us`) and goes by its primary subtag.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let primary = code.trim().split(['-', '_']).next()?;
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(primary))
+    }
+
+    /// The most preferred supported language of an `Accept-Language` header.
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut ranges: Vec<(f32, Locale)> = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let locale = Locale::from_code(parts.next()?)?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+        // Stable, so equally preferred languages keep the header's order.
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranges.first().map(|(_, locale)| *locale)
+    }
+
+    /// The event's own language, for output without a reader to ask (emails, cached images).
+    pub fn for_event(event: &Event) -> Self {
+        event
+            .language
+            .as_deref()
+            .and_then(Locale::from_code)
+            .unwrap_or_default()
+    }
+
+    /// The reader's `Accept-Language` when it names a supported language, otherwise the
+    /// event's language, otherwise English.
+    pub fn negotiate(headers: &HeaderMap, event: &Event) -> Self {
+        headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(Locale::from_accept_language)
+            .unwrap_or_else(|| Locale::for_event(event))
+    }
+
+    /// The catalog entry for `key`, falling back to English and then to the key itself.
+    pub fn text(self, key: &str) -> &str {
+        CATALOGS
+            .get(&self)
+            .and_then(|catalog| catalog.get(key))
+            .or_else(|| CATALOGS.get(&Locale::En)?.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// [`Locale::text`] with `{name}` placeholders filled in.
+    pub fn format(self, key: &str, args: &[(&str, &str)]) -> String {
+        fill(self.text(key), args)
+    }
+
+    pub fn long_date(self, date: NaiveDateTime) -> String {
+        date.format(self.text("long_date")).to_string()
+    }
+
+    pub fn date(self, date: NaiveDateTime) -> String {
+        date.format(self.text("date")).to_string()
+    }
+
+    pub fn datetime(self, date: NaiveDateTime) -> String {
+        date.format(self.text("datetime")).to_string()
+    }
+
+    /// A weight with two decimals and the locale's separator, e.g. `3,25 kg`.
+    pub fn weight(self, kg: f64) -> String {
+        let number = format!("{:.2}", kg).replace('.', self.text("decimal_separator"));
+        self.format("weight", &[("weight", &number)])
+    }
+
+    pub fn guesses(self, count: usize) -> String {
+        match count {
+            1 => self.text("guesses.one").to_string(),
+            n => self.format("guesses.other", &[("count", &n.to_string())]),
+        }
+    }
+
+    /// Translates the API error `code`. Placeholders are filled with the values of the English
+    /// `message`, matched against the English template. `None` when the code is unknown or the
+    /// message doesn't fit its template.
+    pub fn translate_error(self, code: &str, message: &str) -> Option<String> {
+        let key = format!("{}{}", ERRORS_PREFIX, code);
+        let translation = CATALOGS.get(&self)?.get(&key)?;
+        if !translation.contains('{') {
+            return Some(translation.clone());
+        }
+        let template = CATALOGS.get(&Locale::En)?.get(&key)?;
+        let values = match_template(template, message)?;
+        let args: Vec<(&str, &str)> = values
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        Some(fill(translation, &args))
+    }
+
+    /// Every key of the catalog, for checking that translations are complete.
+    pub fn keys(self) -> Vec<&'static str> {
+        CATALOGS
+            .get(&self)
+            .map(|catalog| catalog.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Rewrites the `error` of `{"code": ..., "error": ...}` bodies of failed responses into the
+/// language the client asked for with `Accept-Language`. Codes without a translation are left
+/// in English, and bodies too large to be an error message are passed through as they are.
+pub async fn localize_errors(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Locale::from_accept_language);
+    let response = next.run(request).await;
+
+    let is_json_error = (response.status().is_client_error()
+        || response.status().is_server_error())
+        && response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+    let Some(locale) = locale.filter(|locale| *locale != Locale::En && is_json_error) else {
+        return response;
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match read_small_body(&parts.headers, body).await {
+        Ok(bytes) => bytes,
+        Err(body) => return Response::from_parts(parts, body),
+    };
+    let translated = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|mut value| {
+            let code = value.get("code")?.as_str()?;
+            let message = value.get("error")?.as_str()?;
+            value["error"] = locale.translate_error(code, message)?.into();
+            serde_json::to_vec(&value).ok()
+        });
+
+    match translated {
+        Some(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.headers.insert(
+                header::CONTENT_LANGUAGE,
+                HeaderValue::from_static(locale.code()),
+            );
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// Reads a body of at most [`MAX_ERROR_BODY_BYTES`]. A larger body, by its `Content-Length`,
+/// its size hint or once it gets there, comes back as a body that replays what was read and
+/// streams the rest.
+async fn read_small_body(headers: &HeaderMap, body: Body) -> Result<Bytes, Body> {
+    let content_length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    let known_length = content_length.unwrap_or(0).max(body.size_hint().lower());
+    if known_length > MAX_ERROR_BODY_BYTES as u64 {
+        return Err(body);
+    }
+
+    let mut stream = body.into_data_stream();
+    let mut read = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                read.extend_from_slice(&chunk);
+                if read.len() > MAX_ERROR_BODY_BYTES {
+                    let head = futures::stream::once(async move { Ok(Bytes::from(read)) });
+                    return Err(Body::from_stream(head.chain(stream)));
+                }
+            }
+            Err(e) => {
+                let head = futures::stream::iter([Ok(Bytes::from(read)), Err(e)]);
+                return Err(Body::from_stream(head.chain(stream)));
+            }
+        }
+    }
+    Ok(Bytes::from(read))
+}
@@ -0,0 +1,136 @@
+use axum::{
+    Router,
+    extract::{DefaultBodyLimit, MatchedPath, Request},
+    http::{HeaderName, HeaderValue, Method, header},
+    response::Response,
+};
+use std::time::Duration;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+    limit::RequestBodyLimitLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer},
+    timeout::TimeoutLayer,
+    trace::TraceLayer,
+};
+use tracing::Span;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const DEFAULT_BODY_LIMIT_BYTES: usize = 64 * 1024;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Settings for the HTTP layer stack applied by `build_router`.
+#[derive(Clone, Debug)]
+pub struct HttpConfig {
+    pub body_limit_bytes: usize,
+    /// Applied to every route except the SSE stream.
+    pub request_timeout: Duration,
+    /// Origins allowed to call the API from a browser. `None` disables CORS;
+    /// `Some(vec![])` is treated like `*`.
+    pub cors_allowed_origins: Option<Vec<String>>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            body_limit_bytes: DEFAULT_BODY_LIMIT_BYTES,
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            cors_allowed_origins: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    fn cors_layer(&self) -> Option<CorsLayer> {
+        let origins = self.cors_allowed_origins.as_ref()?;
+        let allow_origin = if origins.is_empty() {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(
+                origins
+                    .iter()
+                    .filter_map(|o| HeaderValue::from_str(o).ok()),
+            )
+        };
+
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::AUTHORIZATION,
+                    HeaderName::from_static(crate::viewer::TOKEN_HEADER),
+                ])
+                .expose_headers([
+                    HeaderName::from_static(REQUEST_ID_HEADER),
+                    HeaderName::from_static(crate::rate_limit::LIMIT_HEADER),
+                    HeaderName::from_static(crate::rate_limit::REMAINING_HEADER),
+                    HeaderName::from_static(crate::rate_limit::RESET_HEADER),
+                    header::RETRY_AFTER,
+                ])
+                .max_age(Duration::from_secs(60 * 60)),
+        )
+    }
+}
+
+fn make_span(request: &Request) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("");
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or("");
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        route,
+        request_id,
+        status = tracing::field::Empty,
+    )
+}
+
+fn on_response(response: &Response, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    tracing::info!(latency_ms = latency.as_millis() as u64, "finished");
+}
+
+/// Per-route timeout; routes added after this (the SSE stream) are not affected.
+pub fn with_timeout<S>(router: Router<S>, config: &HttpConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.route_layer(TimeoutLayer::with_status_code(
+        axum::http::StatusCode::REQUEST_TIMEOUT,
+        config.request_timeout,
+    ))
+}
+
+/// Wraps the whole router, outermost first: request id, trace span, request id propagation,
+/// CORS, compression and the body size limit.
+pub fn apply(router: Router, config: &HttpConfig) -> Router {
+    let header = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    let router = router
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(config.body_limit_bytes))
+        .layer(CompressionLayer::new());
+    let router = match config.cors_layer() {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    router
+        .layer(PropagateRequestIdLayer::new(header.clone()))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_span)
+                .on_response(on_response),
+        )
+        .layer(SetRequestIdLayer::new(header, MakeRequestUuid))
+}
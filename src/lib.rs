@@ -1,34 +1,61 @@
 use axum::{
     Router,
+    handler::Handler,
     routing::{get, post},
 };
-use diesel::pg::PgConnection;
-use diesel::r2d2::{self, ConnectionManager};
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
+pub mod bundle;
+pub mod captcha;
+pub mod chart;
+pub mod client_ip;
+pub mod config;
+pub mod db;
+pub mod embed;
+pub mod event_keys;
+pub mod guess_table;
 pub mod handlers;
+pub mod health;
+pub mod i18n;
+pub mod layers;
+pub mod metrics;
 pub mod models;
+pub mod notifications;
+pub mod og_image;
+pub mod pow;
+pub mod printable;
+pub mod push;
+pub mod qr;
+pub mod rate_limit;
+pub mod render;
+pub mod retention;
 pub mod schema;
+pub mod shutdown;
 pub mod types;
 pub mod utils;
+pub mod validation;
+pub mod viewer;
+pub mod webhooks;
 
+use config::Config;
 use handlers::{
-    claim_event, create_event, delete_event, delete_guess, get_event_by_key, get_event_guesses,
-    health, set_event_answer, share_event_preview, sse_subscribe, submit_guess,
+    claim_event, confirm_unsubscribe, create_event, create_webhook, delete_event, delete_guess,
+    delete_webhook, embed_event, export_event, get_captcha_config, get_event_by_key,
+    get_event_certificate, get_event_chart_png, get_event_chart_svg, get_event_guess_sheet,
+    get_event_guesses, get_event_guesses_csv, get_event_guesses_xlsx, get_event_qr_png,
+    get_event_qr_svg, get_guess_challenge, get_metrics, get_pow_challenge, get_vapid_public_key,
+    health, health_live, health_ready, import_event, list_webhook_deliveries, list_webhooks,
+    oembed, restore_event, set_event_answer, share_event_image, share_event_preview, sse_subscribe,
+    submit_guess, subscribe_push, unlock_event, unsubscribe_notifications, unsubscribe_push,
     update_event_description, update_event_settings, update_guess,
 };
-use types::{AppState, DbPool, RateLimiter};
+use types::{AppState, DbPool};
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
-pub fn create_pool(database_url: &str) -> DbPool {
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
-    r2d2::Pool::builder()
-        .build(manager)
-        .expect("Failed to create pool")
-}
+pub use db::create_pool;
 
 pub fn run_migrations(pool: &DbPool) {
     let mut conn = pool.get().expect("Failed to get connection for migrations");
@@ -36,26 +63,88 @@ pub fn run_migrations(pool: &DbPool) {
         .expect("Failed to run migrations");
 }
 
-pub fn build_state(pool: DbPool) -> AppState {
-    let (tx, _rx) = broadcast::channel(100);
+pub fn build_state(config: &Config, pool: DbPool) -> AppState {
+    let (tx, _rx) = broadcast::channel(config.broadcast_capacity);
+    let pow = Arc::new(pow::ProofOfWork::from_settings(&config.pow));
     AppState {
+        rate_limiter: config.rate_limits.build_store(&pool),
         pool,
         tx,
-        rate_limiter: Arc::new(RateLimiter::new()),
+        client_ip: Arc::new(client_ip::ClientIpResolver::new(
+            config.trusted_proxies.clone(),
+            config.ipv6_rate_limit_prefix,
+        )),
+        push: config.vapid_keys.clone().map(|keys| {
+            Arc::new(push::WebPushClient::new(
+                keys,
+                config.webhook_allow_private_targets,
+            ))
+        }),
+        captcha: captcha::verifier(&config.captcha, pow.clone()),
+        pow,
+        metrics: Arc::new(metrics::Metrics::new(config.metrics_token.clone())),
+        tasks: Arc::new(health::BackgroundTasks::default()),
+        shutdown: shutdown::Shutdown::default(),
+        share_images: Arc::new(og_image::ShareImageCache::default()),
+        viewer_tokens: Arc::new(viewer::ViewerTokens::from_secret(
+            config.viewer_token_secret.as_deref(),
+        )),
+        config: Arc::new(config.clone()),
     }
 }
 
 pub fn build_router(state: AppState) -> Router {
-    Router::new()
+    let policies = &state.config.rate_limits;
+    let limit = |policy: rate_limit::RateLimitPolicy| {
+        axum::middleware::from_fn_with_state(
+            rate_limit::Limited::new(&state, policy),
+            rate_limit::enforce,
+        )
+    };
+
+    let router = Router::new()
         .route("/api/health", get(health))
+        .route("/api/health/live", get(health_live))
+        .route("/api/health/ready", get(health_ready))
+        .route("/metrics", get(get_metrics))
         .route("/api/events", post(create_event))
+        .route("/api/captcha/config", get(get_captcha_config))
+        .route("/api/pow/challenge", get(get_pow_challenge))
         .route("/api/events/{id}", axum::routing::delete(delete_event))
-        .route("/api/events/{id}/claim", post(claim_event))
+        .route("/api/events/{id}/restore", post(restore_event))
+        .route("/api/events/{id}/export", get(export_event))
+        .route("/api/events/import", post(import_event))
+        .route(
+            "/api/events/{id}/claim",
+            post(claim_event.layer(limit(policies.claim_event))),
+        )
         .route("/api/events/by-key/{key}", get(get_event_by_key))
+        .route(
+            "/api/events/by-key/{key}/unlock",
+            post(unlock_event.layer(limit(policies.unlock_event))),
+        )
         .route("/share/{key}", get(share_event_preview))
+        .route("/share/{key}/og.png", get(share_event_image))
+        .route("/embed/{key}", get(embed_event))
+        .route("/oembed", get(oembed))
         .route(
             "/api/events/{id}/guesses",
-            post(submit_guess).get(get_event_guesses),
+            post(submit_guess.layer(limit(policies.submit_guess))).get(get_event_guesses),
+        )
+        .route("/api/events/{id}/guesses.csv", get(get_event_guesses_csv))
+        .route("/api/events/{id}/guesses.xlsx", get(get_event_guesses_xlsx))
+        .route("/api/events/{id}/chart.svg", get(get_event_chart_svg))
+        .route("/api/events/{id}/chart.png", get(get_event_chart_png))
+        .route("/api/events/{id}/qr.svg", get(get_event_qr_svg))
+        .route("/api/events/{id}/qr.png", get(get_event_qr_png))
+        .route("/api/events/{id}/guesses.pdf", get(get_event_guess_sheet))
+        .route(
+            "/api/events/{id}/certificate.pdf",
+            get(get_event_certificate),
+        )
+        .route(
+            "/api/events/{id}/guesses/challenge",
+            get(get_guess_challenge),
         )
         .route(
             "/api/events/{id}/guesses/{invitee_id}",
@@ -70,6 +159,36 @@ pub fn build_router(state: AppState) -> Router {
             axum::routing::put(update_event_description),
         )
         .route("/api/events/{id}/answer", post(set_event_answer))
+        .route(
+            "/api/events/{id}/webhooks",
+            post(create_webhook).get(list_webhooks),
+        )
+        .route(
+            "/api/events/{id}/webhooks/{webhook_id}",
+            axum::routing::delete(delete_webhook),
+        )
+        .route(
+            "/api/events/{id}/webhooks/{webhook_id}/deliveries",
+            get(list_webhook_deliveries),
+        )
+        .route(
+            "/api/events/{id}/push-subscriptions",
+            post(subscribe_push.layer(limit(policies.subscribe_push))).delete(unsubscribe_push),
+        )
+        .route("/api/push/vapid-public-key", get(get_vapid_public_key))
+        .route(
+            "/api/notifications/unsubscribe/{token}",
+            get(confirm_unsubscribe).post(unsubscribe_notifications),
+        );
+
+    let http = state.config.http.clone();
+    let router = layers::with_timeout(router, &http)
         .route("/api/events/live", get(sse_subscribe))
-        .with_state(state)
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.metrics.clone(),
+            metrics::track_latency,
+        ))
+        .layer(axum::middleware::from_fn(i18n::localize_errors))
+        .with_state(state);
+    layers::apply(router, &http)
 }
@@ -4,58 +4,45 @@
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 use axum::serve;
-use chrono::{Duration, Utc};
 use diesel::pg::PgConnection;
-use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
-use std::env;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tower_http::services::{ServeDir, ServeFile};
 use tracing_subscriber::EnvFilter;
 
-use tracing;
-
-use baby_birth_guessr::{build_router, build_state, create_pool, run_migrations};
-
-async fn run_cleanup_task(pool: r2d2::Pool<ConnectionManager<PgConnection>>) {
-    // Run once on startup
-    cleanup_job(&pool).await;
-
-    // Then run every 24 hours
+use baby_birth_guessr::config::Config;
+use baby_birth_guessr::health::Heartbeat;
+use baby_birth_guessr::retention::{self, RetentionPolicy};
+use baby_birth_guessr::shutdown::{self, Shutdown};
+use baby_birth_guessr::{
+    build_router, build_state, create_pool, notifications, push, rate_limit, run_migrations,
+    viewer, webhooks,
+};
+
+async fn run_cleanup_task(
+    pool: r2d2::Pool<ConnectionManager<PgConnection>>,
+    policy: RetentionPolicy,
+    base_url: String,
+    heartbeat: Heartbeat,
+    shutdown: Shutdown,
+) {
+    // Run every 24 hours, starting immediately
     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(24 * 60 * 60));
     loop {
-        interval.tick().await;
-        cleanup_job(&pool).await;
-    }
-}
-
-async fn cleanup_job(pool: &r2d2::Pool<ConnectionManager<PgConnection>>) {
-    let pool = pool.clone();
-    let result = tokio::task::spawn_blocking(move || {
-        use baby_birth_guessr::schema::events::dsl::*;
-        let mut conn = match pool.get() {
-            Ok(c) => c,
-            Err(e) => return Err(format!("Failed to get connection: {}", e)),
-        };
-
-        let limit = Utc::now().naive_utc() - Duration::days(365);
-
-        diesel::delete(events.filter(created_at.lt(limit)))
-            .execute(&mut conn)
-            .map_err(|e| format!("Diesel error: {}", e))
-    })
-    .await;
-
-    match result {
-        Ok(Ok(count)) => {
-            if count > 0 {
-                tracing::info!("Cleaned up {} old events", count);
-            }
+        // A run in progress is finished before stopping
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+        match retention::run(&pool, &policy, &base_url).await {
+            Ok(report) => report.log(),
+            Err(e) => tracing::error!("Error applying the retention policy: {}", e),
         }
-        Ok(Err(e)) => tracing::error!("Error cleaning up events: {}", e),
-        Err(e) => tracing::error!("Cleanup task panicked: {}", e),
+        heartbeat.beat();
     }
+    tracing::info!("Cleanup task stopped");
 }
 
 #[tokio::main]
@@ -68,18 +55,115 @@ async fn main() {
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = create_pool(&database_url);
-
-    // Start cleanup task
-    let cleanup_pool = pool.clone();
-    tokio::spawn(async move {
-        run_cleanup_task(cleanup_pool).await;
-    });
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let pool = create_pool(&config.database_url, &config.database);
 
     run_migrations(&pool);
 
-    let state = build_state(pool);
+    let mailer = match notifications::mailer(config.smtp.clone()) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            eprintln!("Invalid SMTP configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let base_url = config
+        .public_base_url
+        .clone()
+        .unwrap_or_else(|| format!("http://localhost:{}", config.port));
+
+    let state = build_state(&config, pool.clone());
+
+    // Stop on SIGTERM / Ctrl-C: live streams are told to reconnect and then closed
+    let shutdown = state.shutdown.clone();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown::signal().await;
+            tracing::info!("Shutdown signal received, draining connections");
+            shutdown.trigger();
+        }
+    });
+
+    // Start cleanup task; readiness fails if it misses a run
+    let cleanup_pool = pool.clone();
+    let retention_policy = RetentionPolicy::from_config(&config);
+    let heartbeat = state
+        .tasks
+        .register("cleanup", std::time::Duration::from_secs(25 * 60 * 60));
+    let mut tasks = vec![(
+        "Cleanup task",
+        tokio::spawn(run_cleanup_task(
+            cleanup_pool,
+            retention_policy,
+            base_url.clone(),
+            heartbeat,
+            shutdown.clone(),
+        )),
+    )];
+
+    // Start notification worker
+    tasks.push((
+        "Notification worker",
+        tokio::spawn(notifications::run_notification_worker(
+            pool.clone(),
+            mailer,
+            base_url.clone(),
+            shutdown.clone(),
+        )),
+    ));
+
+    // Start webhook delivery
+    tasks.push((
+        "Webhook worker",
+        tokio::spawn(webhooks::run_webhook_worker(
+            pool.clone(),
+            webhooks::WebhookClient::new(config.webhook_allow_private_targets),
+            shutdown.clone(),
+        )),
+    ));
+
+    // Broadcast sealed and hidden guesses as guessing closes
+    tasks.push((
+        "Reveal task",
+        tokio::spawn(viewer::run_reveal_task(
+            pool.clone(),
+            state.tx.clone(),
+            shutdown.clone(),
+        )),
+    ));
+
+    // Evict idle rate limit buckets
+    tasks.push((
+        "Rate limit purge task",
+        tokio::spawn(rate_limit::run_purge_task(
+            state.rate_limiter.clone(),
+            shutdown.clone(),
+        )),
+    ));
+
+    // Start Web Push notifications
+    match &state.push {
+        Some(client) => tasks.push((
+            "Push worker",
+            tokio::spawn(push::run_push_worker(
+                pool,
+                client.clone(),
+                base_url,
+                state.tx.subscribe(),
+                shutdown.clone(),
+            )),
+        )),
+        None => {
+            tracing::info!("Web Push disabled; set VAPID_PRIVATE_KEY to enable it (see the README)")
+        }
+    }
 
     // define routes
     let app = build_router(state).fallback_service(
@@ -87,15 +171,49 @@ async fn main() {
     );
 
     // run server
-    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("PORT").unwrap_or_else(|_| "3000".to_string());
-    let addr = format!("{}:{}", host, port);
-    let listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = format!("{}:{}", config.host, config.port);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
     tracing::info!("Listening on http://{}", addr);
-    serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await
-    .unwrap();
+    let mut server = tokio::spawn({
+        let shutdown = shutdown.clone();
+        serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .into_future()
+    });
+
+    tokio::select! {
+        result = &mut server => {
+            match result {
+                Ok(Ok(())) => tracing::error!("Server stopped unexpectedly"),
+                Ok(Err(e)) => tracing::error!("Server error: {}", e),
+                Err(e) => tracing::error!("Server task panicked: {}", e),
+            }
+            std::process::exit(1);
+        }
+        _ = shutdown.wait() => {}
+    }
+
+    // Connections and background tasks share one drain deadline
+    let deadline = tokio::time::Instant::now() + config.shutdown_timeout;
+    if tokio::time::timeout_at(deadline, server).await.is_err() {
+        tracing::warn!(
+            "Connections still open after {:?}, shutting down anyway",
+            config.shutdown_timeout
+        );
+    }
+    for (name, task) in tasks {
+        if tokio::time::timeout_at(deadline, task).await.is_err() {
+            tracing::warn!("{} did not stop in time", name);
+        }
+    }
+    tracing::info!("Shutdown complete");
 }
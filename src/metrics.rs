@@ -0,0 +1,184 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::types::DbPool;
+
+/// Prometheus collectors for the service, registered in a registry of their own.
+pub struct Metrics {
+    registry: Registry,
+    /// Bearer token required to scrape `/metrics`.
+    pub token: Option<String>,
+    pub guesses_submitted: IntCounter,
+    pub events_created: IntCounter,
+    pub events_ended: IntCounter,
+    pub events_deleted: IntCounter,
+    pub rate_limited: IntCounterVec,
+    pub captcha_failures: IntCounterVec,
+    pub sse_subscribers: IntGauge,
+    pub sse_lagged: IntCounter,
+    pool_connections: IntGauge,
+    pool_idle_connections: IntGauge,
+    pool_max_connections: IntGauge,
+    request_duration: HistogramVec,
+}
+
+fn counter(registry: &Registry, name: &str, help: &str) -> IntCounter {
+    let counter = IntCounter::new(name, help).expect("valid metric");
+    registry
+        .register(Box::new(counter.clone()))
+        .expect("metric registered once");
+    counter
+}
+
+fn counter_vec(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let counter = IntCounterVec::new(Opts::new(name, help), labels).expect("valid metric");
+    registry
+        .register(Box::new(counter.clone()))
+        .expect("metric registered once");
+    counter
+}
+
+fn gauge(registry: &Registry, name: &str, help: &str) -> IntGauge {
+    let gauge = IntGauge::new(name, help).expect("valid metric");
+    registry
+        .register(Box::new(gauge.clone()))
+        .expect("metric registered once");
+    gauge
+}
+
+impl Metrics {
+    pub fn new(token: Option<String>) -> Self {
+        let registry = Registry::new_custom(Some("birth_guessr".to_string()), None)
+            .expect("valid registry prefix");
+
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Handler latency by route.",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        registry
+            .register(Box::new(request_duration.clone()))
+            .expect("metric registered once");
+
+        Self {
+            token,
+            guesses_submitted: counter(&registry, "guesses_submitted_total", "Guesses submitted."),
+            events_created: counter(&registry, "events_created_total", "Events created."),
+            events_ended: counter(
+                &registry,
+                "events_ended_total",
+                "Events ended by publishing the answer.",
+            ),
+            events_deleted: counter(&registry, "events_deleted_total", "Events deleted."),
+            rate_limited: counter_vec(
+                &registry,
+                "rate_limited_total",
+                "Requests rejected by a rate limit policy.",
+                &["policy"],
+            ),
+            captcha_failures: counter_vec(
+                &registry,
+                "captcha_failures_total",
+                "Captcha verifications that failed.",
+                &["provider", "reason"],
+            ),
+            sse_subscribers: gauge(
+                &registry,
+                "sse_subscribers",
+                "Live update streams currently connected.",
+            ),
+            sse_lagged: counter(
+                &registry,
+                "sse_lagged_updates_total",
+                "Live updates dropped because a subscriber fell behind.",
+            ),
+            pool_connections: gauge(
+                &registry,
+                "db_pool_connections",
+                "Connections currently open in the database pool.",
+            ),
+            pool_idle_connections: gauge(
+                &registry,
+                "db_pool_idle_connections",
+                "Idle connections in the database pool.",
+            ),
+            pool_max_connections: gauge(
+                &registry,
+                "db_pool_max_connections",
+                "Maximum size of the database pool.",
+            ),
+            request_duration,
+            registry,
+        }
+    }
+
+    /// Renders all metrics in the Prometheus text format, sampling pool usage first.
+    pub fn render(&self, pool: &DbPool) -> String {
+        let state = pool.state();
+        self.pool_connections.set(i64::from(state.connections));
+        self.pool_idle_connections
+            .set(i64::from(state.idle_connections));
+        self.pool_max_connections.set(i64::from(pool.max_size()));
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding cannot fail");
+        String::from_utf8(buffer).expect("metrics are UTF-8")
+    }
+}
+
+/// Keeps `sse_subscribers` accurate: held by a live stream and dropped with it.
+pub struct SubscriberGuard(Arc<Metrics>);
+
+impl SubscriberGuard {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        metrics.sse_subscribers.inc();
+        Self(metrics)
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.0
+    }
+}
+
+impl Drop for SubscriberGuard {
+    fn drop(&mut self) {
+        self.0.sse_subscribers.dec();
+    }
+}
+
+/// Records handler latency per matched route.
+pub async fn track_latency(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    metrics
+        .request_duration
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .observe(start.elapsed().as_secs_f64());
+    response
+}
@@ -1,4 +1,7 @@
-use crate::schema::{events, guesses, invitees};
+use crate::schema::{
+    email_outbox, email_subscriptions, events, guesses, invitees, push_subscriptions,
+    webhook_deliveries, webhooks,
+};
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use serde::Serialize;
@@ -61,6 +64,33 @@ pub struct Event {
     pub birth_date: Option<NaiveDateTime>,
     pub birth_weight_kg: Option<f64>,
     pub ended_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub close_reminder_sent_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub push_reminder_sent_at: Option<NaiveDateTime>,
+    /// Leading zero bits required from a guess's proof of work; 0 disables it.
+    pub guess_pow_difficulty: i32,
+    /// Set once retention schedules the event for purging; hidden from everyone but the host.
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub restored_at: Option<NaiveDateTime>,
+    /// Origins allowed to frame the embed widget; empty allows any site.
+    pub embed_origins: Vec<String>,
+    /// Language of share pages and emails when the reader's isn't known; English when unset.
+    pub language: Option<String>,
+    /// Host-chosen alternative to the event key in links, e.g. `baby-virtanen`.
+    pub slug: Option<String>,
+    /// `public`, `passcode` or `hidden_guesses`; see [`crate::viewer::Visibility`].
+    pub visibility: String,
+    /// What guests enter to view a `passcode` event. Only the host and guests who entered it
+    /// can load such an event, so it is shown like any other setting.
+    pub passcode: Option<String>,
+    /// Guests see who guessed but not what until guessing closes.
+    pub sealed_guesses: bool,
+    /// When the guesses of a sealed or hidden-guesses event were broadcast at close.
+    #[serde(skip_serializing)]
+    pub guesses_revealed_at: Option<NaiveDateTime>,
 }
 
 #[derive(Serialize)]
@@ -82,6 +112,127 @@ pub struct NewEvent<'a> {
     pub min_weight_kg: f64,
     pub max_weight_kg: f64,
     pub allow_guess_edits: bool,
+    pub guess_pow_difficulty: i32,
+    pub language: Option<&'a str>,
+    pub slug: Option<&'a str>,
+    pub visibility: &'a str,
+    pub passcode: Option<&'a str>,
+    pub sealed_guesses: bool,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = email_subscriptions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EmailSubscription {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub invitee_id: Option<Uuid>,
+    pub email: String,
+    pub role: String,
+    pub unsubscribe_token: String,
+    pub unsubscribed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = email_subscriptions)]
+pub struct NewEmailSubscription<'a> {
+    pub event_id: Uuid,
+    pub invitee_id: Option<Uuid>,
+    pub email: &'a str,
+    pub role: &'a str,
+    pub unsubscribe_token: &'a str,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = email_outbox)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboxEmail {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub subject: String,
+    pub body: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    /// One-click unsubscribe target for the `List-Unsubscribe` header.
+    pub unsubscribe_url: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = email_outbox)]
+pub struct NewOutboxEmail<'a> {
+    pub subscription_id: Uuid,
+    pub subject: &'a str,
+    pub body: &'a str,
+    pub unsubscribe_url: Option<&'a str>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = webhooks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Webhook {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = webhooks)]
+pub struct NewWebhook<'a> {
+    pub event_id: Uuid,
+    pub url: &'a str,
+    pub secret: &'a str,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = webhook_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event_type: String,
+    #[serde(skip_serializing)]
+    pub payload: String,
+    pub attempts: i32,
+    pub status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct NewWebhookDelivery<'a> {
+    pub webhook_id: Uuid,
+    pub event_type: &'a str,
+    pub payload: &'a str,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = push_subscriptions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PushSubscription {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = push_subscriptions)]
+pub struct NewPushSubscription<'a> {
+    pub event_id: Uuid,
+    pub endpoint: &'a str,
+    pub p256dh: &'a str,
+    pub auth: &'a str,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -93,10 +244,39 @@ pub struct GraphPoint {
     pub guessed_weight_kg: f64,
 }
 
+/// A guess of a sealed event before guessing closes: who guessed, but not what.
+#[derive(Serialize, Clone, Debug)]
+pub struct SealedGuess {
+    pub invitee_id: Uuid,
+    pub display_name: String,
+    pub color_hex: String,
+}
+
+impl From<GraphPoint> for SealedGuess {
+    fn from(point: GraphPoint) -> Self {
+        SealedGuess {
+            invitee_id: point.invitee_id,
+            display_name: point.display_name,
+            color_hex: point.color_hex,
+        }
+    }
+}
+
+/// An event's guesses as the reader may see them.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum GuessList {
+    Revealed(Vec<GraphPoint>),
+    Sealed(Vec<SealedGuess>),
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct GuessUpdate {
     pub event_id: Uuid,
     pub guess: GraphPoint,
+    /// Replaces the invitee's earlier guess; not part of the payload, which is the same either way.
+    #[serde(skip)]
+    pub edited: bool,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -126,6 +306,7 @@ pub enum LiveUpdate {
     EventSettings {
         event_id: Uuid,
         allow_guess_edits: bool,
+        sealed_guesses: bool,
     },
     #[serde(rename = "event_description")]
     EventDescription {
@@ -134,4 +315,53 @@ pub enum LiveUpdate {
     },
     #[serde(rename = "event_ended")]
     EventEnded(EventEndedUpdate),
+    /// Sent to guests of a sealed event instead of `guess` until guessing closes.
+    #[serde(rename = "guess_sealed")]
+    GuessSealed { event_id: Uuid },
+    /// Sent to guests of a sealed event instead of an edited `guess` until guessing closes.
+    #[serde(rename = "guess_sealed_edited")]
+    GuessSealedEdited { event_id: Uuid, guess: SealedGuess },
+    /// Every guess of a sealed or hidden-guesses event, once guessing has closed.
+    #[serde(rename = "guesses_revealed")]
+    GuessesRevealed {
+        event_id: Uuid,
+        guesses: Vec<GraphPoint>,
+    },
+    /// Only delivered to webhooks: retention hid the event and will purge it after the date.
+    #[serde(rename = "event_scheduled_for_deletion")]
+    EventScheduledForDeletion {
+        event_id: Uuid,
+        purge_after: NaiveDateTime,
+    },
+}
+
+impl LiveUpdate {
+    pub fn event_id(&self) -> Uuid {
+        match self {
+            LiveUpdate::Guess(g) => g.event_id,
+            LiveUpdate::GuessDeleted(g) => g.event_id,
+            LiveUpdate::EventSettings { event_id, .. } => *event_id,
+            LiveUpdate::EventDescription { event_id, .. } => *event_id,
+            LiveUpdate::EventEnded(e) => e.event_id,
+            LiveUpdate::GuessSealed { event_id } => *event_id,
+            LiveUpdate::GuessSealedEdited { event_id, .. } => *event_id,
+            LiveUpdate::GuessesRevealed { event_id, .. } => *event_id,
+            LiveUpdate::EventScheduledForDeletion { event_id, .. } => *event_id,
+        }
+    }
+
+    /// The serialized `type` tag of this update.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LiveUpdate::Guess(_) => "guess",
+            LiveUpdate::GuessDeleted(_) => "guess_deleted",
+            LiveUpdate::EventSettings { .. } => "event_settings",
+            LiveUpdate::EventDescription { .. } => "event_description",
+            LiveUpdate::EventEnded(_) => "event_ended",
+            LiveUpdate::GuessSealed { .. } => "guess_sealed",
+            LiveUpdate::GuessSealedEdited { .. } => "guess_sealed_edited",
+            LiveUpdate::GuessesRevealed { .. } => "guesses_revealed",
+            LiveUpdate::EventScheduledForDeletion { .. } => "event_scheduled_for_deletion",
+        }
+    }
 }
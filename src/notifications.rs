@@ -0,0 +1,584 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use futures::future::BoxFuture;
+use lettre::message::{
+    Mailbox,
+    header::{ContentType, Header, HeaderName, HeaderValue},
+};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    chart,
+    db::{self, DbError},
+    i18n::Locale,
+    models::{
+        EmailSubscription, Event, EventEndedUpdate, NewEmailSubscription, NewOutboxEmail,
+        OutboxEmail,
+    },
+    schema::{email_outbox, email_subscriptions, events},
+    shutdown::Shutdown,
+    types::DbPool,
+    utils::{effective_guess_close_date, generate_token},
+    viewer::Visibility,
+};
+
+pub const ROLE_HOST: &str = "host";
+pub const ROLE_GUEST: &str = "guest";
+
+const MAX_ATTEMPTS: i32 = 8;
+const DELIVERY_BATCH_SIZE: i64 = 50;
+const REMINDER_WINDOW_HOURS: i64 = 24;
+
+#[derive(Clone, Debug)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    /// Sent as `List-Unsubscribe` with one-click (RFC 8058) support.
+    pub unsubscribe_url: Option<String>,
+}
+
+#[derive(Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self(
+            s.trim_start_matches('<').trim_end_matches('>').to_string(),
+        ))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// Tells mail clients that a POST to the `List-Unsubscribe` URL unsubscribes without a page.
+#[derive(Clone)]
+struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(_: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self)
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), "List-Unsubscribe=One-Click".to_string())
+    }
+}
+
+/// A transport capable of delivering a single rendered email.
+pub trait Mailer: Send + Sync {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> BoxFuture<'a, Result<(), String>>;
+}
+
+#[derive(Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub starttls: bool,
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: SmtpSettings) -> Result<Self, String> {
+        let from = settings
+            .from
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid SMTP_FROM: {}", e))?;
+
+        let mut builder = if settings.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)
+                .map_err(|e| format!("Invalid SMTP relay: {}", e))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)
+        }
+        .port(settings.port);
+
+        if let (Some(user), Some(pass)) = (settings.username, settings.password) {
+            builder = builder.credentials(Credentials::new(user, pass));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let to = email
+                .to
+                .parse::<Mailbox>()
+                .map_err(|e| format!("Invalid recipient: {}", e))?;
+
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(email.subject.clone())
+                .header(ContentType::TEXT_PLAIN);
+            if let Some(url) = &email.unsubscribe_url {
+                builder = builder
+                    .header(ListUnsubscribe(url.clone()))
+                    .header(ListUnsubscribePost);
+            }
+            let message = builder
+                .body(email.body.clone())
+                .map_err(|e| format!("Failed to build message: {}", e))?;
+
+            self.transport
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("SMTP error: {}", e))
+        })
+    }
+}
+
+/// Logs messages instead of sending them; used when SMTP is not configured.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            tracing::info!("Email to {} ({}) not sent: SMTP is not configured", email.to, email.subject);
+            Ok(())
+        })
+    }
+}
+
+/// An SMTP mailer for the configured relay, or one that only logs without a relay.
+pub fn mailer(settings: Option<SmtpSettings>) -> Result<Arc<dyn Mailer>, String> {
+    match settings {
+        Some(settings) => Ok(Arc::new(SmtpMailer::new(settings)?)),
+        None => Ok(Arc::new(LogMailer)),
+    }
+}
+
+pub fn is_valid_email(email: &str) -> bool {
+    email.len() <= 254 && email.parse::<lettre::Address>().is_ok()
+}
+
+pub fn subscribe(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    invitee_id: Option<Uuid>,
+    email: &str,
+    role: &str,
+) -> QueryResult<EmailSubscription> {
+    let token = generate_token();
+    diesel::insert_into(email_subscriptions::table)
+        .values(&NewEmailSubscription {
+            event_id,
+            invitee_id,
+            email,
+            role,
+            unsubscribe_token: &token,
+        })
+        .returning(EmailSubscription::as_returning())
+        .get_result(conn)
+}
+
+/// Whether `token` belongs to a subscription, without changing it.
+pub fn subscription_exists(conn: &mut PgConnection, token: &str) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        email_subscriptions::table.filter(email_subscriptions::unsubscribe_token.eq(token)),
+    ))
+    .get_result(conn)
+}
+
+/// Marks the subscription as unsubscribed. Returns false for unknown tokens.
+pub fn unsubscribe(conn: &mut PgConnection, token: &str) -> QueryResult<bool> {
+    let now = Utc::now().naive_utc();
+    let updated = diesel::update(
+        email_subscriptions::table.filter(email_subscriptions::unsubscribe_token.eq(token)),
+    )
+    .set(email_subscriptions::unsubscribed_at.eq(Some(now)))
+    .execute(conn)?;
+    Ok(updated > 0)
+}
+
+fn event_url(base_url: &str, event: &Event) -> String {
+    format!("{}/event?key={}", base_url, event.event_key)
+}
+
+fn unsubscribe_url(base_url: &str, subscription: &EmailSubscription) -> String {
+    format!(
+        "{}/api/notifications/unsubscribe/{}",
+        base_url, subscription.unsubscribe_token
+    )
+}
+
+/// The signature every email ends with: why it was sent and how to stop it.
+fn footer(locale: Locale, reason_key: &str, unsubscribe_url: &str) -> String {
+    format!(
+        "\n--\n{}\n{}\n",
+        locale.text(reason_key),
+        locale.format("email.unsubscribe", &[("url", unsubscribe_url)])
+    )
+}
+
+pub fn render_close_reminder(
+    event: &Event,
+    close_date: NaiveDateTime,
+    event_url: &str,
+    unsubscribe_url: &str,
+) -> (String, String) {
+    let locale = Locale::for_event(event);
+    let subject = locale.format("email.close_reminder.subject", &[("title", &event.title)]);
+    let mut body = locale.format(
+        "email.close_reminder.body",
+        &[
+            ("title", &event.title),
+            ("close", &locale.datetime(close_date)),
+            ("url", event_url),
+        ],
+    );
+    body.push_str(&footer(locale, "email.host_footer", unsubscribe_url));
+    (subject, body)
+}
+
+pub fn render_results(
+    event: &Event,
+    update: &EventEndedUpdate,
+    event_url: &str,
+    chart_url: Option<&str>,
+    unsubscribe_url: &str,
+) -> (String, String) {
+    let locale = Locale::for_event(event);
+    let subject = locale.format("email.results.subject", &[("title", &event.title)]);
+
+    let mut body = format!(
+        "{}\n\n{}\n{}\n",
+        locale.text("email.results.heading"),
+        locale.format(
+            "email.results.born",
+            &[("date", &locale.datetime(update.birth_date))]
+        ),
+        locale.format(
+            "email.results.weight",
+            &[("weight", &locale.weight(update.birth_weight_kg))]
+        ),
+    );
+
+    if let Some(winner) = update.closest_date_top.first() {
+        body.push_str(&format!(
+            "\n{}\n",
+            locale.format(
+                "email.results.closest_date",
+                &[
+                    ("name", &winner.display_name),
+                    ("date", &locale.date(winner.guessed_date)),
+                ],
+            )
+        ));
+    }
+    if let Some(winner) = update.closest_weight_top.first() {
+        body.push_str(&format!(
+            "{}\n",
+            locale.format(
+                "email.results.closest_weight",
+                &[
+                    ("name", &winner.display_name),
+                    ("weight", &locale.weight(winner.guessed_weight_kg)),
+                ],
+            )
+        ));
+    }
+
+    body.push_str(&format!(
+        "\n{}\n",
+        locale.format("email.results.see_all", &[("url", event_url)])
+    ));
+    if let Some(chart_url) = chart_url {
+        body.push_str(&format!(
+            "{}\n",
+            locale.format("email.results.chart", &[("url", chart_url)])
+        ));
+    }
+    body.push_str(&footer(locale, "email.guest_footer", unsubscribe_url));
+
+    (subject, body)
+}
+
+pub fn render_deletion_warning(
+    event: &Event,
+    purge_after: NaiveDateTime,
+    restore_url: &str,
+    unsubscribe_url: &str,
+) -> (String, String) {
+    let locale = Locale::for_event(event);
+    let subject = locale.format("email.deletion_warning.subject", &[("title", &event.title)]);
+    let mut body = locale.format(
+        "email.deletion_warning.body",
+        &[
+            ("title", &event.title),
+            ("purge", &locale.datetime(purge_after)),
+            ("url", restore_url),
+        ],
+    );
+    body.push_str(&footer(locale, "email.host_footer", unsubscribe_url));
+    (subject, body)
+}
+
+fn active_subscriptions(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    role: &str,
+) -> QueryResult<Vec<EmailSubscription>> {
+    email_subscriptions::table
+        .filter(email_subscriptions::event_id.eq(event_id))
+        .filter(email_subscriptions::role.eq(role))
+        .filter(email_subscriptions::unsubscribed_at.is_null())
+        .select(EmailSubscription::as_select())
+        .load(conn)
+}
+
+fn enqueue(
+    conn: &mut PgConnection,
+    subscription: &EmailSubscription,
+    subject: &str,
+    body: &str,
+    unsubscribe_url: &str,
+) -> QueryResult<()> {
+    diesel::insert_into(email_outbox::table)
+        .values(&NewOutboxEmail {
+            subscription_id: subscription.id,
+            subject,
+            body,
+            unsubscribe_url: Some(unsubscribe_url),
+        })
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Queues the results email for every guest who opted in. Returns the number of queued emails.
+pub fn enqueue_results(
+    conn: &mut PgConnection,
+    event: &Event,
+    update: &EventEndedUpdate,
+    base_url: &str,
+) -> QueryResult<usize> {
+    let subscriptions = active_subscriptions(conn, event.id, ROLE_GUEST)?;
+    let url = event_url(base_url, event);
+    // The chart of a passcode event only opens with a viewer token, so it isn't linked.
+    let chart_url = (Visibility::of(event) != Visibility::Passcode).then(|| {
+        format!(
+            "{}?lang={}",
+            chart::png_url(base_url, event.id),
+            Locale::for_event(event).code()
+        )
+    });
+
+    for subscription in &subscriptions {
+        let unsubscribe = unsubscribe_url(base_url, subscription);
+        let (subject, body) =
+            render_results(event, update, &url, chart_url.as_deref(), &unsubscribe);
+        enqueue(conn, subscription, &subject, &body, &unsubscribe)?;
+    }
+
+    Ok(subscriptions.len())
+}
+
+/// Queues the scheduled-deletion warning for every host who opted in.
+pub fn enqueue_deletion_warning(
+    conn: &mut PgConnection,
+    event: &Event,
+    purge_after: NaiveDateTime,
+    base_url: &str,
+) -> QueryResult<usize> {
+    let subscriptions = active_subscriptions(conn, event.id, ROLE_HOST)?;
+    let restore_url = format!("{}/api/events/{}/restore", base_url, event.id);
+
+    for subscription in &subscriptions {
+        let unsubscribe = unsubscribe_url(base_url, subscription);
+        let (subject, body) =
+            render_deletion_warning(event, purge_after, &restore_url, &unsubscribe);
+        enqueue(conn, subscription, &subject, &body, &unsubscribe)?;
+    }
+
+    Ok(subscriptions.len())
+}
+
+/// Queues a reminder to hosts of events whose guessing closes within the next 24 hours.
+pub fn enqueue_close_reminders(conn: &mut PgConnection, base_url: &str) -> QueryResult<usize> {
+    let now = Utc::now().naive_utc();
+    let window_end = now + Duration::hours(REMINDER_WINDOW_HOURS);
+
+    let candidates = events::table
+        .filter(events::ended_at.is_null())
+        .filter(events::deleted_at.is_null())
+        .filter(events::close_reminder_sent_at.is_null())
+        .filter(
+            events::guess_close_date
+                .between(now, window_end)
+                .or(events::guess_close_date
+                    .is_null()
+                    .and(events::due_date.between(now - Duration::days(1), window_end))),
+        )
+        .select(Event::as_select())
+        .load(conn)?;
+
+    let mut queued = 0;
+    for event in candidates {
+        let Some(close_date) = effective_guess_close_date(&event) else {
+            continue;
+        };
+        if close_date <= now || close_date > window_end {
+            continue;
+        }
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let url = event_url(base_url, &event);
+            for subscription in active_subscriptions(conn, event.id, ROLE_HOST)? {
+                let unsubscribe = unsubscribe_url(base_url, &subscription);
+                let (subject, body) = render_close_reminder(&event, close_date, &url, &unsubscribe);
+                enqueue(conn, &subscription, &subject, &body, &unsubscribe)?;
+                queued += 1;
+            }
+
+            diesel::update(events::table.find(event.id))
+                .set(events::close_reminder_sent_at.eq(Some(now)))
+                .execute(conn)?;
+            Ok(())
+        })?;
+    }
+
+    Ok(queued)
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let minutes = 1i64 << attempts.clamp(0, 9);
+    Duration::minutes(minutes.min(6 * 60))
+}
+
+fn load_due(conn: &mut PgConnection) -> QueryResult<Vec<(OutboxEmail, String)>> {
+    let now = Utc::now().naive_utc();
+
+    email_outbox::table
+        .inner_join(email_subscriptions::table)
+        .filter(email_outbox::sent_at.is_null())
+        .filter(email_outbox::attempts.lt(MAX_ATTEMPTS))
+        .filter(email_outbox::next_attempt_at.le(now))
+        .filter(email_subscriptions::unsubscribed_at.is_null())
+        .order(email_outbox::created_at.asc())
+        .limit(DELIVERY_BATCH_SIZE)
+        .select((OutboxEmail::as_select(), email_subscriptions::email))
+        .load(conn)
+}
+
+fn record_result(
+    conn: &mut PgConnection,
+    entry: &OutboxEmail,
+    result: &Result<(), String>,
+) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    let target = email_outbox::table.find(entry.id);
+
+    let query = match result {
+        Ok(()) => diesel::update(target)
+            .set((
+                email_outbox::sent_at.eq(Some(now)),
+                email_outbox::attempts.eq(entry.attempts + 1),
+                email_outbox::last_error.eq(None::<String>),
+            ))
+            .execute(conn),
+        Err(e) => diesel::update(target)
+            .set((
+                email_outbox::attempts.eq(entry.attempts + 1),
+                email_outbox::last_error.eq(Some(e.clone())),
+                email_outbox::next_attempt_at.eq(now + retry_delay(entry.attempts)),
+            ))
+            .execute(conn),
+    };
+
+    query.map(|_| ())
+}
+
+/// Sends every due outbox email once. Returns the number of successfully delivered emails.
+pub async fn deliver_pending(pool: &DbPool, mailer: &dyn Mailer) -> Result<usize, DbError> {
+    let due = db::run(pool, load_due).await?;
+
+    let mut delivered = 0;
+    for (entry, recipient) in due {
+        let email = OutgoingEmail {
+            to: recipient,
+            subject: entry.subject.clone(),
+            body: entry.body.clone(),
+            unsubscribe_url: entry.unsubscribe_url.clone(),
+        };
+
+        let result = mailer.send(&email).await;
+        match &result {
+            Ok(()) => delivered += 1,
+            Err(e) => tracing::warn!(
+                "Failed to deliver email {} (attempt {}): {}",
+                entry.id,
+                entry.attempts + 1,
+                e
+            ),
+        }
+
+        db::run(pool, move |conn| record_result(conn, &entry, &result)).await?;
+    }
+
+    Ok(delivered)
+}
+
+async fn notification_tick(pool: &DbPool, mailer: &dyn Mailer, base_url: &str) {
+    let reminder_base_url = base_url.to_string();
+    let reminders = db::run(pool, move |conn| {
+        enqueue_close_reminders(conn, &reminder_base_url)
+    })
+    .await;
+
+    match reminders {
+        Ok(count) if count > 0 => tracing::info!("Queued {} close reminder emails", count),
+        Ok(_) => {}
+        Err(e) => tracing::error!("Error queueing close reminders: {}", e),
+    }
+
+    match deliver_pending(pool, mailer).await {
+        Ok(count) if count > 0 => tracing::info!("Delivered {} notification emails", count),
+        Ok(_) => {}
+        Err(e) => tracing::error!("Error delivering notification emails: {}", e),
+    }
+}
+
+pub async fn run_notification_worker(
+    pool: DbPool,
+    mailer: Arc<dyn Mailer>,
+    base_url: String,
+    shutdown: Shutdown,
+) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+    loop {
+        // Emails being sent are finished before stopping
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+        notification_tick(&pool, mailer.as_ref(), &base_url).await;
+    }
+    tracing::info!("Notification worker stopped");
+}
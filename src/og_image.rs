@@ -0,0 +1,274 @@
+use axum::body::Bytes;
+use chrono::NaiveDateTime;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::{
+    guess_table::{self, GuessRow},
+    i18n::Locale,
+    models::Event,
+    render::{self, RenderError, xml_escape},
+};
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+const DEFAULT_CACHE_CAPACITY: usize = 256;
+/// Roughly how many bold title characters fit on one line of the card.
+const TITLE_LINE_CHARS: usize = 24;
+const TITLE_MAX_LINES: usize = 2;
+
+/// What the share card of an event shows; its rendering is reused until this changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShareCard {
+    pub title: String,
+    pub due_date: Option<NaiveDateTime>,
+    pub guesses: usize,
+    pub result: Option<CardResult>,
+    pub locale: Locale,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CardResult {
+    pub birth_date: NaiveDateTime,
+    pub birth_weight_kg: f64,
+    pub closest_date: Option<String>,
+    pub closest_weight: Option<String>,
+}
+
+impl ShareCard {
+    pub fn new(event: &Event, rows: &[GuessRow], locale: Locale) -> Self {
+        let result = match (event.ended_at, event.birth_date, event.birth_weight_kg) {
+            (Some(_), Some(birth_date), Some(birth_weight_kg)) => {
+                let name = |order: Vec<usize>| order.first().map(|&i| rows[i].display_name.clone());
+                Some(CardResult {
+                    birth_date,
+                    birth_weight_kg,
+                    closest_date: name(guess_table::closest_by_date(rows, birth_date)),
+                    closest_weight: name(guess_table::closest_by_weight(rows, birth_weight_kg)),
+                })
+            }
+            _ => None,
+        };
+
+        Self {
+            title: event.title.clone(),
+            due_date: event.due_date,
+            guesses: rows.len(),
+            result,
+            locale,
+        }
+    }
+
+    /// Stable digest of everything drawn on the card; used as cache key and ETag.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.locale.code().as_bytes());
+        hasher.update([0]);
+        hasher.update(self.title.as_bytes());
+        hasher.update([0]);
+        if let Some(due_date) = self.due_date {
+            hasher.update(due_date.and_utc().timestamp().to_be_bytes());
+        }
+        hasher.update([0]);
+        hasher.update((self.guesses as u64).to_be_bytes());
+        if let Some(result) = &self.result {
+            hasher.update(result.birth_date.and_utc().timestamp().to_be_bytes());
+            hasher.update(result.birth_weight_kg.to_be_bytes());
+            for name in [&result.closest_date, &result.closest_weight] {
+                hasher.update([0]);
+                hasher.update(name.as_deref().unwrap_or_default().as_bytes());
+            }
+        }
+        hex::encode(&hasher.finalize()[..12])
+    }
+
+    pub fn to_svg(&self) -> String {
+        let locale = self.locale;
+        let mut lines = Vec::new();
+        let mut y = 210;
+        for line in wrap_title(&self.title) {
+            lines.push(format!(
+                r##"<text x="100" y="{y}" font-size="64" font-weight="bold" fill="#3b2f4a">{}</text>"##,
+                xml_escape(&line)
+            ));
+            y += 78;
+        }
+
+        let mut details = Vec::new();
+        if let Some(due_date) = self.due_date {
+            details.push(locale.format("card.due", &[("date", &locale.long_date(due_date))]));
+        }
+        details.push(locale.guesses(self.guesses));
+        y += 10;
+        lines.push(format!(
+            r##"<text x="100" y="{y}" font-size="36" fill="#5c5470">{}</text>"##,
+            xml_escape(&details.join(" · "))
+        ));
+
+        if let Some(result) = &self.result {
+            y += 70;
+            lines.push(format!(
+                r##"<text x="100" y="{y}" font-size="40" font-weight="bold" fill="#2f6f5e">{}</text>"##,
+                xml_escape(&locale.format(
+                    "card.born",
+                    &[
+                        ("date", &locale.long_date(result.birth_date)),
+                        ("weight", &locale.weight(result.birth_weight_kg)),
+                    ],
+                ))
+            ));
+            let winners: Vec<String> = [
+                ("card.closest_date", &result.closest_date),
+                ("card.closest_weight", &result.closest_weight),
+            ]
+            .into_iter()
+            .filter_map(|(key, name)| {
+                name.as_ref()
+                    .map(|name| locale.format(key, &[("name", name)]))
+            })
+            .collect();
+            if !winners.is_empty() {
+                y += 56;
+                lines.push(format!(
+                    r##"<text x="100" y="{y}" font-size="32" fill="#5c5470">{}</text>"##,
+                    xml_escape(&truncate(&winners.join(" · "), 58))
+                ));
+            }
+        }
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">
+<defs><linearGradient id="bg" x1="0" y1="0" x2="1" y2="1"><stop offset="0" stop-color="#fde2e4"/><stop offset="1" stop-color="#cde7f7"/></linearGradient></defs>
+<rect width="{WIDTH}" height="{HEIGHT}" fill="url(#bg)"/>
+<rect x="50" y="50" width="1100" height="530" rx="36" fill="#ffffff" fill-opacity="0.88"/>
+<text x="100" y="120" font-size="30" font-weight="bold" fill="#b56576">Baby Birth Guessr</text>
+{}
+</svg>
+"##,
+            lines.join("\n")
+        )
+    }
+
+    pub fn render(&self) -> Result<Vec<u8>, RenderError> {
+        render::svg_to_png(&self.to_svg(), 1.0)
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max_chars - 1).collect();
+    out.truncate(out.trim_end().len());
+    out.push('…');
+    out
+}
+
+/// Breaks the title at spaces into at most two lines, cutting the rest with an ellipsis.
+fn wrap_title(title: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut words = title.split_whitespace();
+
+    while let Some(word) = words.next() {
+        let fits = current.is_empty()
+            || current.chars().count() + 1 + word.chars().count() <= TITLE_LINE_CHARS;
+        if !fits {
+            if lines.len() + 1 == TITLE_MAX_LINES {
+                current.push(' ');
+                current.push_str(word);
+                words.for_each(|word| {
+                    current.push(' ');
+                    current.push_str(word);
+                });
+                break;
+            }
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+        .into_iter()
+        .map(|line| truncate(&line, TITLE_LINE_CHARS))
+        .collect()
+}
+
+struct CachedCard {
+    fingerprint: String,
+    png: Bytes,
+    last_used: Instant,
+}
+
+/// Rendered cards per event and language, replaced when the event's card changes. Holds at
+/// most `capacity` cards and drops the least recently used one beyond that.
+pub struct ShareImageCache {
+    cards: Mutex<HashMap<(Uuid, Locale), CachedCard>>,
+    capacity: usize,
+}
+
+impl Default for ShareImageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl ShareImageCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            cards: Mutex::new(HashMap::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cached PNG for this exact card, if there is one.
+    pub fn get(&self, event_id: Uuid, locale: Locale, fingerprint: &str) -> Option<Bytes> {
+        let mut cards = self.cards.lock().unwrap_or_else(|e| e.into_inner());
+        let card = cards.get_mut(&(event_id, locale))?;
+        if card.fingerprint != fingerprint {
+            return None;
+        }
+        card.last_used = Instant::now();
+        Some(card.png.clone())
+    }
+
+    pub fn insert(&self, event_id: Uuid, locale: Locale, fingerprint: String, png: Bytes) {
+        let key = (event_id, locale);
+        let mut cards = self.cards.lock().unwrap_or_else(|e| e.into_inner());
+        if !cards.contains_key(&key)
+            && cards.len() >= self.capacity
+            && let Some(oldest) = cards
+                .iter()
+                .min_by_key(|(_, card)| card.last_used)
+                .map(|(key, _)| *key)
+        {
+            cards.remove(&oldest);
+        }
+        cards.insert(
+            key,
+            CachedCard {
+                fingerprint,
+                png,
+                last_used: Instant::now(),
+            },
+        );
+    }
+}
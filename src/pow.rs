@@ -0,0 +1,195 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const CHALLENGE_VERSION: &str = "v1";
+pub const DEFAULT_DIFFICULTY: u8 = 18;
+const DEFAULT_TTL_SECS: i64 = 10 * 60;
+pub const MAX_DIFFICULTY: u8 = 32;
+/// Upper bound hosts may pick for guesses; higher values take minutes on phones.
+pub const MAX_EVENT_DIFFICULTY: u8 = 24;
+
+#[derive(Debug, PartialEq)]
+pub enum PowError {
+    Malformed,
+    BadSignature,
+    /// The challenge was issued for a different scope (e.g. another event).
+    WrongScope,
+    Expired,
+    InsufficientWork,
+    AlreadyUsed,
+}
+
+/// Key and default difficulty of the challenges this instance issues.
+#[derive(Clone, Debug)]
+pub struct PowSettings {
+    /// HMAC key; without one a random key is used, so challenges are only valid on the
+    /// instance that issued them.
+    pub secret: Option<String>,
+    pub difficulty: u8,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Challenge {
+    pub challenge: String,
+    pub difficulty: u8,
+    pub expires_at: i64,
+}
+
+/// Issues and verifies self-signed hashcash-style challenges.
+///
+/// A challenge is `v1.<expires>.<difficulty>.<scope>.<nonce>.<hmac>`; a solution is
+/// `<challenge>:<counter>` such that `sha256(solution)` starts with `difficulty` zero bits.
+/// The scope binds a challenge to one use site (empty for the global captcha).
+pub struct ProofOfWork {
+    key: Vec<u8>,
+    difficulty: u8,
+    ttl_secs: i64,
+    used: Mutex<HashMap<String, i64>>,
+}
+
+impl ProofOfWork {
+    pub fn new(key: &[u8], difficulty: u8, ttl_secs: i64) -> Self {
+        Self {
+            key: key.to_vec(),
+            difficulty: difficulty.min(MAX_DIFFICULTY),
+            ttl_secs,
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_settings(settings: &PowSettings) -> Self {
+        let key = match &settings.secret {
+            Some(secret) => secret.clone().into_bytes(),
+            None => {
+                let mut key = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+        Self::new(&key, settings.difficulty, DEFAULT_TTL_SECS)
+    }
+
+    pub fn difficulty(&self) -> u8 {
+        self.difficulty
+    }
+
+    fn mac(&self, unsigned: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(unsigned.as_bytes());
+        mac
+    }
+
+    /// Issues a global challenge at the default difficulty.
+    pub fn issue(&self) -> Challenge {
+        self.issue_scoped("", self.difficulty)
+    }
+
+    /// Issues a challenge that only verifies for `scope` (which must not contain `.` or `:`).
+    pub fn issue_scoped(&self, scope: &str, difficulty: u8) -> Challenge {
+        let difficulty = difficulty.min(MAX_DIFFICULTY);
+        let expires_at = Utc::now().timestamp() + self.ttl_secs;
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let unsigned = format!(
+            "{}.{}.{}.{}.{}",
+            CHALLENGE_VERSION,
+            expires_at,
+            difficulty,
+            scope,
+            hex::encode(nonce)
+        );
+        let signature = hex::encode(self.mac(&unsigned).finalize().into_bytes());
+
+        Challenge {
+            challenge: format!("{}.{}", unsigned, signature),
+            difficulty,
+            expires_at,
+        }
+    }
+
+    /// Checks a solution to a global challenge and marks the challenge as spent.
+    pub fn verify(&self, solution: &str) -> Result<(), PowError> {
+        self.verify_scoped("", self.difficulty, solution)
+    }
+
+    /// Checks a `<challenge>:<counter>` solution for `scope`, requiring at least
+    /// `min_difficulty` bits of work, and marks the challenge as spent.
+    pub fn verify_scoped(
+        &self,
+        scope: &str,
+        min_difficulty: u8,
+        solution: &str,
+    ) -> Result<(), PowError> {
+        let (challenge, counter) = solution.rsplit_once(':').ok_or(PowError::Malformed)?;
+        counter.parse::<u64>().map_err(|_| PowError::Malformed)?;
+
+        let (unsigned, signature) = challenge.rsplit_once('.').ok_or(PowError::Malformed)?;
+        let parts: Vec<&str> = unsigned.split('.').collect();
+        let [version, expires_at, difficulty, challenge_scope, _nonce] = parts[..] else {
+            return Err(PowError::Malformed);
+        };
+        if version != CHALLENGE_VERSION {
+            return Err(PowError::Malformed);
+        }
+
+        let signature = hex::decode(signature).map_err(|_| PowError::Malformed)?;
+        self.mac(unsigned)
+            .verify_slice(&signature)
+            .map_err(|_| PowError::BadSignature)?;
+
+        if challenge_scope != scope {
+            return Err(PowError::WrongScope);
+        }
+
+        let expires_at: i64 = expires_at.parse().map_err(|_| PowError::Malformed)?;
+        let now = Utc::now().timestamp();
+        if now > expires_at {
+            return Err(PowError::Expired);
+        }
+
+        let difficulty: u8 = difficulty.parse().map_err(|_| PowError::Malformed)?;
+        if difficulty < min_difficulty
+            || leading_zero_bits(&Sha256::digest(solution.as_bytes())) < u32::from(difficulty)
+        {
+            return Err(PowError::InsufficientWork);
+        }
+
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        used.retain(|_, expiry| *expiry >= now);
+        if used.insert(challenge.to_string(), expires_at).is_some() {
+            return Err(PowError::AlreadyUsed);
+        }
+
+        Ok(())
+    }
+}
+
+pub fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Brute-forces a solution; what a client does before submitting.
+pub fn solve(challenge: &str, difficulty: u8) -> String {
+    (0u64..)
+        .map(|counter| format!("{}:{}", challenge, counter))
+        .find(|candidate| {
+            leading_zero_bits(&Sha256::digest(candidate.as_bytes())) >= u32::from(difficulty)
+        })
+        .expect("a solution exists for any difficulty <= 256")
+}
@@ -0,0 +1,676 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use qrcode::EcLevel;
+use std::fmt;
+
+use crate::{
+    guess_table::{self, GuessRow},
+    i18n::Locale,
+    models::Event,
+    qr::{QrError, QrMatrix},
+    utils::effective_guess_close_date,
+};
+
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+
+/// A4 portrait in points.
+const A4: (f32, f32) = (595.0, 842.0);
+const MARGIN: f32 = 48.0;
+const SHEET_QR_SIZE: f32 = 112.0;
+const CERTIFICATE_QR_SIZE: f32 = 84.0;
+const ROW_HEIGHT: f32 = 24.0;
+/// Sheets of open events end with at least this many numbered rows for hand-written guesses.
+const MIN_BLANK_ROWS: usize = 6;
+/// Places listed under each category of the certificate, winner included.
+const PODIUM_PLACES: usize = 3;
+
+type Rgb = (f32, f32, f32);
+
+const INK: Rgb = (0.118, 0.161, 0.231);
+const MUTED: Rgb = (0.392, 0.455, 0.545);
+const ACCENT: Rgb = (0.710, 0.396, 0.463);
+const WINNER: Rgb = (0.184, 0.435, 0.369);
+const RULE: Rgb = (0.886, 0.910, 0.941);
+
+/// Advance widths of the printable ASCII characters (space to `~`) in the standard Helvetica
+/// fonts, in thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+#[derive(Debug)]
+pub enum PdfError {
+    /// Certificates need the birth date and weight of an ended event.
+    NotEnded,
+    Qr(QrError),
+}
+
+impl fmt::Display for PdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdfError::NotEnded => write!(f, "Event has not ended yet"),
+            PdfError::Qr(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// The two built-in PDF fonts used here. They need no embedding, which keeps files small, but
+/// only cover Windows-1252: other characters print as `?`.
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    const ALL: [Font; 2] = [Font::Regular, Font::Bold];
+
+    fn resource(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+        }
+    }
+
+    fn base_font(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"Helvetica"),
+            Font::Bold => Name(b"Helvetica-Bold"),
+        }
+    }
+
+    /// Width of `text` in points. Letters outside ASCII are measured like an `o`, which is
+    /// close enough for accented Latin letters.
+    fn width(self, text: &str, size: f32) -> f32 {
+        let widths = match self {
+            Font::Regular => &HELVETICA_WIDTHS,
+            Font::Bold => &HELVETICA_BOLD_WIDTHS,
+        };
+        let units: u32 = text
+            .chars()
+            .map(|c| match c {
+                ' '..='~' => u32::from(widths[c as usize - 32]),
+                _ => u32::from(widths[usize::from(b'o' - 32)]),
+            })
+            .sum();
+        units as f32 * size / 1000.0
+    }
+}
+
+/// Encodes text for the built-in fonts' `WinAnsiEncoding`.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '‚' => 0x82,
+            'ƒ' => 0x83,
+            '„' => 0x84,
+            '…' => 0x85,
+            '†' => 0x86,
+            '‡' => 0x87,
+            'ˆ' => 0x88,
+            '‰' => 0x89,
+            'Š' => 0x8a,
+            '‹' => 0x8b,
+            'Œ' => 0x8c,
+            'Ž' => 0x8e,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '˜' => 0x98,
+            '™' => 0x99,
+            'š' => 0x9a,
+            '›' => 0x9b,
+            'œ' => 0x9c,
+            'ž' => 0x9e,
+            'Ÿ' => 0x9f,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Cuts `text` with an ellipsis so it fits in `max_width` points.
+fn fit(font: Font, size: f32, text: &str, max_width: f32) -> String {
+    if font.width(text, size) <= max_width {
+        return text.to_string();
+    }
+    let mut out: String = text.to_string();
+    while !out.is_empty() && font.width(&format!("{}…", out), size) > max_width {
+        out.pop();
+    }
+    out.truncate(out.trim_end().len());
+    out.push('…');
+    out
+}
+
+/// Guest-chosen colors are only trusted as `#rgb` or `#rrggbb`.
+fn parse_color(hex: &str) -> Option<Rgb> {
+    let digits = hex.strip_prefix('#')?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |digits: &str| {
+        u8::from_str_radix(digits, 16)
+            .ok()
+            .map(|v| f32::from(v) / 255.0)
+    };
+    match digits.len() {
+        3 => {
+            let expanded: String = digits.chars().flat_map(|c| [c, c]).collect();
+            parse_color(&format!("#{}", expanded))
+        }
+        6 => Some((
+            channel(&digits[0..2])?,
+            channel(&digits[2..4])?,
+            channel(&digits[4..6])?,
+        )),
+        _ => None,
+    }
+}
+
+/// Drawing commands of one page. Coordinates are measured from the top-left corner like in
+/// the SVG renderers, and `y` of text is its baseline.
+struct Page {
+    content: Content,
+    height: f32,
+}
+
+impl Page {
+    fn new(height: f32) -> Self {
+        Self {
+            content: Content::new(),
+            height,
+        }
+    }
+
+    fn text(&mut self, font: Font, size: f32, color: Rgb, x: f32, y: f32, text: &str) {
+        self.content
+            .set_fill_rgb(color.0, color.1, color.2)
+            .begin_text()
+            .set_font(font.resource(), size)
+            .next_line(x, self.height - y)
+            .show(Str(&win_ansi(text)))
+            .end_text();
+    }
+
+    fn text_centered(&mut self, font: Font, size: f32, color: Rgb, x: f32, y: f32, text: &str) {
+        let x = x - font.width(text, size) / 2.0;
+        self.text(font, size, color, x, y, text);
+    }
+
+    fn fill_rect(&mut self, color: Rgb, x: f32, y: f32, width: f32, height: f32) {
+        self.content
+            .set_fill_rgb(color.0, color.1, color.2)
+            .rect(x, self.height - y - height, width, height)
+            .fill_nonzero();
+    }
+
+    fn stroke_rect(&mut self, color: Rgb, line_width: f32, x: f32, y: f32, w: f32, h: f32) {
+        self.content
+            .set_stroke_rgb(color.0, color.1, color.2)
+            .set_line_width(line_width)
+            .rect(x, self.height - y - h, w, h)
+            .stroke();
+    }
+
+    fn line(&mut self, color: Rgb, x1: f32, x2: f32, y: f32) {
+        self.content
+            .set_stroke_rgb(color.0, color.1, color.2)
+            .set_line_width(0.75)
+            .move_to(x1, self.height - y)
+            .line_to(x2, self.height - y)
+            .stroke();
+    }
+
+    /// The QR code `size` points square, quiet zone included, with its top-left corner at
+    /// `x`, `y`.
+    fn qr_code(&mut self, matrix: &QrMatrix, x: f32, y: f32, size: f32) {
+        let module = size / matrix.extent() as f32;
+        self.fill_rect((1.0, 1.0, 1.0), x, y, size, size);
+        self.content.set_fill_rgb(0.0, 0.0, 0.0);
+        for (column, row) in matrix.dark_modules() {
+            self.content.rect(
+                x + column as f32 * module,
+                self.height - y - (row + 1) as f32 * module,
+                module,
+                module,
+            );
+        }
+        self.content.fill_nonzero();
+    }
+}
+
+/// Writes the pages into a PDF using the built-in fonts.
+fn assemble(title: &str, (width, height): (f32, f32), pages: Vec<Page>) -> Vec<u8> {
+    let mut next_ref = Ref::new(1);
+    let catalog_id = next_ref.bump();
+    let page_tree_id = next_ref.bump();
+    let info_id = next_ref.bump();
+    let font_ids: Vec<Ref> = Font::ALL.iter().map(|_| next_ref.bump()).collect();
+    let page_ids: Vec<(Ref, Ref)> = pages
+        .iter()
+        .map(|_| (next_ref.bump(), next_ref.bump()))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.document_info(info_id)
+        .title(TextStr(title))
+        .producer(TextStr("Baby Birth Guessr"));
+    for (font, id) in Font::ALL.into_iter().zip(&font_ids) {
+        pdf.type1_font(*id)
+            .base_font(font.base_font())
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().map(|(page_id, _)| *page_id))
+        .count(page_ids.len() as i32);
+
+    for (page, (page_id, content_id)) in pages.into_iter().zip(page_ids) {
+        let mut writer = pdf.page(page_id);
+        writer
+            .parent(page_tree_id)
+            .media_box(Rect::new(0.0, 0.0, width, height))
+            .contents(content_id);
+        let mut resources = writer.resources();
+        let mut fonts = resources.fonts();
+        for (font, id) in Font::ALL.into_iter().zip(&font_ids) {
+            fonts.pair(font.resource(), *id);
+        }
+        fonts.finish();
+        resources.finish();
+        writer.finish();
+        pdf.stream(content_id, &page.content.finish());
+    }
+
+    pdf.finish()
+}
+
+/// Column positions of the guess sheet table.
+const NUMBER_X: f32 = MARGIN;
+const SWATCH_X: f32 = MARGIN + 30.0;
+const NAME_X: f32 = MARGIN + 46.0;
+const DATE_X: f32 = MARGIN + 290.0;
+const WEIGHT_X: f32 = MARGIN + 410.0;
+
+fn sheet_table_header(page: &mut Page, locale: Locale, y: f32) -> f32 {
+    let y = y + 12.0;
+    page.text(Font::Bold, 10.0, MUTED, NUMBER_X, y, "#");
+    page.text(Font::Bold, 10.0, MUTED, NAME_X, y, locale.text("pdf.name"));
+    page.text(
+        Font::Bold,
+        10.0,
+        MUTED,
+        DATE_X,
+        y,
+        locale.text("pdf.guessed_date"),
+    );
+    page.text(
+        Font::Bold,
+        10.0,
+        MUTED,
+        WEIGHT_X,
+        y,
+        locale.text("pdf.guessed_weight"),
+    );
+    page.line(MUTED, MARGIN, A4.0 - MARGIN, y + 8.0);
+    y + 8.0
+}
+
+/// A printable A4 list of the guesses for in-person baby showers: the event's details, a QR
+/// code of `share_url` and every guess in submission order. Open events get numbered blank
+/// rows at the end for guests to write their guesses on.
+pub fn guess_sheet(
+    event: &Event,
+    rows: &[GuessRow],
+    locale: Locale,
+    share_url: &str,
+) -> Result<Vec<u8>, PdfError> {
+    let qr = QrMatrix::new(share_url, EcLevel::M).map_err(PdfError::Qr)?;
+    let (width, height) = A4;
+    let bottom = height - MARGIN - 24.0;
+    let header_width = width - 2.0 * MARGIN - SHEET_QR_SIZE - 16.0;
+
+    let mut pages = Vec::new();
+    let mut page = Page::new(height);
+    page.text(
+        Font::Bold,
+        12.0,
+        ACCENT,
+        MARGIN,
+        MARGIN + 10.0,
+        "Baby Birth Guessr",
+    );
+    page.text(
+        Font::Bold,
+        24.0,
+        INK,
+        MARGIN,
+        MARGIN + 42.0,
+        &fit(Font::Bold, 24.0, &event.title, header_width),
+    );
+
+    let mut details = Vec::new();
+    match (event.ended_at, event.birth_date, event.birth_weight_kg) {
+        (Some(_), Some(birth_date), Some(birth_weight_kg)) => details.push(locale.format(
+            "card.born",
+            &[
+                ("date", &locale.long_date(birth_date)),
+                ("weight", &locale.weight(birth_weight_kg)),
+            ],
+        )),
+        _ => {
+            if let Some(due_date) = event.due_date {
+                details.push(locale.format("card.due", &[("date", &locale.long_date(due_date))]));
+            }
+            if let Some(close) = effective_guess_close_date(event) {
+                details.push(locale.format(
+                    "card.guessing_closes",
+                    &[("date", &locale.long_date(close))],
+                ));
+            }
+        }
+    }
+    details.push(locale.guesses(rows.len()));
+    let mut y = MARGIN + 64.0;
+    for line in details {
+        page.text(
+            Font::Regular,
+            11.0,
+            MUTED,
+            MARGIN,
+            y,
+            &fit(Font::Regular, 11.0, &line, header_width),
+        );
+        y += 16.0;
+    }
+
+    let qr_x = width - MARGIN - SHEET_QR_SIZE;
+    let qr_center = qr_x + SHEET_QR_SIZE / 2.0;
+    page.qr_code(&qr, qr_x, MARGIN - 8.0, SHEET_QR_SIZE);
+    let caption_y = MARGIN + SHEET_QR_SIZE + 2.0;
+    let scan = locale.text("pdf.scan_to_guess");
+    page.text_centered(
+        Font::Bold,
+        8.0,
+        INK,
+        qr_center,
+        caption_y,
+        &fit(Font::Bold, 8.0, scan, SHEET_QR_SIZE + 24.0),
+    );
+    page.text_centered(
+        Font::Regular,
+        7.0,
+        MUTED,
+        qr_center,
+        caption_y + 10.0,
+        &fit(Font::Regular, 7.0, share_url, SHEET_QR_SIZE + 24.0),
+    );
+
+    let mut y = sheet_table_header(&mut page, locale, y.max(caption_y + 10.0) + 12.0);
+    let mut number = 0;
+    let mut next_row = |page: &mut Page, y: &mut f32, pages: &mut Vec<Page>| {
+        if *y + ROW_HEIGHT > bottom {
+            pages.push(std::mem::replace(page, Page::new(height)));
+            *y = sheet_table_header(page, locale, MARGIN);
+        }
+        number += 1;
+        let baseline = *y + ROW_HEIGHT - 8.0;
+        page.text(
+            Font::Regular,
+            10.0,
+            MUTED,
+            NUMBER_X,
+            baseline,
+            &format!("{}.", number),
+        );
+        page.line(RULE, MARGIN, width - MARGIN, *y + ROW_HEIGHT);
+        *y += ROW_HEIGHT;
+        baseline
+    };
+
+    for row in rows {
+        let baseline = next_row(&mut page, &mut y, &mut pages);
+        if let Some(color) = parse_color(&row.color_hex) {
+            page.fill_rect(color, SWATCH_X, baseline - 8.0, 9.0, 9.0);
+        }
+        let name = fit(
+            Font::Regular,
+            11.0,
+            &row.display_name,
+            DATE_X - NAME_X - 12.0,
+        );
+        page.text(Font::Regular, 11.0, INK, NAME_X, baseline, &name);
+        let date = locale.date(row.guessed_date);
+        page.text(Font::Regular, 11.0, INK, DATE_X, baseline, &date);
+        let weight = locale.weight(row.guessed_weight_kg);
+        page.text(Font::Regular, 11.0, INK, WEIGHT_X, baseline, &weight);
+    }
+
+    if event.ended_at.is_none() {
+        // A handful of rows at the foot of a page is easy to miss; start a fresh one instead.
+        if bottom - y < MIN_BLANK_ROWS as f32 * ROW_HEIGHT {
+            pages.push(std::mem::replace(&mut page, Page::new(height)));
+            y = sheet_table_header(&mut page, locale, MARGIN);
+        }
+        while y + ROW_HEIGHT <= bottom {
+            next_row(&mut page, &mut y, &mut pages);
+        }
+    }
+    pages.push(page);
+
+    let count = pages.len();
+    for (i, page) in pages.iter_mut().enumerate() {
+        let footer = locale.format(
+            "pdf.page",
+            &[
+                ("page", &(i + 1).to_string()),
+                ("pages", &count.to_string()),
+            ],
+        );
+        page.text_centered(
+            Font::Regular,
+            9.0,
+            MUTED,
+            width / 2.0,
+            height - MARGIN + 8.0,
+            &footer,
+        );
+    }
+
+    let title = format!("{} – {}", event.title, locale.text("pdf.guess_sheet"));
+    Ok(assemble(&title, A4, pages))
+}
+
+/// One column of the certificate: the winner of a category, how close they got and the
+/// places after them.
+struct Podium {
+    label: String,
+    places: Vec<(String, String)>,
+}
+
+fn podium(
+    rows: &[GuessRow],
+    order: Vec<usize>,
+    label: String,
+    detail: impl Fn(&GuessRow) -> String,
+) -> Podium {
+    Podium {
+        label,
+        places: order
+            .into_iter()
+            .take(PODIUM_PLACES)
+            .map(|i| (rows[i].display_name.clone(), detail(&rows[i])))
+            .collect(),
+    }
+}
+
+/// A landscape A4 certificate for the closest guessers of an ended event: the birth date
+/// and weight, the winners by date and by weight with the runners-up, and a QR code of
+/// `share_url` leading to all results.
+pub fn certificate(
+    event: &Event,
+    rows: &[GuessRow],
+    locale: Locale,
+    share_url: &str,
+) -> Result<Vec<u8>, PdfError> {
+    let (Some(_), Some(birth_date), Some(birth_weight_kg)) =
+        (event.ended_at, event.birth_date, event.birth_weight_kg)
+    else {
+        return Err(PdfError::NotEnded);
+    };
+    let qr = QrMatrix::new(share_url, EcLevel::M).map_err(PdfError::Qr)?;
+    let (height, width) = A4;
+    let center = width / 2.0;
+
+    let mut page = Page::new(height);
+    page.stroke_rect(ACCENT, 3.0, 24.0, 24.0, width - 48.0, height - 48.0);
+    page.stroke_rect(ACCENT, 0.75, 32.0, 32.0, width - 64.0, height - 64.0);
+    page.text_centered(Font::Bold, 14.0, ACCENT, center, 84.0, "Baby Birth Guessr");
+    page.text_centered(
+        Font::Bold,
+        36.0,
+        INK,
+        center,
+        130.0,
+        locale.text("pdf.certificate"),
+    );
+    page.text_centered(
+        Font::Regular,
+        18.0,
+        MUTED,
+        center,
+        162.0,
+        &fit(Font::Regular, 18.0, &event.title, width - 2.0 * 72.0),
+    );
+    let born = locale.format(
+        "card.born",
+        &[
+            ("date", &locale.long_date(birth_date)),
+            ("weight", &locale.weight(birth_weight_kg)),
+        ],
+    );
+    page.text_centered(Font::Bold, 14.0, WINNER, center, 192.0, &born);
+
+    let podiums = [
+        podium(
+            rows,
+            guess_table::closest_by_date(rows, birth_date),
+            locale.text("pdf.closest_date").to_string(),
+            |row| {
+                let days = (row.guessed_date - birth_date).num_days().abs();
+                let delta = match days {
+                    0 => locale.text("pdf.exact").to_string(),
+                    1 => locale.text("pdf.days_off.one").to_string(),
+                    n => locale.format("pdf.days_off.other", &[("count", &n.to_string())]),
+                };
+                format!("{} · {}", locale.date(row.guessed_date), delta)
+            },
+        ),
+        podium(
+            rows,
+            guess_table::closest_by_weight(rows, birth_weight_kg),
+            locale.text("pdf.closest_weight").to_string(),
+            |row| {
+                let grams = ((row.guessed_weight_kg - birth_weight_kg) * 1000.0)
+                    .round()
+                    .abs();
+                let delta = if grams == 0.0 {
+                    locale.text("pdf.exact").to_string()
+                } else {
+                    locale.format("pdf.grams_off", &[("grams", &grams.to_string())])
+                };
+                format!("{} · {}", locale.weight(row.guessed_weight_kg), delta)
+            },
+        ),
+    ];
+
+    if rows.is_empty() {
+        page.text_centered(
+            Font::Regular,
+            16.0,
+            MUTED,
+            center,
+            290.0,
+            locale.text("pdf.no_guesses"),
+        );
+    }
+    let column_width = width / 2.0 - 96.0;
+    for (podium, x) in podiums.iter().zip([width * 0.28, width * 0.72]) {
+        let Some((winner, detail)) = podium.places.first() else {
+            continue;
+        };
+        page.text_centered(Font::Regular, 12.0, MUTED, x, 250.0, &podium.label);
+        page.text_centered(
+            Font::Bold,
+            26.0,
+            INK,
+            x,
+            284.0,
+            &fit(Font::Bold, 26.0, winner, column_width),
+        );
+        let guessed = locale.format("pdf.guessed", &[("value", detail)]);
+        page.text_centered(Font::Regular, 11.0, MUTED, x, 304.0, &guessed);
+        for (place, (name, detail)) in podium.places.iter().enumerate().skip(1) {
+            let line = format!("{}. {} · {}", place + 1, name, detail);
+            page.text_centered(
+                Font::Regular,
+                10.0,
+                MUTED,
+                x,
+                320.0 + 16.0 * place as f32,
+                &fit(Font::Regular, 10.0, &line, column_width),
+            );
+        }
+    }
+
+    page.text_centered(
+        Font::Bold,
+        18.0,
+        ACCENT,
+        center,
+        height - 92.0,
+        locale.text("pdf.congratulations"),
+    );
+    page.text_centered(
+        Font::Regular,
+        9.0,
+        MUTED,
+        center,
+        height - 72.0,
+        &fit(Font::Regular, 9.0, share_url, width / 2.0),
+    );
+    page.qr_code(
+        &qr,
+        width - 40.0 - CERTIFICATE_QR_SIZE,
+        height - 40.0 - CERTIFICATE_QR_SIZE,
+        CERTIFICATE_QR_SIZE,
+    );
+
+    let title = format!("{} – {}", event.title, locale.text("pdf.certificate"));
+    Ok(assemble(&title, (width, height), vec![page]))
+}
+
+/// `inline` header value, so browsers open the PDF in their viewer ready to print.
+pub fn content_disposition(event: &Event, kind: &str) -> String {
+    format!(
+        "inline; filename=\"{}\"",
+        guess_table::file_name(event, kind, "pdf")
+    )
+}
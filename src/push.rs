@@ -0,0 +1,484 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use hkdf::Hkdf;
+use p256::ecdsa::{Signature, SigningKey, signature::Signer};
+use p256::elliptic_curve::rand_core::OsRng;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::Sha256;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    db::{self, DbError},
+    models::{Event, LiveUpdate, NewPushSubscription, PushSubscription},
+    schema::{events, push_subscriptions},
+    shutdown::Shutdown,
+    types::DbPool,
+    utils::effective_guess_close_date,
+    webhooks,
+};
+
+/// Record size advertised in the aes128gcm header; payloads always fit in one record.
+const RECORD_SIZE: u32 = 4096;
+const MAX_PAYLOAD_LEN: usize = 3000;
+const TTL_SECS: u32 = 24 * 60 * 60;
+const JWT_LIFETIME_HOURS: i64 = 12;
+const REMINDER_WINDOW_HOURS: i64 = 24;
+
+#[derive(Debug)]
+pub enum PushError {
+    InvalidKey(&'static str),
+    PayloadTooLarge,
+    Encryption,
+}
+
+impl std::fmt::Display for PushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushError::InvalidKey(what) => write!(f, "invalid {}", what),
+            PushError::PayloadTooLarge => write!(f, "payload too large"),
+            PushError::Encryption => write!(f, "encryption failed"),
+        }
+    }
+}
+
+fn decode_b64(input: &str, what: &'static str) -> Result<Vec<u8>, PushError> {
+    URL_SAFE_NO_PAD
+        .decode(input.trim_end_matches('='))
+        .map_err(|_| PushError::InvalidKey(what))
+}
+
+fn hkdf_expand(salt: &[u8], ikm: &[u8], info: &[u8], out: &mut [u8]) {
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, out)
+        .expect("HKDF output length is valid");
+}
+
+/// Encrypts `payload` for a subscription per RFC 8291 (`aes128gcm` content coding),
+/// using the given application-server key and salt. Returns the full request body.
+pub fn encrypt_with(
+    payload: &[u8],
+    p256dh: &str,
+    auth: &str,
+    as_secret: &SecretKey,
+    salt: &[u8; 16],
+) -> Result<Vec<u8>, PushError> {
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(PushError::PayloadTooLarge);
+    }
+
+    let ua_public_bytes = decode_b64(p256dh, "p256dh")?;
+    let ua_public =
+        PublicKey::from_sec1_bytes(&ua_public_bytes).map_err(|_| PushError::InvalidKey("p256dh"))?;
+    let auth_secret = decode_b64(auth, "auth")?;
+    if auth_secret.len() != 16 {
+        return Err(PushError::InvalidKey("auth"));
+    }
+
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let ua_public_point = ua_public.to_encoded_point(false);
+
+    let shared = p256::ecdh::diffie_hellman(as_secret.to_nonzero_scalar(), ua_public.as_affine());
+
+    let mut key_info = Vec::with_capacity(14 + 65 + 65);
+    key_info.extend_from_slice(b"WebPush: info\0");
+    key_info.extend_from_slice(ua_public_point.as_bytes());
+    key_info.extend_from_slice(as_public.as_bytes());
+
+    let mut ikm = [0u8; 32];
+    hkdf_expand(&auth_secret, shared.raw_secret_bytes(), &key_info, &mut ikm);
+
+    let mut cek = [0u8; 16];
+    hkdf_expand(salt, &ikm, b"Content-Encoding: aes128gcm\0", &mut cek);
+    let mut nonce = [0u8; 12];
+    hkdf_expand(salt, &ikm, b"Content-Encoding: nonce\0", &mut nonce);
+
+    // Single, final record: payload followed by the 0x02 delimiter.
+    let mut plaintext = Vec::with_capacity(payload.len() + 1);
+    plaintext.extend_from_slice(payload);
+    plaintext.push(0x02);
+
+    let cipher = Aes128Gcm::new_from_slice(&cek).map_err(|_| PushError::Encryption)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|_| PushError::Encryption)?;
+
+    let key_id = as_public.as_bytes();
+    let mut body = Vec::with_capacity(16 + 4 + 1 + key_id.len() + ciphertext.len());
+    body.extend_from_slice(salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(key_id.len() as u8);
+    body.extend_from_slice(key_id);
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// Encrypts `payload` with a fresh ephemeral key and random salt.
+pub fn encrypt(payload: &[u8], p256dh: &str, auth: &str) -> Result<Vec<u8>, PushError> {
+    let as_secret = SecretKey::random(&mut OsRng);
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    encrypt_with(payload, p256dh, auth, &as_secret, &salt)
+}
+
+/// Application-server identity used to sign VAPID (RFC 8292) tokens.
+#[derive(Clone)]
+pub struct VapidKeys {
+    signing_key: SigningKey,
+    subject: String,
+}
+
+impl VapidKeys {
+    /// `private_key` is the base64url-encoded 32-byte P-256 scalar.
+    pub fn new(private_key: &str, subject: &str) -> Result<Self, PushError> {
+        let bytes = decode_b64(private_key.trim(), "VAPID private key")?;
+        let secret =
+            SecretKey::from_slice(&bytes).map_err(|_| PushError::InvalidKey("VAPID private key"))?;
+        Ok(Self {
+            signing_key: SigningKey::from(secret),
+            subject: subject.to_string(),
+        })
+    }
+
+    pub fn generate_private_key() -> String {
+        URL_SAFE_NO_PAD.encode(SecretKey::random(&mut OsRng).to_bytes())
+    }
+
+    /// The uncompressed public key, base64url-encoded (the browser's `applicationServerKey`).
+    pub fn public_key(&self) -> String {
+        let point = self.signing_key.verifying_key().to_encoded_point(false);
+        URL_SAFE_NO_PAD.encode(point.as_bytes())
+    }
+
+    /// Builds the signed ES256 JWT for the push service origin `audience`.
+    pub fn jwt(&self, audience: &str, expires_at: i64) -> String {
+        let header = URL_SAFE_NO_PAD.encode(br#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": expires_at,
+            "sub": self.subject,
+        });
+        let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signing_input = format!("{}.{}", header, claims);
+        let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+        format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    /// Value of the `Authorization` header for a push to `endpoint`.
+    pub fn authorization(&self, endpoint: &str) -> Option<String> {
+        let url = reqwest::Url::parse(endpoint).ok()?;
+        let audience = url.origin().ascii_serialization();
+        let expires_at = (Utc::now() + Duration::hours(JWT_LIFETIME_HOURS)).timestamp();
+        Some(format!(
+            "vapid t={}, k={}",
+            self.jwt(&audience, expires_at),
+            self.public_key()
+        ))
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+    pub tag: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SendOutcome {
+    Delivered,
+    /// The push service reported the subscription as expired (404/410).
+    Gone,
+    Failed(String),
+}
+
+pub struct WebPushClient {
+    http: reqwest::Client,
+    keys: VapidKeys,
+    allow_private: bool,
+}
+
+impl WebPushClient {
+    /// Like [`webhooks::WebhookClient`], refuses endpoints on non-public addresses unless
+    /// `allow_private` is set, since any guest can register an endpoint.
+    pub fn new(keys: VapidKeys, allow_private: bool) -> Self {
+        let mut builder = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private {
+            builder = builder.dns_resolver(Arc::new(webhooks::PublicResolver));
+        }
+        let http = builder.build().expect("Failed to build push HTTP client");
+        Self {
+            http,
+            keys,
+            allow_private,
+        }
+    }
+
+    pub fn public_key(&self) -> String {
+        self.keys.public_key()
+    }
+
+    pub async fn send(&self, subscription: &PushSubscription, message: &PushMessage) -> SendOutcome {
+        let payload = serde_json::to_vec(message).unwrap_or_default();
+        let body = match encrypt(&payload, &subscription.p256dh, &subscription.auth) {
+            Ok(body) => body,
+            Err(e) => return SendOutcome::Failed(e.to_string()),
+        };
+        let Some(authorization) = self.keys.authorization(&subscription.endpoint) else {
+            return SendOutcome::Failed("invalid endpoint".to_string());
+        };
+        if !webhooks::literal_host_allowed(&subscription.endpoint, self.allow_private) {
+            return SendOutcome::Failed("endpoint is not a public address".to_string());
+        }
+
+        let result = self
+            .http
+            .post(&subscription.endpoint)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .header(reqwest::header::CONTENT_ENCODING, "aes128gcm")
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", TTL_SECS.to_string())
+            .header("Urgency", "high")
+            .body(body)
+            .send()
+            .await;
+
+        match result {
+            Ok(res) if res.status().is_success() => SendOutcome::Delivered,
+            Ok(res) if matches!(res.status().as_u16(), 404 | 410) => SendOutcome::Gone,
+            Ok(res) => SendOutcome::Failed(format!("push service responded with {}", res.status())),
+            Err(e) => SendOutcome::Failed(format!("request failed: {}", e)),
+        }
+    }
+}
+
+/// Checks a browser subscription: an https endpoint and well-formed encryption keys.
+pub fn is_valid_subscription(endpoint: &str, p256dh: &str, auth: &str) -> bool {
+    let endpoint_ok = reqwest::Url::parse(endpoint)
+        .map(|u| u.scheme() == "https" && u.host_str().is_some())
+        .unwrap_or(false);
+    let p256dh_ok = decode_b64(p256dh, "p256dh")
+        .ok()
+        .and_then(|b| PublicKey::from_sec1_bytes(&b).ok())
+        .is_some();
+    let auth_ok = decode_b64(auth, "auth").map(|b| b.len() == 16).unwrap_or(false);
+    endpoint_ok && p256dh_ok && auth_ok
+}
+
+/// Stores (or refreshes) a browser push subscription for an event.
+pub fn subscribe(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    endpoint: &str,
+    p256dh: &str,
+    auth: &str,
+) -> QueryResult<PushSubscription> {
+    diesel::insert_into(push_subscriptions::table)
+        .values(&NewPushSubscription {
+            event_id,
+            endpoint,
+            p256dh,
+            auth,
+        })
+        .on_conflict((push_subscriptions::event_id, push_subscriptions::endpoint))
+        .do_update()
+        .set((
+            push_subscriptions::p256dh.eq(p256dh),
+            push_subscriptions::auth.eq(auth),
+        ))
+        .returning(PushSubscription::as_returning())
+        .get_result(conn)
+}
+
+pub fn unsubscribe(conn: &mut PgConnection, event_id: Uuid, endpoint: &str) -> QueryResult<usize> {
+    diesel::delete(
+        push_subscriptions::table
+            .filter(push_subscriptions::event_id.eq(event_id))
+            .filter(push_subscriptions::endpoint.eq(endpoint)),
+    )
+    .execute(conn)
+}
+
+fn event_url(base_url: &str, event: &Event) -> String {
+    format!("{}/event?key={}", base_url, event.event_key)
+}
+
+pub fn arrival_message(event: &Event, base_url: &str) -> PushMessage {
+    PushMessage {
+        title: "The baby has arrived!".to_string(),
+        body: format!("Results for \"{}\" are in. See who guessed closest!", event.title),
+        url: event_url(base_url, event),
+        tag: format!("event-ended-{}", event.id),
+    }
+}
+
+pub fn closing_soon_message(event: &Event, base_url: &str) -> PushMessage {
+    PushMessage {
+        title: "Guessing closes in 24h".to_string(),
+        body: format!("Last chance to change or share your guess for \"{}\".", event.title),
+        url: event_url(base_url, event),
+        tag: format!("closing-{}", event.id),
+    }
+}
+
+/// Sends `message` to every subscription of the event, pruning expired ones.
+/// Returns the number of successful deliveries.
+pub async fn notify_event(
+    pool: &DbPool,
+    client: &WebPushClient,
+    event_id: Uuid,
+    message: &PushMessage,
+) -> Result<usize, DbError> {
+    let subscriptions = db::run(pool, move |conn| {
+        push_subscriptions::table
+            .filter(push_subscriptions::event_id.eq(event_id))
+            .select(PushSubscription::as_select())
+            .load(conn)
+    })
+    .await?;
+
+    let mut delivered = 0;
+    let mut gone = Vec::new();
+    for subscription in &subscriptions {
+        match client.send(subscription, message).await {
+            SendOutcome::Delivered => delivered += 1,
+            SendOutcome::Gone => gone.push(subscription.id),
+            SendOutcome::Failed(e) => {
+                tracing::warn!("Push to subscription {} failed: {}", subscription.id, e)
+            }
+        }
+    }
+
+    if !gone.is_empty() {
+        db::run(pool, move |conn| {
+            diesel::delete(push_subscriptions::table.filter(push_subscriptions::id.eq_any(gone)))
+                .execute(conn)
+        })
+        .await?;
+    }
+
+    Ok(delivered)
+}
+
+/// Claims events whose guessing closes within the next 24 hours and have not been reminded yet.
+pub fn claim_closing_events(conn: &mut PgConnection) -> QueryResult<Vec<Event>> {
+    let now = Utc::now().naive_utc();
+    let window_end = now + Duration::hours(REMINDER_WINDOW_HOURS);
+
+    conn.transaction(|conn| {
+        let candidates = events::table
+            .filter(events::ended_at.is_null())
+            .filter(events::deleted_at.is_null())
+            .filter(events::push_reminder_sent_at.is_null())
+            .filter(
+                events::guess_close_date
+                    .between(now, window_end)
+                    .or(events::guess_close_date
+                        .is_null()
+                        .and(events::due_date.between(now - Duration::days(1), window_end))),
+            )
+            .select(Event::as_select())
+            .load(conn)?;
+
+        let closing: Vec<Event> = candidates
+            .into_iter()
+            .filter(|event| {
+                effective_guess_close_date(event)
+                    .is_some_and(|close| close > now && close <= window_end)
+            })
+            .collect();
+
+        let ids: Vec<Uuid> = closing.iter().map(|e| e.id).collect();
+        diesel::update(events::table.filter(events::id.eq_any(ids)))
+            .set(events::push_reminder_sent_at.eq(Some(now)))
+            .execute(conn)?;
+
+        Ok(closing)
+    })
+}
+
+async fn send_closing_reminders(pool: &DbPool, client: &WebPushClient, base_url: &str) {
+    let closing = match db::run(pool, claim_closing_events).await {
+        Ok(closing) => closing,
+        Err(e) => {
+            tracing::error!("Error loading events closing soon: {}", e);
+            return;
+        }
+    };
+
+    for event in closing {
+        let message = closing_soon_message(&event, base_url);
+        if let Err(e) = notify_event(pool, client, event.id, &message).await {
+            tracing::error!("Error sending closing reminder for {}: {}", event.id, e);
+        }
+    }
+}
+
+async fn send_arrival(pool: &DbPool, client: &WebPushClient, base_url: &str, event_id: Uuid) {
+    let event = match db::run(pool, move |conn| {
+        events::table
+            .find(event_id)
+            .select(Event::as_select())
+            .first(conn)
+    })
+    .await
+    {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::error!("Error loading ended event {}: {}", event_id, e);
+            return;
+        }
+    };
+
+    let message = arrival_message(&event, base_url);
+    match notify_event(pool, client, event_id, &message).await {
+        Ok(count) if count > 0 => tracing::info!("Sent {} arrival push notifications", count),
+        Ok(_) => {}
+        Err(e) => tracing::error!("Error sending arrival push for {}: {}", event_id, e),
+    }
+}
+
+/// Pushes arrival announcements as events end, and hourly "closes in 24h" reminders.
+pub async fn run_push_worker(
+    pool: DbPool,
+    client: Arc<WebPushClient>,
+    base_url: String,
+    mut rx: broadcast::Receiver<LiveUpdate>,
+    shutdown: Shutdown,
+) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+    loop {
+        tokio::select! {
+            _ = shutdown.wait() => break,
+            _ = interval.tick() => {
+                send_closing_reminders(&pool, &client, &base_url).await;
+            }
+            update = rx.recv() => match update {
+                Ok(LiveUpdate::EventEnded(ended)) => {
+                    send_arrival(&pool, &client, &base_url, ended.event_id).await;
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Push worker lagged, skipped {} updates", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+    tracing::info!("Push worker stopped");
+}
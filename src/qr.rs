@@ -0,0 +1,135 @@
+use qrcode::{EcLevel, QrCode};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::render::{self, RenderError};
+use crate::validation::Invalid;
+
+pub const DEFAULT_SIZE: u32 = 512;
+pub const MIN_SIZE: u32 = 128;
+pub const MAX_SIZE: u32 = 2048;
+/// Light border, in modules, that scanners need around the code.
+const QUIET_ZONE: usize = 4;
+
+/// Query parameters of the QR code endpoints: `size` in pixels and the error-correction
+/// level `ec` (`L`, `M`, `Q` or `H`).
+#[derive(Deserialize, Debug, Default)]
+pub struct QrQuery {
+    pub size: Option<u32>,
+    pub ec: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QrOptions {
+    pub size: u32,
+    pub ec_level: EcLevel,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_SIZE,
+            ec_level: EcLevel::M,
+        }
+    }
+}
+
+impl TryFrom<&QrQuery> for QrOptions {
+    type Error = Invalid;
+
+    /// Sizes are clamped to what prints well; an unknown level is an error, since silently
+    /// lowering it could make a code that doesn't survive a logo or a crease.
+    fn try_from(query: &QrQuery) -> Result<Self, Invalid> {
+        let ec_level = match query.ec.as_deref().map(str::to_ascii_uppercase).as_deref() {
+            None => EcLevel::M,
+            Some("L") => EcLevel::L,
+            Some("M") => EcLevel::M,
+            Some("Q") => EcLevel::Q,
+            Some("H") => EcLevel::H,
+            Some(_) => {
+                return Err(Invalid::new(
+                    "invalid_ec_level",
+                    "ec must be one of L, M, Q, H",
+                ));
+            }
+        };
+        Ok(Self {
+            size: query
+                .size
+                .map_or(DEFAULT_SIZE, |size| size.clamp(MIN_SIZE, MAX_SIZE)),
+            ec_level,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum QrError {
+    /// The data doesn't fit in a QR code at the requested level.
+    Encode(qrcode::types::QrError),
+    Render(RenderError),
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrError::Encode(e) => write!(f, "Failed to encode QR code: {}", e),
+            QrError::Render(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// The modules of an encoded QR code, for drawing it in any format.
+pub struct QrMatrix {
+    width: usize,
+    colors: Vec<qrcode::Color>,
+}
+
+impl QrMatrix {
+    pub fn new(data: &str, ec_level: EcLevel) -> Result<Self, QrError> {
+        let code = QrCode::with_error_correction_level(data, ec_level).map_err(QrError::Encode)?;
+        Ok(Self {
+            width: code.width(),
+            colors: code.to_colors(),
+        })
+    }
+
+    /// Side length in modules, including the quiet zone on both sides.
+    pub fn extent(&self) -> usize {
+        self.width + 2 * QUIET_ZONE
+    }
+
+    /// Column and row of every dark module, counted from the top-left corner of the quiet
+    /// zone.
+    pub fn dark_modules(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.colors
+            .iter()
+            .enumerate()
+            .filter(|(_, color)| **color == qrcode::Color::Dark)
+            .map(|(i, _)| (i % self.width + QUIET_ZONE, i / self.width + QUIET_ZONE))
+    }
+}
+
+/// Black-on-white QR code of `data`, `options.size` pixels square including the quiet zone.
+/// Modules are drawn as one path with crisp edges, so the PNG has no blurred module borders.
+pub fn to_svg(data: &str, options: QrOptions) -> Result<String, QrError> {
+    let matrix = QrMatrix::new(data, options.ec_level)?;
+    let modules = matrix.extent();
+    let size = options.size;
+
+    let mut path = String::new();
+    for (x, y) in matrix.dark_modules() {
+        path.push_str(&format!("M{x} {y}h1v1h-1z"));
+    }
+
+    Ok(format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges">
+<rect width="{modules}" height="{modules}" fill="#ffffff"/>
+<path d="{path}" fill="#000000"/>
+</svg>
+"##
+    ))
+}
+
+pub fn to_png(data: &str, options: QrOptions) -> Result<Vec<u8>, QrError> {
+    render::svg_to_png(&to_svg(data, options)?, 1.0).map_err(QrError::Render)
+}
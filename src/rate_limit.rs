@@ -0,0 +1,493 @@
+use axum::{
+    Json,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use futures::future::BoxFuture;
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{
+    db::{self, DbError},
+    schema::rate_limit_buckets,
+    shutdown::Shutdown,
+    types::{AppState, DbPool},
+};
+
+pub const LIMIT_HEADER: &str = "ratelimit-limit";
+pub const REMAINING_HEADER: &str = "ratelimit-remaining";
+pub const RESET_HEADER: &str = "ratelimit-reset";
+
+const DEFAULT_MAX_BUCKETS: usize = 100_000;
+
+/// A token bucket: `capacity` requests in a burst, refilled at `refill_per_sec`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitPolicy {
+    pub name: &'static str,
+    pub capacity: u32,
+    pub refill_per_sec: f64,
+}
+
+impl RateLimitPolicy {
+    /// Seconds an empty bucket takes to fill up again.
+    pub fn full_refill_secs(&self) -> f64 {
+        f64::from(self.capacity) / self.refill_per_sec
+    }
+}
+
+pub const SUBMIT_GUESS: RateLimitPolicy = RateLimitPolicy {
+    name: "submit_guess",
+    capacity: 5,
+    refill_per_sec: 10.0 / 60.0,
+};
+
+pub const CLAIM_EVENT: RateLimitPolicy = RateLimitPolicy {
+    name: "claim_event",
+    capacity: 3,
+    refill_per_sec: 6.0 / 60.0,
+};
+
+pub const SUBSCRIBE_PUSH: RateLimitPolicy = RateLimitPolicy {
+    name: "subscribe_push",
+    capacity: 5,
+    refill_per_sec: 10.0 / 60.0,
+};
+
+pub const UNLOCK_EVENT: RateLimitPolicy = RateLimitPolicy {
+    name: "unlock_event",
+    capacity: 5,
+    refill_per_sec: 10.0 / 60.0,
+};
+
+pub const POLICIES: &[&RateLimitPolicy] =
+    &[&SUBMIT_GUESS, &CLAIM_EVENT, &SUBSCRIBE_PUSH, &UNLOCK_EVENT];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreKind {
+    /// Per process.
+    Memory,
+    /// Shared by all instances through the `rate_limit_buckets` table.
+    Postgres,
+}
+
+impl std::str::FromStr for StoreKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "memory" => Ok(StoreKind::Memory),
+            "postgres" => Ok(StoreKind::Postgres),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Store selection and the policy applied to each limited route.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub store: StoreKind,
+    pub submit_guess: RateLimitPolicy,
+    pub claim_event: RateLimitPolicy,
+    pub subscribe_push: RateLimitPolicy,
+    pub unlock_event: RateLimitPolicy,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            store: StoreKind::Memory,
+            submit_guess: SUBMIT_GUESS,
+            claim_event: CLAIM_EVENT,
+            subscribe_push: SUBSCRIBE_PUSH,
+            unlock_event: UNLOCK_EVENT,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn policies(&self) -> Vec<RateLimitPolicy> {
+        vec![
+            self.submit_guess,
+            self.claim_event,
+            self.subscribe_push,
+            self.unlock_event,
+        ]
+    }
+
+    pub fn build_store(&self, pool: &DbPool) -> Arc<dyn RateLimitStore> {
+        match self.store {
+            StoreKind::Memory => Arc::new(MemoryStore::default()),
+            StoreKind::Postgres => {
+                Arc::new(PostgresStore::new(pool.clone()).with_policies(self.policies()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Whole seconds until the bucket is full again.
+    pub reset_secs: u64,
+    /// Whole seconds until the next request would be allowed, when denied.
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Refills `tokens` for `elapsed_secs`, takes one token if available and describes the result.
+/// Returns the new token count alongside the decision.
+pub fn take_token(
+    policy: &RateLimitPolicy,
+    tokens: f64,
+    elapsed_secs: f64,
+) -> (f64, RateLimitDecision) {
+    let capacity = f64::from(policy.capacity);
+    let mut tokens = (tokens + elapsed_secs.max(0.0) * policy.refill_per_sec).min(capacity);
+    let allowed = tokens >= 1.0;
+    if allowed {
+        tokens -= 1.0;
+    }
+
+    let decision = RateLimitDecision {
+        allowed,
+        limit: policy.capacity,
+        remaining: tokens.floor() as u32,
+        reset_secs: ((capacity - tokens) / policy.refill_per_sec).ceil() as u64,
+        retry_after_secs: (!allowed)
+            .then(|| ((1.0 - tokens) / policy.refill_per_sec).ceil().max(1.0) as u64),
+    };
+    (tokens, decision)
+}
+
+/// Backing storage for token buckets, keyed by (policy, client address).
+pub trait RateLimitStore: Send + Sync {
+    fn check<'a>(
+        &'a self,
+        policy: &'a RateLimitPolicy,
+        key: IpAddr,
+    ) -> BoxFuture<'a, Result<RateLimitDecision, DbError>>;
+
+    /// Drops buckets that have refilled completely and are indistinguishable from new ones.
+    fn purge_idle(&self) -> BoxFuture<'_, Result<usize, DbError>>;
+}
+
+type BucketKey = (&'static str, IpAddr);
+
+struct MemoryBucket {
+    tokens: f64,
+    last_refill: Instant,
+    full_at: Instant,
+    /// Tick of the last use; matches the bucket's newest entry in `MemoryBuckets::recency`.
+    used_at: u64,
+}
+
+/// Buckets with their order of use. Every use appends to `recency` and older entries of the
+/// same bucket go stale, so the least recently used bucket is found by popping stale entries
+/// off the front instead of scanning the map.
+#[derive(Default)]
+struct MemoryBuckets {
+    map: HashMap<BucketKey, MemoryBucket>,
+    recency: VecDeque<(BucketKey, u64)>,
+    tick: u64,
+}
+
+impl MemoryBuckets {
+    fn is_current(&self, (key, used_at): &(BucketKey, u64)) -> bool {
+        self.map
+            .get(key)
+            .is_some_and(|bucket| bucket.used_at == *used_at)
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        while let Some(entry) = self.recency.pop_front() {
+            if self.is_current(&entry) {
+                self.map.remove(&entry.0);
+                return;
+            }
+        }
+    }
+
+    /// Drops stale entries once they outnumber the buckets, keeping `recency` within twice
+    /// the size of the map.
+    fn compact(&mut self) {
+        if self.recency.len() > 2 * self.map.len().max(16) {
+            let recency = std::mem::take(&mut self.recency);
+            self.recency = recency
+                .into_iter()
+                .filter(|entry| self.is_current(entry))
+                .collect();
+        }
+    }
+}
+
+/// Per-process store. It never grows past `max_buckets`: a new client evicts the least
+/// recently used bucket, and `purge_idle` drops buckets that have refilled.
+pub struct MemoryStore {
+    buckets: Mutex<MemoryBuckets>,
+    max_buckets: usize,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BUCKETS)
+    }
+}
+
+impl MemoryStore {
+    pub fn new(max_buckets: usize) -> Self {
+        Self {
+            buckets: Mutex::new(MemoryBuckets::default()),
+            max_buckets: max_buckets.max(1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .map
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn check_now(&self, policy: &RateLimitPolicy, key: IpAddr) -> RateLimitDecision {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (policy.name, key);
+
+        if !buckets.map.contains_key(&key) && buckets.map.len() >= self.max_buckets {
+            buckets.evict_least_recently_used();
+        }
+
+        buckets.tick += 1;
+        let tick = buckets.tick;
+        let bucket = buckets.map.entry(key).or_insert_with(|| MemoryBucket {
+            tokens: f64::from(policy.capacity),
+            last_refill: now,
+            full_at: now,
+            used_at: tick,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        let (tokens, decision) = take_token(policy, bucket.tokens, elapsed);
+        bucket.tokens = tokens;
+        bucket.last_refill = now;
+        bucket.full_at = now
+            + Duration::from_secs_f64(
+                (f64::from(policy.capacity) - tokens).max(0.0) / policy.refill_per_sec,
+            );
+        bucket.used_at = tick;
+
+        buckets.recency.push_back((key, tick));
+        buckets.compact();
+        decision
+    }
+
+    fn purge_now(&self) -> usize {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let before = buckets.map.len();
+        buckets.map.retain(|_, bucket| bucket.full_at > now);
+        buckets.compact();
+        before - buckets.map.len()
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn check<'a>(
+        &'a self,
+        policy: &'a RateLimitPolicy,
+        key: IpAddr,
+    ) -> BoxFuture<'a, Result<RateLimitDecision, DbError>> {
+        Box::pin(async move { Ok(self.check_now(policy, key)) })
+    }
+
+    fn purge_idle(&self) -> BoxFuture<'_, Result<usize, DbError>> {
+        Box::pin(async move { Ok(self.purge_now()) })
+    }
+}
+
+/// Shares buckets between instances through the `rate_limit_buckets` table.
+pub struct PostgresStore {
+    pool: DbPool,
+    policies: Vec<RateLimitPolicy>,
+}
+
+impl PostgresStore {
+    /// A store purging buckets by the default policies.
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            pool,
+            policies: POLICIES.iter().map(|p| **p).collect(),
+        }
+    }
+
+    /// Purges idle buckets by the refill times of these policies instead.
+    pub fn with_policies(mut self, policies: Vec<RateLimitPolicy>) -> Self {
+        self.policies = policies;
+        self
+    }
+}
+
+fn check_in_db(
+    conn: &mut PgConnection,
+    policy: &RateLimitPolicy,
+    key: &str,
+) -> QueryResult<RateLimitDecision> {
+    conn.transaction(|conn| {
+        let now = Utc::now().naive_utc();
+
+        diesel::insert_into(rate_limit_buckets::table)
+            .values((
+                rate_limit_buckets::policy.eq(policy.name),
+                rate_limit_buckets::key.eq(key),
+                rate_limit_buckets::tokens.eq(f64::from(policy.capacity)),
+                rate_limit_buckets::updated_at.eq(now),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        let (tokens, updated_at): (f64, chrono::NaiveDateTime) = rate_limit_buckets::table
+            .find((policy.name, key))
+            .select((rate_limit_buckets::tokens, rate_limit_buckets::updated_at))
+            .for_update()
+            .first(conn)?;
+
+        let elapsed = (now - updated_at).num_milliseconds() as f64 / 1000.0;
+        let (tokens, decision) = take_token(policy, tokens, elapsed);
+
+        diesel::update(rate_limit_buckets::table.find((policy.name, key)))
+            .set((
+                rate_limit_buckets::tokens.eq(tokens),
+                rate_limit_buckets::updated_at.eq(now.max(updated_at)),
+            ))
+            .execute(conn)?;
+
+        Ok(decision)
+    })
+}
+
+impl RateLimitStore for PostgresStore {
+    fn check<'a>(
+        &'a self,
+        policy: &'a RateLimitPolicy,
+        key: IpAddr,
+    ) -> BoxFuture<'a, Result<RateLimitDecision, DbError>> {
+        let policy = *policy;
+        Box::pin(db::run(&self.pool, move |conn| {
+            check_in_db(conn, &policy, &key.to_string())
+        }))
+    }
+
+    fn purge_idle(&self) -> BoxFuture<'_, Result<usize, DbError>> {
+        let policies = self.policies.clone();
+        Box::pin(db::run(&self.pool, move |conn| {
+            let now = Utc::now().naive_utc();
+            let mut purged = 0;
+            for policy in &policies {
+                let idle_since = now
+                    - chrono::Duration::milliseconds(
+                        (policy.full_refill_secs() * 1000.0).ceil() as i64
+                    );
+                purged += diesel::delete(
+                    rate_limit_buckets::table
+                        .filter(rate_limit_buckets::policy.eq(policy.name))
+                        .filter(rate_limit_buckets::updated_at.lt(idle_since)),
+                )
+                .execute(conn)?;
+            }
+            Ok(purged)
+        }))
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    headers.insert(LIMIT_HEADER, HeaderValue::from(decision.limit));
+    headers.insert(REMAINING_HEADER, HeaderValue::from(decision.remaining));
+    headers.insert(RESET_HEADER, HeaderValue::from(decision.reset_secs));
+    if let Some(retry_after) = decision.retry_after_secs {
+        headers.insert(
+            axum::http::header::RETRY_AFTER,
+            HeaderValue::from(retry_after),
+        );
+    }
+}
+
+/// Route middleware state: which policy a route is limited by.
+#[derive(Clone)]
+pub struct Limited {
+    state: AppState,
+    policy: RateLimitPolicy,
+}
+
+impl Limited {
+    pub fn new(state: &AppState, policy: RateLimitPolicy) -> Self {
+        Self {
+            state: state.clone(),
+            policy,
+        }
+    }
+}
+
+/// Middleware applying a policy to the client address. Store failures let the request through.
+pub async fn enforce(
+    State(limited): State<Limited>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let state = &limited.state;
+    let ip = state.client_ip.resolve(request.headers(), peer);
+    let key = state.client_ip.rate_limit_key(ip);
+
+    let decision = match state.rate_limiter.check(&limited.policy, key).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            tracing::error!("Rate limit check for {} failed: {}", limited.policy.name, e);
+            return next.run(request).await;
+        }
+    };
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        state
+            .metrics
+            .rate_limited
+            .with_label_values(&[limited.policy.name])
+            .inc();
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(serde_json::json!({ "code": "rate_limited", "error": "Rate limit exceeded" })),
+        )
+            .into_response()
+    };
+    set_headers(response.headers_mut(), &decision);
+    response
+}
+
+pub async fn run_purge_task(store: Arc<dyn RateLimitStore>, shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10 * 60));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+        match store.purge_idle().await {
+            Ok(count) if count > 0 => tracing::debug!("Purged {} idle rate limit buckets", count),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Error purging rate limit buckets: {}", e),
+        }
+    }
+    tracing::info!("Rate limit purge task stopped");
+}
@@ -0,0 +1,79 @@
+use resvg::{tiny_skia, usvg};
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+
+pub const PNG_CONTENT_TYPE: &str = "image/png";
+pub const SVG_CONTENT_TYPE: &str = "image/svg+xml";
+
+/// Family name of the bundled font; SVGs rendered here should ask for it.
+pub const FONT_FAMILY: &str = "DejaVu Sans";
+
+/// Fonts are compiled in so images look the same on every host, including slim containers
+/// without any fonts installed.
+static FONTS: LazyLock<Arc<usvg::fontdb::Database>> = LazyLock::new(|| {
+    let mut db = usvg::fontdb::Database::new();
+    db.load_font_data(include_bytes!("../assets/fonts/DejaVuSans.ttf").to_vec());
+    db.load_font_data(include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf").to_vec());
+    db.set_sans_serif_family(FONT_FAMILY);
+    Arc::new(db)
+});
+
+#[derive(Debug)]
+pub enum RenderError {
+    Svg(usvg::Error),
+    /// The SVG has no area to draw on.
+    EmptyCanvas,
+    Png(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Svg(e) => write!(f, "Invalid SVG: {}", e),
+            RenderError::EmptyCanvas => write!(f, "SVG has an empty canvas"),
+            RenderError::Png(e) => write!(f, "Failed to encode PNG: {}", e),
+        }
+    }
+}
+
+/// Rasterizes an SVG at its own size, or scaled by `scale`.
+pub fn svg_to_png(svg: &str, scale: f32) -> Result<Vec<u8>, RenderError> {
+    let options = usvg::Options {
+        font_family: FONT_FAMILY.to_string(),
+        fontdb: FONTS.clone(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(RenderError::Svg)?;
+
+    let size = tree.size().to_int_size().scale_by(scale);
+    let size = size.ok_or(RenderError::EmptyCanvas)?;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).ok_or(RenderError::EmptyCanvas)?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    pixmap
+        .encode_png()
+        .map_err(|e| RenderError::Png(e.to_string()))
+}
+
+/// Escapes text for SVG element content and attribute values.
+pub fn xml_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0 and would fail the whole parse.
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            _ => out.push(ch),
+        }
+    }
+    out
+}
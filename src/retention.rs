@@ -0,0 +1,218 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::Timestamp;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::{
+    bundle::EventBundle,
+    config::Config,
+    db::{self, DbError},
+    models::{Event, LiveUpdate},
+    notifications,
+    schema::events,
+    types::DbPool,
+    webhooks,
+};
+
+/// When events are scheduled for deletion and purged. An event expires `retention` after it
+/// ended, or after its last activity (creation, a new invitee or guess, a restore) if it never
+/// did; it is then hidden and purged once `grace` has passed without the host restoring it.
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    pub retention: Duration,
+    pub grace: Duration,
+    pub export_dir: Option<PathBuf>,
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            retention: Duration::days(i64::from(config.retention_days)),
+            grace: Duration::days(i64::from(config.deletion_grace_days)),
+            export_dir: config.purge_export_dir.clone(),
+        }
+    }
+}
+
+/// An event removed for good, as recorded in the cleanup report.
+#[derive(Debug)]
+pub struct PurgedEvent {
+    pub id: Uuid,
+    pub title: String,
+    pub created_at: NaiveDateTime,
+    pub ended_at: Option<NaiveDateTime>,
+    pub invitees: usize,
+    pub guesses: usize,
+    pub export: Option<PathBuf>,
+}
+
+/// What one cleanup run did.
+#[derive(Debug, Default)]
+pub struct RetentionReport {
+    pub scheduled: Vec<Uuid>,
+    pub purged: Vec<PurgedEvent>,
+    /// Events kept past their grace window because they could not be exported.
+    pub export_failed: Vec<Uuid>,
+}
+
+impl RetentionReport {
+    pub fn log(&self) {
+        for id in &self.scheduled {
+            tracing::info!("Retention: scheduled event {} for deletion", id);
+        }
+        for event in &self.purged {
+            tracing::info!(
+                "Retention: purged event {} {:?} (created {}, ended {}, {} invitees, {} guesses{})",
+                event.id,
+                event.title,
+                event.created_at.format("%Y-%m-%d"),
+                event
+                    .ended_at
+                    .map(|at| at.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "never".to_string()),
+                event.invitees,
+                event.guesses,
+                event
+                    .export
+                    .as_ref()
+                    .map(|path| format!(", exported to {}", path.display()))
+                    .unwrap_or_default(),
+            );
+        }
+        for id in &self.export_failed {
+            tracing::warn!("Retention: event {} was not purged, its export failed", id);
+        }
+        if !self.scheduled.is_empty() || !self.purged.is_empty() {
+            tracing::info!(
+                "Retention: {} events scheduled for deletion, {} purged",
+                self.scheduled.len(),
+                self.purged.len()
+            );
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct ExpiredEvent {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    id: Uuid,
+}
+
+/// Hides every event that expired before `now`, and warns its hosts by email and its webhooks
+/// that it will be purged once the grace window has passed.
+pub fn schedule_expired(
+    conn: &mut PgConnection,
+    policy: &RetentionPolicy,
+    now: NaiveDateTime,
+    base_url: &str,
+) -> QueryResult<Vec<Uuid>> {
+    // GREATEST ignores NULLs, so events without invitees, guesses or restores still qualify.
+    let expired: Vec<ExpiredEvent> = diesel::sql_query(
+        "SELECT e.id FROM events e
+         WHERE e.deleted_at IS NULL
+           AND GREATEST(
+                 COALESCE(e.ended_at, GREATEST(
+                   e.created_at,
+                   (SELECT MAX(i.created_at) FROM invitees i WHERE i.event_id = e.id),
+                   (SELECT MAX(g.created_at) FROM guesses g
+                      JOIN invitees i ON i.id = g.invitee_id WHERE i.event_id = e.id))),
+                 e.restored_at
+               ) < $1",
+    )
+    .bind::<Timestamp, _>(now - policy.retention)
+    .load(conn)?;
+
+    let purge_after = now + policy.grace;
+    let mut scheduled = Vec::with_capacity(expired.len());
+    for ExpiredEvent { id } in expired {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let event = diesel::update(events::table.find(id))
+                .set(events::deleted_at.eq(Some(now)))
+                .returning(Event::as_returning())
+                .get_result(conn)?;
+
+            notifications::enqueue_deletion_warning(conn, &event, purge_after, base_url)?;
+            webhooks::enqueue_update(
+                conn,
+                &LiveUpdate::EventScheduledForDeletion {
+                    event_id: id,
+                    purge_after,
+                },
+            )?;
+            Ok(())
+        })?;
+        scheduled.push(id);
+    }
+
+    Ok(scheduled)
+}
+
+fn export(dir: &Path, event_id: Uuid, bundle: &EventBundle) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.json", event_id));
+    let json = serde_json::to_vec_pretty(bundle).map_err(std::io::Error::other)?;
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// Exports (if configured) and deletes every event whose grace window ended before `now`.
+pub fn purge_due(
+    conn: &mut PgConnection,
+    policy: &RetentionPolicy,
+    now: NaiveDateTime,
+    report: &mut RetentionReport,
+) -> QueryResult<()> {
+    let due = events::table
+        .filter(events::deleted_at.lt(now - policy.grace))
+        .select(Event::as_select())
+        .load(conn)?;
+
+    for event in due {
+        let bundle = EventBundle::export(conn, &event, now)?;
+        let export_path = match &policy.export_dir {
+            Some(dir) => match export(dir, event.id, &bundle) {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    tracing::error!("Failed to export event {}: {}", event.id, e);
+                    report.export_failed.push(event.id);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        diesel::delete(events::table.find(event.id)).execute(conn)?;
+        report.purged.push(PurgedEvent {
+            id: event.id,
+            title: event.title,
+            created_at: event.created_at,
+            ended_at: event.ended_at,
+            invitees: bundle.invitees.len(),
+            guesses: bundle.invitees.iter().map(|i| i.guesses.len()).sum(),
+            export: export_path,
+        });
+    }
+
+    Ok(())
+}
+
+/// One cleanup run: schedules newly expired events, then purges those past their grace window.
+pub async fn run(
+    pool: &DbPool,
+    policy: &RetentionPolicy,
+    base_url: &str,
+) -> Result<RetentionReport, DbError> {
+    let policy = policy.clone();
+    let base_url = base_url.to_string();
+    db::run(pool, move |conn| {
+        let now = Utc::now().naive_utc();
+        let mut report = RetentionReport {
+            scheduled: schedule_expired(conn, &policy, now, &base_url)?,
+            ..Default::default()
+        };
+        purge_due(conn, &policy, now, &mut report)?;
+        Ok(report)
+    })
+    .await
+}
@@ -1,5 +1,33 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    email_outbox (id) {
+        id -> Uuid,
+        subscription_id -> Uuid,
+        subject -> Varchar,
+        body -> Text,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        unsubscribe_url -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    email_subscriptions (id) {
+        id -> Uuid,
+        event_id -> Uuid,
+        invitee_id -> Nullable<Uuid>,
+        email -> Varchar,
+        role -> Varchar,
+        unsubscribe_token -> Varchar,
+        unsubscribed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     events (id) {
         id -> Uuid,
@@ -16,6 +44,18 @@ diesel::table! {
         birth_date -> Nullable<Timestamp>,
         birth_weight_kg -> Nullable<Float8>,
         ended_at -> Nullable<Timestamp>,
+        close_reminder_sent_at -> Nullable<Timestamp>,
+        push_reminder_sent_at -> Nullable<Timestamp>,
+        guess_pow_difficulty -> Int4,
+        deleted_at -> Nullable<Timestamp>,
+        restored_at -> Nullable<Timestamp>,
+        embed_origins -> Array<Text>,
+        language -> Nullable<Varchar>,
+        slug -> Nullable<Varchar>,
+        visibility -> Varchar,
+        passcode -> Nullable<Varchar>,
+        sealed_guesses -> Bool,
+        guesses_revealed_at -> Nullable<Timestamp>,
     }
 }
 
@@ -40,7 +80,68 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    push_subscriptions (id) {
+        id -> Uuid,
+        event_id -> Uuid,
+        endpoint -> Varchar,
+        p256dh -> Varchar,
+        auth -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    rate_limit_buckets (policy, key) {
+        policy -> Varchar,
+        key -> Varchar,
+        tokens -> Float8,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Uuid,
+        webhook_id -> Uuid,
+        event_type -> Varchar,
+        payload -> Text,
+        attempts -> Int4,
+        status_code -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Uuid,
+        event_id -> Uuid,
+        url -> Varchar,
+        secret -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(email_outbox -> email_subscriptions (subscription_id));
+diesel::joinable!(email_subscriptions -> events (event_id));
+diesel::joinable!(email_subscriptions -> invitees (invitee_id));
 diesel::joinable!(guesses -> invitees (invitee_id));
 diesel::joinable!(invitees -> events (event_id));
+diesel::joinable!(push_subscriptions -> events (event_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> events (event_id));
 
-diesel::allow_tables_to_appear_in_same_query!(events, guesses, invitees,);
+diesel::allow_tables_to_appear_in_same_query!(
+    email_outbox,
+    email_subscriptions,
+    events,
+    guesses,
+    invitees,
+    push_subscriptions,
+    rate_limit_buckets,
+    webhook_deliveries,
+    webhooks,
+);
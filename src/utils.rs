@@ -1,22 +1,27 @@
 use rand::Rng;
+use rand::distributions::Alphanumeric;
 
-/// Generates a random event key in the format XXXXXX-XXXXXX-XXXXXX
-pub fn generate_event_key() -> String {
-    let mut rng = rand::thread_rng();
-    let key: String = (0..18)
-        .map(|i| {
-            if i > 0 && i % 6 == 0 {
-                '-'
-            } else {
-                const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-                let idx = rng.gen_range(0..CHARSET.len());
-                CHARSET[idx] as char
-            }
-        })
-        .collect();
-    key
-}
+use crate::models::Event;
 
 pub fn generate_secret_key() -> String {
     petname::petname(3, "-").unwrap_or_else(|| "secret-key-fallback".to_string())
 }
+
+/// Generates an opaque, URL-safe token (e.g. for unsubscribe links)
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Guessing closes at `guess_close_date`, or at the end of the due date when unset.
+pub fn effective_guess_close_date(event: &Event) -> Option<chrono::NaiveDateTime> {
+    if let Some(close) = event.guess_close_date {
+        return Some(close);
+    }
+
+    let due = event.due_date?;
+    chrono::NaiveTime::from_hms_opt(23, 59, 59).map(|t| due.date().and_time(t))
+}
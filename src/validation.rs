@@ -0,0 +1,270 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fmt;
+
+use crate::config::WeightLimits;
+use crate::event_keys::{self, MAX_SLUG_CHARS, MIN_SLUG_CHARS};
+use crate::i18n::Locale;
+use crate::pow;
+use crate::viewer::{MAX_PASSCODE_CHARS, MIN_PASSCODE_CHARS, Visibility};
+
+/// A rejected value: a stable `code` that clients and the translation catalogs key on, and
+/// the English message reported with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invalid {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Invalid {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Proof-of-work difficulty a host may require for guesses. Like every check here, the error
+/// is what gets reported to the client.
+pub fn guess_pow_difficulty(difficulty: u8) -> Result<u8, Invalid> {
+    if difficulty > pow::MAX_EVENT_DIFFICULTY {
+        return Err(Invalid::new(
+            "pow_difficulty_too_high",
+            format!(
+                "guess_pow_difficulty must be at most {}",
+                pow::MAX_EVENT_DIFFICULTY
+            ),
+        ));
+    }
+    Ok(difficulty)
+}
+
+fn due_date_required() -> Invalid {
+    Invalid::new("due_date_required", "due_date is required")
+}
+
+/// Due date and guessing deadline of a new event: both after `today`, the deadline no later
+/// than the due date.
+pub fn event_dates(
+    due_date: Option<NaiveDateTime>,
+    guess_close_date: Option<NaiveDateTime>,
+    today: NaiveDate,
+) -> Result<NaiveDateTime, Invalid> {
+    let due_date = due_date.ok_or_else(due_date_required)?;
+    if due_date.date() <= today {
+        return Err(Invalid::new(
+            "due_date_in_past",
+            "due_date must be after today",
+        ));
+    }
+
+    if let Some(close_date) = guess_close_date
+        && close_date.date() <= today
+    {
+        return Err(Invalid::new(
+            "guess_close_date_in_past",
+            "guess_close_date must be after today",
+        ));
+    }
+
+    event_date_order(Some(due_date), guess_close_date)
+}
+
+/// The part of [`event_dates`] that still holds once the dates have passed.
+pub fn event_date_order(
+    due_date: Option<NaiveDateTime>,
+    guess_close_date: Option<NaiveDateTime>,
+) -> Result<NaiveDateTime, Invalid> {
+    let due_date = due_date.ok_or_else(due_date_required)?;
+    if let Some(close_date) = guess_close_date
+        && close_date.date() > due_date.date()
+    {
+        return Err(Invalid::new(
+            "guess_close_date_after_due_date",
+            "guess_close_date must be on or before due_date",
+        ));
+    }
+    Ok(due_date)
+}
+
+/// Resolves an event's weight range: defaults fill the gaps and the hard limits clamp it.
+pub fn weight_range(
+    limits: &WeightLimits,
+    min_weight_kg: Option<f64>,
+    max_weight_kg: Option<f64>,
+) -> Result<(f64, f64), Invalid> {
+    let min_weight_kg = min_weight_kg.unwrap_or(limits.default_min_kg);
+    let max_weight_kg = max_weight_kg.unwrap_or(limits.default_max_kg);
+
+    if !min_weight_kg.is_finite() || !max_weight_kg.is_finite() {
+        return Err(Invalid::new(
+            "weight_not_finite",
+            "min_weight_kg and max_weight_kg must be finite",
+        ));
+    }
+
+    let min_weight_kg = min_weight_kg.clamp(limits.hard_min_kg, limits.hard_max_kg);
+    let max_weight_kg = max_weight_kg.clamp(limits.hard_min_kg, limits.hard_max_kg);
+
+    if max_weight_kg <= min_weight_kg {
+        return Err(Invalid::new(
+            "weight_range_empty",
+            "max_weight_kg must be greater than min_weight_kg",
+        ));
+    }
+    Ok((min_weight_kg, max_weight_kg))
+}
+
+/// Whether a guessed weight lies within the event's range.
+pub fn guessed_weight_in_range(weight_kg: f64, min_weight_kg: f64, max_weight_kg: f64) -> bool {
+    weight_kg.is_finite() && weight_kg >= min_weight_kg && weight_kg <= max_weight_kg
+}
+
+/// How many sites a host may allow to embed an event.
+pub const MAX_EMBED_ORIGINS: usize = 10;
+
+/// Sites allowed to frame an event's embed widget, normalized to `scheme://host[:port]`.
+pub fn embed_origins(origins: &[String]) -> Result<Vec<String>, Invalid> {
+    if origins.len() > MAX_EMBED_ORIGINS {
+        return Err(Invalid::new(
+            "too_many_embed_origins",
+            format!("embed_origins may list at most {} sites", MAX_EMBED_ORIGINS),
+        ));
+    }
+
+    let mut normalized: Vec<String> = Vec::with_capacity(origins.len());
+    for origin in origins {
+        let invalid = || {
+            Invalid::new(
+                "invalid_embed_origin",
+                format!("Invalid embed origin {:?}", origin),
+            )
+        };
+        let url = reqwest::Url::parse(origin.trim()).map_err(|_| invalid())?;
+        let is_bare_origin = matches!(url.scheme(), "http" | "https")
+            && url.host_str().is_some()
+            && url.username().is_empty()
+            && url.password().is_none()
+            && url.path() == "/"
+            && url.query().is_none()
+            && url.fragment().is_none();
+        if !is_bare_origin {
+            return Err(invalid());
+        }
+
+        let origin = url.origin().ascii_serialization();
+        if !normalized.contains(&origin) {
+            normalized.push(origin);
+        }
+    }
+    Ok(normalized)
+}
+
+/// An event's language: a tag such as `fi` or `fi-FI` for a language the server has a catalog
+/// for, stored as its primary code. Blank clears it.
+pub fn event_language(language: Option<&str>) -> Result<Option<String>, Invalid> {
+    let Some(language) = language.map(str::trim).filter(|l| !l.is_empty()) else {
+        return Ok(None);
+    };
+    Locale::from_code(language)
+        .map(|locale| Some(locale.code().to_string()))
+        .ok_or_else(|| {
+            Invalid::new(
+                "unsupported_language",
+                format!(
+                    "language must be one of {}",
+                    Locale::ALL.map(Locale::code).join(", ")
+                ),
+            )
+        })
+}
+
+/// A vanity slug such as `baby-virtanen`: lower-case letters, digits and single dashes, not a
+/// reserved page name and free of blocked words. Stored in lower case; blank clears it.
+pub fn event_slug(slug: Option<&str>) -> Result<Option<String>, Invalid> {
+    let Some(slug) = slug.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let slug = slug.to_ascii_lowercase();
+
+    let length = slug.chars().count();
+    if !(MIN_SLUG_CHARS..=MAX_SLUG_CHARS).contains(&length) {
+        return Err(Invalid::new(
+            "slug_length",
+            format!(
+                "slug must be {} to {} characters long",
+                MIN_SLUG_CHARS, MAX_SLUG_CHARS
+            ),
+        ));
+    }
+    let well_formed = slug
+        .split('-')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+        && slug.chars().any(|c| c.is_ascii_lowercase());
+    if !well_formed {
+        return Err(Invalid::new(
+            "slug_malformed",
+            "slug may only contain letters, digits and single dashes, with at least one letter",
+        ));
+    }
+    if event_keys::is_reserved_slug(&slug) {
+        return Err(Invalid::new(
+            "slug_reserved",
+            format!("slug {:?} is reserved", slug),
+        ));
+    }
+    if event_keys::contains_blocked_word(&slug) {
+        return Err(Invalid::new("slug_blocked", "slug contains a blocked word"));
+    }
+    Ok(Some(slug))
+}
+
+/// An event's visibility and passcode. Passcode-protected events need a passcode; the others
+/// drop it. A blank visibility means public.
+pub fn event_visibility(
+    visibility: Option<&str>,
+    passcode: Option<&str>,
+) -> Result<(Visibility, Option<String>), Invalid> {
+    let visibility = match visibility.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(visibility) => Visibility::parse(visibility).ok_or_else(|| {
+            Invalid::new(
+                "invalid_visibility",
+                format!(
+                    "visibility must be one of {}",
+                    Visibility::ALL.map(Visibility::as_str).join(", ")
+                ),
+            )
+        })?,
+        None => Visibility::Public,
+    };
+    if visibility != Visibility::Passcode {
+        return Ok((visibility, None));
+    }
+
+    let passcode = passcode
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| {
+            Invalid::new(
+                "passcode_missing",
+                "passcode is required for passcode-protected events",
+            )
+        })?;
+    let length = passcode.chars().count();
+    if !(MIN_PASSCODE_CHARS..=MAX_PASSCODE_CHARS).contains(&length) {
+        return Err(Invalid::new(
+            "passcode_length",
+            format!(
+                "passcode must be {} to {} characters long",
+                MIN_PASSCODE_CHARS, MAX_PASSCODE_CHARS
+            ),
+        ));
+    }
+    Ok((visibility, Some(passcode.to_string())))
+}
@@ -0,0 +1,351 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::convert::Infallible;
+use std::fmt;
+use tokio::sync::broadcast;
+
+use crate::{
+    db::{self, DbError},
+    guess_table,
+    models::{Event, LiveUpdate},
+    schema::events,
+    shutdown::Shutdown,
+    types::DbPool,
+    utils::effective_guess_close_date,
+    webhooks,
+};
+
+const TOKEN_VERSION: &str = "v1";
+/// Long enough to last from the baby shower to the birth announcement.
+const DEFAULT_TTL_SECS: i64 = 180 * 24 * 60 * 60;
+pub const MIN_PASSCODE_CHARS: usize = 4;
+pub const MAX_PASSCODE_CHARS: usize = 64;
+/// Header that carries a viewer token; `EventSource` can't set headers, so `?token=` works too.
+pub const TOKEN_HEADER: &str = "x-viewer-token";
+
+/// Who may see an event and its guesses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    /// Anyone with the event key.
+    #[default]
+    Public,
+    /// Guests with a viewer token from the host-set passcode.
+    Passcode,
+    /// Anyone sees the event, but its guesses only once guessing closes.
+    HiddenGuesses,
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 3] = [
+        Visibility::Public,
+        Visibility::Passcode,
+        Visibility::HiddenGuesses,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Passcode => "passcode",
+            Visibility::HiddenGuesses => "hidden_guesses",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Visibility::ALL
+            .into_iter()
+            .find(|visibility| visibility.as_str() == value)
+    }
+
+    /// The event's stored visibility; the column's check constraint keeps it one of ours.
+    pub fn of(event: &Event) -> Self {
+        Visibility::parse(&event.visibility).unwrap_or_default()
+    }
+}
+
+/// Whether guessing has closed, so hidden and sealed guesses may be shown.
+pub fn guesses_revealed(event: &Event, now: NaiveDateTime) -> bool {
+    event.ended_at.is_some() || effective_guess_close_date(event).is_some_and(|close| now > close)
+}
+
+/// Who is reading an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Viewer {
+    /// Sent the event's secret key.
+    Host,
+    /// Sent a valid viewer token.
+    Unlocked,
+    Guest,
+}
+
+/// What a viewer may see of an event right now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Full,
+    /// The event and who guessed, but not the guessed dates and weights.
+    Sealed,
+    /// The event, but not its guesses.
+    EventOnly,
+    /// Nothing until the passcode is entered.
+    Locked,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessDenied {
+    PasscodeRequired,
+    GuessesHidden,
+    GuessesSealed,
+}
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessDenied::PasscodeRequired => write!(f, "Passcode required"),
+            AccessDenied::GuessesHidden => write!(f, "Guesses are hidden until guessing closes"),
+            AccessDenied::GuessesSealed => write!(f, "Guesses are sealed until guessing closes"),
+        }
+    }
+}
+
+impl AccessDenied {
+    /// Stable identifier reported to clients next to the message.
+    pub fn code(self) -> &'static str {
+        match self {
+            AccessDenied::PasscodeRequired => "passcode_required",
+            AccessDenied::GuessesHidden => "guesses_hidden",
+            AccessDenied::GuessesSealed => "guesses_sealed",
+        }
+    }
+}
+
+impl Access {
+    pub fn new(event: &Event, viewer: Viewer, now: NaiveDateTime) -> Self {
+        let revealed = guesses_revealed(event, now);
+        match (Visibility::of(event), viewer) {
+            (_, Viewer::Host) => Access::Full,
+            (Visibility::Passcode, Viewer::Guest) => Access::Locked,
+            (Visibility::HiddenGuesses, _) if !revealed => Access::EventOnly,
+            _ if event.sealed_guesses && !revealed => Access::Sealed,
+            _ => Access::Full,
+        }
+    }
+
+    /// Allows reading the event itself: its details, share card, QR code and live stream.
+    pub fn event(self) -> Result<(), AccessDenied> {
+        match self {
+            Access::Locked => Err(AccessDenied::PasscodeRequired),
+            Access::Full | Access::Sealed | Access::EventOnly => Ok(()),
+        }
+    }
+
+    /// Allows reading the guesses: the list, downloads, chart and printables.
+    pub fn guesses(self) -> Result<(), AccessDenied> {
+        match self {
+            Access::Locked => Err(AccessDenied::PasscodeRequired),
+            Access::EventOnly => Err(AccessDenied::GuessesHidden),
+            Access::Sealed => Err(AccessDenied::GuessesSealed),
+            Access::Full => Ok(()),
+        }
+    }
+}
+
+/// Claims sealed and hidden-guesses events whose guessing has closed and whose guesses have
+/// not been broadcast yet, queueing their webhook deliveries and returning the updates to
+/// broadcast.
+pub fn claim_closed_events(
+    conn: &mut PgConnection,
+    now: NaiveDateTime,
+) -> QueryResult<Vec<LiveUpdate>> {
+    conn.transaction(|conn| {
+        let candidates = events::table
+            .filter(events::deleted_at.is_null())
+            .filter(events::guesses_revealed_at.is_null())
+            .filter(
+                events::sealed_guesses
+                    .eq(true)
+                    .or(events::visibility.eq(Visibility::HiddenGuesses.as_str())),
+            )
+            .filter(
+                events::ended_at
+                    .is_not_null()
+                    .or(events::guess_close_date.lt(now))
+                    .or(events::guess_close_date
+                        .is_null()
+                        .and(events::due_date.lt(now))),
+            )
+            .select(Event::as_select())
+            .load(conn)?;
+
+        let closed: Vec<Event> = candidates
+            .into_iter()
+            .filter(|event| guesses_revealed(event, now))
+            .collect();
+
+        let ids: Vec<_> = closed.iter().map(|event| event.id).collect();
+        diesel::update(events::table.filter(events::id.eq_any(ids)))
+            .set(events::guesses_revealed_at.eq(Some(now)))
+            .execute(conn)?;
+
+        closed
+            .into_iter()
+            .map(|event| {
+                let rows = guess_table::load(conn, event.id)?;
+                let update = LiveUpdate::GuessesRevealed {
+                    event_id: event.id,
+                    guesses: rows.iter().map(guess_table::GuessRow::to_point).collect(),
+                };
+                webhooks::enqueue_update(conn, &update)?;
+                Ok(update)
+            })
+            .collect()
+    })
+}
+
+/// Broadcasts every guess of the events whose guessing has closed since the last run, so open
+/// pages of sealed and hidden-guesses events fill in. Returns how many events were revealed.
+pub async fn reveal_closed_guesses(
+    pool: &DbPool,
+    tx: &broadcast::Sender<LiveUpdate>,
+) -> Result<usize, DbError> {
+    let now = Utc::now().naive_utc();
+    let closed = db::run(pool, move |conn| claim_closed_events(conn, now)).await?;
+    let revealed = closed.len();
+    for update in closed {
+        let _ = tx.send(update);
+    }
+    Ok(revealed)
+}
+
+/// Checks every minute for events whose sealed or hidden guesses are due to be revealed.
+pub async fn run_reveal_task(pool: DbPool, tx: broadcast::Sender<LiveUpdate>, shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+        match reveal_closed_guesses(&pool, &tx).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Revealed the guesses of {} events", count),
+            Err(e) => tracing::error!("Error revealing closed guesses: {}", e),
+        }
+    }
+    tracing::info!("Reveal task stopped");
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct IssuedToken {
+    pub token: String,
+    pub expires_at: i64,
+}
+
+/// Issues and verifies viewer tokens for passcode-protected events.
+///
+/// A token is `v1.<event id>.<expires>.<hmac>`. The passcode is part of the signed data
+/// without being in the token, so changing the passcode revokes every token issued for it.
+pub struct ViewerTokens {
+    key: Vec<u8>,
+    ttl_secs: i64,
+}
+
+impl ViewerTokens {
+    pub fn new(key: &[u8], ttl_secs: i64) -> Self {
+        Self {
+            key: key.to_vec(),
+            ttl_secs,
+        }
+    }
+
+    /// Signs with `secret`, or with a random key when there is none, so tokens are only valid
+    /// on the instance that issued them.
+    pub fn from_secret(secret: Option<&str>) -> Self {
+        let key = match secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                let mut key = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+        Self::new(&key, DEFAULT_TTL_SECS)
+    }
+
+    fn mac(&self, unsigned: &str, event: &Event) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(unsigned.as_bytes());
+        mac.update(&[0]);
+        mac.update(event.passcode.as_deref().unwrap_or_default().as_bytes());
+        mac
+    }
+
+    pub fn issue(&self, event: &Event) -> IssuedToken {
+        let expires_at = Utc::now().timestamp() + self.ttl_secs;
+        let unsigned = format!("{}.{}.{}", TOKEN_VERSION, event.id, expires_at);
+        let signature = hex::encode(self.mac(&unsigned, event).finalize().into_bytes());
+
+        IssuedToken {
+            token: format!("{}.{}", unsigned, signature),
+            expires_at,
+        }
+    }
+
+    /// Whether `token` was issued for this event and its current passcode and hasn't expired.
+    pub fn verify(&self, event: &Event, token: &str) -> bool {
+        let Some((unsigned, signature)) = token.rsplit_once('.') else {
+            return false;
+        };
+        let parts: Vec<&str> = unsigned.split('.').collect();
+        let [version, event_id, expires_at] = parts[..] else {
+            return false;
+        };
+        if version != TOKEN_VERSION || event_id != event.id.to_string() {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        if self.mac(unsigned, event).verify_slice(&signature).is_err() {
+            return false;
+        }
+        expires_at
+            .parse::<i64>()
+            .is_ok_and(|expires_at| Utc::now().timestamp() <= expires_at)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// The viewer token of a request, from the `X-Viewer-Token` header or the `token` query
+/// parameter. Whether it is valid depends on the event, so handlers check it with
+/// [`ViewerTokens::verify`].
+#[derive(Debug, Default, Clone)]
+pub struct ViewerToken(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for ViewerToken {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get(TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let token = header.or_else(|| {
+            Query::<TokenQuery>::try_from_uri(&parts.uri)
+                .ok()
+                .and_then(|Query(query)| query.token)
+        });
+        Ok(ViewerToken(token.filter(|token| !token.is_empty())))
+    }
+}
@@ -0,0 +1,380 @@
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    db::{self, DbError},
+    models::{LiveUpdate, NewWebhook, NewWebhookDelivery, Webhook, WebhookDelivery},
+    schema::{webhook_deliveries, webhooks},
+    shutdown::Shutdown,
+    types::DbPool,
+    utils::generate_token,
+};
+
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const EVENT_TYPE_HEADER: &str = "x-webhook-event";
+pub const DELIVERY_ID_HEADER: &str = "x-webhook-delivery";
+
+const MAX_WEBHOOKS_PER_EVENT: i64 = 10;
+const MAX_ATTEMPTS: i32 = 8;
+const DELIVERY_BATCH_SIZE: i64 = 50;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug)]
+pub enum RegisterError {
+    InvalidUrl,
+    /// The host is, or resolves to, an address webhooks may not reach.
+    PrivateTarget,
+    TooMany,
+    Db(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for RegisterError {
+    fn from(e: diesel::result::Error) -> Self {
+        RegisterError::Db(e)
+    }
+}
+
+/// Only absolute http(s) URLs are accepted as webhook targets.
+pub fn is_valid_url(url: &str) -> bool {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => matches!(parsed.scheme(), "http" | "https") && parsed.host().is_some(),
+        Err(_) => false,
+    }
+}
+
+/// Whether an address is on the public internet. Loopback, private, link-local, CGNAT,
+/// unique-local and other special-purpose ranges would let a webhook probe the server's own
+/// network.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_v4(v4);
+            }
+            let segments = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7 unique local
+                || (segments[0] & 0xfe00) == 0xfc00
+                // fe80::/10 link-local
+                || (segments[0] & 0xffc0) == 0xfe80
+                // 64:ff9b::/96 NAT64, which reaches IPv4 addresses
+                || (segments[0] == 0x64 && segments[1] == 0xff9b)
+                // 2001:db8::/32 documentation
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8 "this network"
+        || a == 0
+        // 100.64.0.0/10 carrier-grade NAT
+        || (a == 100 && (b & 0xc0) == 64)
+        // 192.0.0.0/24 protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 benchmarking
+        || (a == 198 && (b & 0xfe) == 18)
+        // 240.0.0.0/4 reserved
+        || a >= 240)
+}
+
+/// The host of `url` when it is an IP address rather than a name.
+fn literal_ip(url: &reqwest::Url) -> Option<IpAddr> {
+    url.host_str()?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// Resolves the URL's host and checks every address, so a webhook can't be pointed at the
+/// server's own network. Run at registration; deliveries check again through [`WebhookClient`].
+pub async fn check_target(url: &str, allow_private: bool) -> Result<(), RegisterError> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| RegisterError::InvalidUrl)?;
+    if allow_private {
+        return Ok(());
+    }
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = match (literal_ip(&parsed), parsed.host_str()) {
+        (Some(ip), _) => vec![SocketAddr::new(ip, port)],
+        (None, Some(host)) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| RegisterError::InvalidUrl)?
+            .collect(),
+        (None, None) => return Err(RegisterError::InvalidUrl),
+    };
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public_address(addr.ip())) {
+        return Err(RegisterError::PrivateTarget);
+    }
+    Ok(())
+}
+
+/// Literal IP hosts skip DNS, so [`PublicResolver`] can't filter them.
+pub(crate) fn literal_host_allowed(url: &str, allow_private: bool) -> bool {
+    if allow_private {
+        return true;
+    }
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| literal_ip(&url))
+        .is_none_or(is_public_address)
+}
+
+/// DNS for deliveries that only hands out public addresses, so a name that resolved to a
+/// public address at registration can't later be pointed inwards.
+pub(crate) struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_address(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+pub fn register(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    url: &str,
+) -> Result<Webhook, RegisterError> {
+    if !is_valid_url(url) {
+        return Err(RegisterError::InvalidUrl);
+    }
+
+    let existing: i64 = webhooks::table
+        .filter(webhooks::event_id.eq(event_id))
+        .count()
+        .get_result(conn)?;
+    if existing >= MAX_WEBHOOKS_PER_EVENT {
+        return Err(RegisterError::TooMany);
+    }
+
+    let secret = generate_token();
+    let webhook = diesel::insert_into(webhooks::table)
+        .values(&NewWebhook {
+            event_id,
+            url,
+            secret: &secret,
+        })
+        .returning(Webhook::as_returning())
+        .get_result(conn)?;
+
+    Ok(webhook)
+}
+
+/// Computes the hex HMAC-SHA256 signature over `"{timestamp}.{body}"`.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queues a delivery of `update` for every webhook registered on its event. Call it in the
+/// transaction that makes the change, so a delivery is queued exactly when the change commits.
+pub fn enqueue_update(conn: &mut PgConnection, update: &LiveUpdate) -> QueryResult<usize> {
+    let targets = webhooks::table
+        .filter(webhooks::event_id.eq(update.event_id()))
+        .select(webhooks::id)
+        .load::<Uuid>(conn)?;
+
+    if targets.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = serde_json::to_string(update).unwrap_or_default();
+    let rows: Vec<NewWebhookDelivery> = targets
+        .iter()
+        .map(|webhook_id| NewWebhookDelivery {
+            webhook_id: *webhook_id,
+            event_type: update.kind(),
+            payload: &payload,
+        })
+        .collect();
+
+    diesel::insert_into(webhook_deliveries::table)
+        .values(&rows)
+        .execute(conn)
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let seconds = 30i64 << attempts.clamp(0, 10);
+    Duration::seconds(seconds.min(6 * 60 * 60))
+}
+
+fn load_due(conn: &mut PgConnection) -> QueryResult<Vec<(WebhookDelivery, Webhook)>> {
+    let now = Utc::now().naive_utc();
+
+    webhook_deliveries::table
+        .inner_join(webhooks::table)
+        .filter(webhook_deliveries::delivered_at.is_null())
+        .filter(webhook_deliveries::attempts.lt(MAX_ATTEMPTS))
+        .filter(webhook_deliveries::next_attempt_at.le(now))
+        .order(webhook_deliveries::created_at.asc())
+        .limit(DELIVERY_BATCH_SIZE)
+        .select((WebhookDelivery::as_select(), Webhook::as_select()))
+        .load(conn)
+}
+
+struct Attempt {
+    status_code: Option<i32>,
+    error: Option<String>,
+}
+
+fn record_attempt(
+    conn: &mut PgConnection,
+    delivery: &WebhookDelivery,
+    attempt: &Attempt,
+) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    let target = webhook_deliveries::table.find(delivery.id);
+
+    let delivered_at = attempt.error.is_none().then_some(now);
+    let next_attempt_at = if attempt.error.is_some() {
+        now + retry_delay(delivery.attempts)
+    } else {
+        delivery.next_attempt_at
+    };
+
+    diesel::update(target)
+        .set((
+            webhook_deliveries::attempts.eq(delivery.attempts + 1),
+            webhook_deliveries::status_code.eq(attempt.status_code),
+            webhook_deliveries::last_error.eq(attempt.error.clone()),
+            webhook_deliveries::delivered_at.eq(delivered_at),
+            webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
+#[derive(Clone)]
+pub struct WebhookClient {
+    http: reqwest::Client,
+    allow_private: bool,
+}
+
+impl WebhookClient {
+    /// A client that refuses non-public addresses unless `allow_private` is set (see
+    /// [`is_public_address`]). Redirects are never followed.
+    pub fn new(allow_private: bool) -> Self {
+        let mut builder = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        let http = builder
+            .build()
+            .expect("Failed to build webhook HTTP client");
+        Self {
+            http,
+            allow_private,
+        }
+    }
+
+    async fn post(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> Attempt {
+        if !literal_host_allowed(&webhook.url, self.allow_private) {
+            return Attempt {
+                status_code: None,
+                error: Some("Target is not a public address".to_string()),
+            };
+        }
+
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(&webhook.secret, timestamp, &delivery.payload);
+
+        let result = self
+            .http
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(EVENT_TYPE_HEADER, &delivery.event_type)
+            .header(DELIVERY_ID_HEADER, delivery.id.to_string())
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+
+        match result {
+            Ok(res) if res.status().is_success() => Attempt {
+                status_code: Some(res.status().as_u16() as i32),
+                error: None,
+            },
+            Ok(res) => Attempt {
+                status_code: Some(res.status().as_u16() as i32),
+                error: Some(format!("Receiver responded with {}", res.status())),
+            },
+            Err(e) => Attempt {
+                status_code: None,
+                error: Some(format!("Request failed: {}", e)),
+            },
+        }
+    }
+}
+
+/// Attempts every due delivery once. Returns the number of successful deliveries.
+pub async fn deliver_pending(pool: &DbPool, client: &WebhookClient) -> Result<usize, DbError> {
+    let due = db::run(pool, load_due).await?;
+
+    let mut delivered = 0;
+    for (delivery, webhook) in due {
+        let attempt = client.post(&webhook, &delivery).await;
+        match &attempt.error {
+            None => delivered += 1,
+            Some(e) => tracing::warn!(
+                "Webhook delivery {} to {} failed (attempt {}): {}",
+                delivery.id,
+                webhook.url,
+                delivery.attempts + 1,
+                e
+            ),
+        }
+
+        db::run(pool, move |conn| record_attempt(conn, &delivery, &attempt)).await?;
+    }
+
+    Ok(delivered)
+}
+
+pub async fn run_webhook_worker(pool: DbPool, client: WebhookClient, shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+    loop {
+        // A batch in progress is finished before stopping
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+        if let Err(e) = deliver_pending(&pool, &client).await {
+            tracing::error!("Error delivering webhooks: {}", e);
+        }
+    }
+    tracing::info!("Webhook worker stopped");
+}
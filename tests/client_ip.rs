@@ -0,0 +1,149 @@
+use axum::http::{HeaderMap, HeaderValue};
+use baby_birth_guessr::client_ip::{ClientIpResolver, parse_networks};
+use std::net::{IpAddr, SocketAddr};
+
+fn resolver(trusted: &str) -> ClientIpResolver {
+    ClientIpResolver::new(parse_networks(trusted).unwrap(), 64)
+}
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.append(*name, HeaderValue::from_str(value).unwrap());
+    }
+    headers
+}
+
+fn peer(ip: &str) -> SocketAddr {
+    SocketAddr::new(ip.parse().unwrap(), 40000)
+}
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn untrusted_peer_cannot_spoof_forwarded_for() {
+    let resolver = resolver("127.0.0.1/32");
+    let h = headers(&[("x-forwarded-for", "1.2.3.4")]);
+
+    assert_eq!(resolver.resolve(&h, peer("203.0.113.9")), ip("203.0.113.9"));
+}
+
+#[test]
+fn x_forwarded_for_is_walked_right_to_left() {
+    let resolver = resolver("127.0.0.1, 10.0.0.0/8");
+    // The client prepended a fake address; the real one was appended by the first proxy.
+    let h = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.7, 10.0.0.5")]);
+
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("198.51.100.7"));
+}
+
+#[test]
+fn multiple_x_forwarded_for_headers_are_concatenated() {
+    let resolver = resolver("127.0.0.1, 10.0.0.0/8");
+    let h = headers(&[
+        ("x-forwarded-for", "198.51.100.7"),
+        ("x-forwarded-for", "10.0.0.5"),
+    ]);
+
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("198.51.100.7"));
+}
+
+#[test]
+fn all_trusted_chain_resolves_to_leftmost_hop() {
+    let resolver = resolver("127.0.0.1, 10.0.0.0/8");
+    let h = headers(&[("x-forwarded-for", "10.1.1.1, 10.0.0.5")]);
+
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("10.1.1.1"));
+}
+
+#[test]
+fn garbage_hop_stops_the_walk() {
+    let resolver = resolver("127.0.0.1, 10.0.0.0/8");
+    let h = headers(&[("x-forwarded-for", "198.51.100.7, not-an-ip, 10.0.0.5")]);
+
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("10.0.0.5"));
+}
+
+#[test]
+fn missing_headers_fall_back_to_peer() {
+    let resolver = resolver("127.0.0.1");
+
+    assert_eq!(
+        resolver.resolve(&HeaderMap::new(), peer("127.0.0.1")),
+        ip("127.0.0.1")
+    );
+}
+
+#[test]
+fn forwarded_header_takes_precedence() {
+    let resolver = resolver("127.0.0.1, 10.0.0.0/8");
+    let h = headers(&[
+        (
+            "forwarded",
+            "for=192.0.2.60;proto=http;by=203.0.113.43, for=\"[2001:db8:cafe::17]:4711\", For=10.0.0.5",
+        ),
+        ("x-forwarded-for", "1.2.3.4"),
+    ]);
+
+    assert_eq!(
+        resolver.resolve(&h, peer("127.0.0.1")),
+        ip("2001:db8:cafe::17")
+    );
+}
+
+#[test]
+fn forwarded_header_ports_and_obfuscated_nodes() {
+    let resolver = resolver("127.0.0.1, 10.0.0.0/8");
+
+    let h = headers(&[("forwarded", "for=\"198.51.100.7:8080\"")]);
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("198.51.100.7"));
+
+    let h = headers(&[("forwarded", "for=198.51.100.7, for=_hidden, for=10.0.0.5")]);
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("10.0.0.5"));
+
+    let h = headers(&[("forwarded", "for=unknown")]);
+    assert_eq!(resolver.resolve(&h, peer("127.0.0.1")), ip("127.0.0.1"));
+}
+
+#[test]
+fn ipv4_mapped_addresses_are_normalised() {
+    let resolver = resolver("127.0.0.1");
+    let h = headers(&[("x-forwarded-for", "::ffff:198.51.100.7")]);
+
+    assert_eq!(resolver.resolve(&h, peer("::ffff:127.0.0.1")), ip("198.51.100.7"));
+}
+
+#[test]
+fn ipv6_rate_limit_key_aggregates_by_prefix() {
+    let resolver = resolver("127.0.0.1");
+
+    assert_eq!(
+        resolver.rate_limit_key(ip("2001:db8:1:2:aaaa:bbbb:cccc:dddd")),
+        ip("2001:db8:1:2::")
+    );
+    assert_eq!(
+        resolver.rate_limit_key(ip("2001:db8:1:2::1")),
+        resolver.rate_limit_key(ip("2001:db8:1:2:ffff::1"))
+    );
+    assert_ne!(
+        resolver.rate_limit_key(ip("2001:db8:1:2::1")),
+        resolver.rate_limit_key(ip("2001:db8:1:3::1"))
+    );
+    assert_eq!(resolver.rate_limit_key(ip("198.51.100.7")), ip("198.51.100.7"));
+
+    let wide = ClientIpResolver::new(Vec::new(), 48);
+    assert_eq!(
+        wide.rate_limit_key(ip("2001:db8:1:2::1")),
+        ip("2001:db8:1::")
+    );
+}
+
+#[test]
+fn trusted_proxy_list_is_validated() {
+    assert!(parse_networks("10.0.0.0/8, ::1, 192.168.1.1").is_ok());
+    assert!(parse_networks("10.0.0.0/33").is_err());
+    assert!(parse_networks("example.com").is_err());
+    assert!(parse_networks("").unwrap().is_empty());
+}
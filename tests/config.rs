@@ -0,0 +1,411 @@
+use baby_birth_guessr::captcha::CaptchaProvider;
+use baby_birth_guessr::config::{AppEnv, Config, ConfigError};
+use baby_birth_guessr::rate_limit::StoreKind;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn load(file: Option<&str>, env: &[(&str, &str)]) -> Result<Config, ConfigError> {
+    let env: HashMap<String, String> = env
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Config::from_sources(file, &move |name| env.get(name).cloned())
+}
+
+fn rejected(result: Result<Config, ConfigError>) -> Vec<String> {
+    match result {
+        Ok(_) => panic!("expected the configuration to be rejected"),
+        Err(e) => e.problems,
+    }
+}
+
+const DB: (&str, &str) = ("DATABASE_URL", "postgres://localhost/guessr");
+const VIEWER: (&str, &str) = ("VIEWER_TOKEN_SECRET", "viewer-secret");
+
+#[test]
+fn defaults_apply_when_only_the_database_is_set() {
+    let config = load(None, &[DB, VIEWER, ("TURNSTILE_SECRET_KEY", "secret")]).unwrap();
+
+    assert_eq!(config.app_env, AppEnv::Production);
+    assert_eq!(config.host, "0.0.0.0");
+    assert_eq!(config.port, 3000);
+    assert_eq!(config.shutdown_timeout, Duration::from_secs(8));
+    assert_eq!(config.public_base_url, None);
+    assert_eq!(config.retention_days, 365);
+    assert_eq!(config.deletion_grace_days, 30);
+    assert_eq!(config.purge_export_dir, None);
+    assert_eq!(config.broadcast_capacity, 100);
+    assert_eq!(config.event_keys.chars(), 12);
+    assert_eq!(config.weights.hard_max_kg, 8.0);
+    assert_eq!(config.rate_limits.store, StoreKind::Memory);
+    assert_eq!(config.rate_limits.submit_guess.capacity, 5);
+    assert_eq!(config.http.cors_allowed_origins, None);
+    assert!(matches!(
+        config.captcha.provider,
+        CaptchaProvider::Turnstile { ref secret_key } if secret_key == "secret"
+    ));
+}
+
+#[test]
+fn environment_overrides_the_file() {
+    let file = r#"
+        app_env = "test"
+
+        [database]
+        url = "postgres://file/guessr"
+        pool_max_size = 4
+
+        [server]
+        port = 8080
+        public_base_url = "https://guessr.example.com/"
+
+        [http]
+        cors_allowed_origins = ["https://a.example.com", "https://b.example.com"]
+
+        [events]
+        retention_days = 30
+        deletion_grace_days = 7
+        purge_export_dir = "/var/lib/guessr/purged"
+        broadcast_capacity = 500
+
+        [weights]
+        hard_max_kg = 7.0
+
+        [rate_limits]
+        store = "postgres"
+
+        [rate_limits.claim_event]
+        burst = 10
+        per_minute = 30
+    "#;
+    let config = load(
+        Some(file),
+        &[("PORT", "9090"), ("EVENT_RETENTION_DAYS", "")],
+    )
+    .unwrap();
+
+    assert_eq!(config.app_env, AppEnv::Test);
+    assert_eq!(config.database_url, "postgres://file/guessr");
+    assert_eq!(config.database.max_size, 4);
+    assert_eq!(config.port, 9090);
+    assert_eq!(
+        config.public_base_url.as_deref(),
+        Some("https://guessr.example.com")
+    );
+    assert_eq!(
+        config.http.cors_allowed_origins,
+        Some(vec![
+            "https://a.example.com".to_string(),
+            "https://b.example.com".to_string()
+        ])
+    );
+    // A blank variable counts as unset.
+    assert_eq!(config.retention_days, 30);
+    assert_eq!(config.deletion_grace_days, 7);
+    assert_eq!(
+        config.purge_export_dir.as_deref(),
+        Some(std::path::Path::new("/var/lib/guessr/purged"))
+    );
+    assert_eq!(config.broadcast_capacity, 500);
+    assert_eq!(config.weights.hard_max_kg, 7.0);
+    assert_eq!(config.rate_limits.store, StoreKind::Postgres);
+    assert_eq!(config.rate_limits.claim_event.capacity, 10);
+    assert_eq!(config.rate_limits.claim_event.refill_per_sec, 0.5);
+    assert!(matches!(config.captcha.provider, CaptchaProvider::None));
+}
+
+#[test]
+fn every_problem_is_reported_by_name() {
+    let problems = rejected(load(
+        None,
+        &[
+            ("APP_ENV", "test"),
+            ("PORT", "eighty"),
+            ("BROADCAST_CAPACITY", "0"),
+            ("DB_CONNECTION_TIMEOUT_SECS", "0"),
+            ("RATE_LIMIT_SUBMIT_GUESS_PER_MINUTE", "-1"),
+            ("TRUSTED_PROXIES", "10.0.0.0/33"),
+            ("PUBLIC_BASE_URL", "guessr.example.com"),
+        ],
+    ));
+
+    for name in [
+        "DATABASE_URL",
+        "PORT",
+        "BROADCAST_CAPACITY",
+        "DB_CONNECTION_TIMEOUT_SECS",
+        "RATE_LIMIT_SUBMIT_GUESS_PER_MINUTE",
+        "TRUSTED_PROXIES",
+        "PUBLIC_BASE_URL",
+    ] {
+        assert!(
+            problems.iter().any(|p| p.starts_with(name)),
+            "no problem reported for {}: {:?}",
+            name,
+            problems
+        );
+    }
+    assert_eq!(problems.len(), 7, "{:?}", problems);
+}
+
+#[test]
+fn weight_defaults_must_lie_within_the_hard_limits() {
+    let problems = rejected(load(
+        None,
+        &[DB, ("APP_ENV", "test"), ("WEIGHT_HARD_MIN_KG", "2.0")],
+    ));
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("default_min_kg"), "{:?}", problems);
+
+    let problems = rejected(load(
+        None,
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("WEIGHT_HARD_MIN_KG", "6"),
+            ("WEIGHT_HARD_MAX_KG", "3"),
+        ],
+    ));
+    assert_eq!(problems.len(), 1);
+    assert!(
+        problems[0].contains("hard_min_kg < hard_max_kg"),
+        "{:?}",
+        problems
+    );
+}
+
+#[test]
+fn captcha_secrets_are_required_for_hosted_providers() {
+    let problems = rejected(load(None, &[DB, VIEWER]));
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("TURNSTILE_SECRET_KEY"));
+
+    let problems = rejected(load(None, &[DB, VIEWER, ("CAPTCHA_PROVIDER", "hcaptcha")]));
+    assert!(problems[0].starts_with("HCAPTCHA_SECRET_KEY"));
+
+    // The frontend can't render the hCaptcha widget without its site key.
+    let hcaptcha = [
+        DB,
+        VIEWER,
+        ("CAPTCHA_PROVIDER", "hcaptcha"),
+        ("HCAPTCHA_SECRET_KEY", "secret"),
+    ];
+    let problems = rejected(load(None, &hcaptcha));
+    assert!(problems[0].starts_with("CAPTCHA_SITE_KEY"));
+    let config = load(
+        None,
+        &[&hcaptcha[..], &[("CAPTCHA_SITE_KEY", "site")]].concat(),
+    )
+    .unwrap();
+    assert_eq!(config.captcha.site_key.as_deref(), Some("site"));
+
+    let config = load(
+        None,
+        &[
+            DB,
+            VIEWER,
+            ("CAPTCHA_PROVIDER", "pow"),
+            ("CAPTCHA_ON_GUESS", "1"),
+        ],
+    )
+    .unwrap();
+    assert!(matches!(config.captcha.provider, CaptchaProvider::Pow));
+    assert!(config.captcha.on_guess);
+}
+
+#[test]
+fn unknown_and_malformed_file_settings_are_rejected() {
+    let problems = rejected(load(
+        Some("[server]\nprot = 3000\n"),
+        &[DB, ("APP_ENV", "test")],
+    ));
+    assert_eq!(
+        problems,
+        vec![r#"Config file: unknown setting "server.prot""#]
+    );
+
+    let problems = rejected(load(Some("port = "), &[DB, ("APP_ENV", "test")]));
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("Config file:"));
+
+    let problems = rejected(load(
+        Some("[server]\nport = \"http\"\n"),
+        &[DB, ("APP_ENV", "test")],
+    ));
+    assert_eq!(
+        problems,
+        vec![r#"PORT (server.port): expected a port number, got "http""#]
+    );
+}
+
+#[test]
+fn mail_push_and_proof_of_work_settings_are_validated() {
+    let config = load(
+        Some("[smtp]\nhost = \"smtp.example.com\"\nstarttls = false\n\n[pow]\ndifficulty = 20\n"),
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("SMTP_FROM", "Guessr <noreply@example.com>"),
+        ],
+    )
+    .unwrap_or_else(|e| panic!("{}", e));
+    let smtp = config.smtp.unwrap();
+    assert_eq!(smtp.host, "smtp.example.com");
+    assert_eq!(smtp.port, 25);
+    assert_eq!(config.pow.difficulty, 20);
+    assert!(config.vapid_keys.is_none());
+
+    let problems = rejected(load(
+        None,
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("SMTP_HOST", "smtp.example.com"),
+            ("SMTP_FROM", "not an address"),
+            ("SMTP_USERNAME", "guessr"),
+            ("VAPID_PRIVATE_KEY", "not-a-key"),
+            ("POW_DIFFICULTY", "40"),
+        ],
+    ));
+    let names: Vec<&str> = problems
+        .iter()
+        .map(|p| p.split(' ').next().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "SMTP_FROM",
+            "SMTP_USERNAME",
+            "VAPID_PRIVATE_KEY",
+            "POW_DIFFICULTY"
+        ],
+        "{:?}",
+        problems
+    );
+}
+
+#[test]
+fn durations_are_converted() {
+    let config = load(
+        None,
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("REQUEST_TIMEOUT_SECS", "5"),
+            ("DB_STATEMENT_TIMEOUT_MS", "1500"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(config.http.request_timeout, Duration::from_secs(5));
+    assert_eq!(
+        config.database.statement_timeout,
+        Duration::from_millis(1500)
+    );
+}
+
+#[test]
+fn example_file_matches_the_defaults() {
+    let example = load(
+        Some(include_str!("../config.example.toml")),
+        &[VIEWER, ("TURNSTILE_SECRET_KEY", "secret")],
+    )
+    .unwrap();
+    let defaults = load(None, &[DB, VIEWER, ("TURNSTILE_SECRET_KEY", "secret")]).unwrap();
+
+    assert_eq!(example.port, defaults.port);
+    assert_eq!(example.shutdown_timeout, defaults.shutdown_timeout);
+    assert_eq!(example.weights, defaults.weights);
+    assert_eq!(example.retention_days, defaults.retention_days);
+    assert_eq!(example.deletion_grace_days, defaults.deletion_grace_days);
+    assert_eq!(example.broadcast_capacity, defaults.broadcast_capacity);
+    assert_eq!(example.event_keys, defaults.event_keys);
+    assert_eq!(example.trusted_proxies, defaults.trusted_proxies);
+    assert_eq!(example.pow.difficulty, defaults.pow.difficulty);
+    assert_eq!(
+        example.rate_limits.policies(),
+        defaults.rate_limits.policies()
+    );
+}
+
+#[test]
+fn event_keys_must_stay_long_enough_to_be_unguessable() {
+    let config = load(
+        None,
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("EVENT_KEY_GROUPS", "2"),
+            ("EVENT_KEY_GROUP_LENGTH", "5"),
+        ],
+    )
+    .unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(config.event_keys.chars(), 10);
+
+    let problems = rejected(load(
+        None,
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("EVENT_KEY_GROUPS", "2"),
+            ("EVENT_KEY_GROUP_LENGTH", "3"),
+        ],
+    ));
+    assert_eq!(
+        problems,
+        vec![
+            "EVENT_KEY_GROUP_LENGTH (event_keys.group_length): keys must have at least 8 characters, got 2 groups of 3"
+        ]
+    );
+    let problems = rejected(load(
+        None,
+        &[DB, ("APP_ENV", "test"), ("EVENT_KEY_GROUPS", "0")],
+    ));
+    assert_eq!(
+        problems,
+        vec!["EVENT_KEY_GROUPS (event_keys.groups): must be between 1 and 6"]
+    );
+}
+
+#[test]
+fn viewer_token_secret_is_required_in_production() {
+    let problems = rejected(load(None, &[DB, ("TURNSTILE_SECRET_KEY", "secret")]));
+    assert_eq!(
+        problems,
+        vec!["VIEWER_TOKEN_SECRET (viewer_tokens.secret): must be set in production"]
+    );
+
+    let config = load(
+        Some("[viewer_tokens]\nsecret = \"from-file\"\n"),
+        &[DB, ("TURNSTILE_SECRET_KEY", "secret")],
+    )
+    .unwrap();
+    assert_eq!(config.viewer_token_secret.as_deref(), Some("from-file"));
+
+    let config = load(None, &[DB, ("APP_ENV", "test")]).unwrap();
+    assert_eq!(config.viewer_token_secret, None);
+}
+
+#[test]
+fn a_cors_wildcard_must_stand_alone() {
+    let config = load(
+        None,
+        &[DB, ("APP_ENV", "test"), ("CORS_ALLOWED_ORIGINS", " * ")],
+    )
+    .unwrap();
+    assert_eq!(config.http.cors_allowed_origins, Some(Vec::new()));
+
+    let problems = rejected(load(
+        None,
+        &[
+            DB,
+            ("APP_ENV", "test"),
+            ("CORS_ALLOWED_ORIGINS", "https://a.example.com,*"),
+        ],
+    ));
+    assert_eq!(
+        problems,
+        vec![
+            "CORS_ALLOWED_ORIGINS (http.cors_allowed_origins): * allows every origin and can't be combined with a list"
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+use baby_birth_guessr::event_keys::{KEY_ALPHABET, KeyFormat, contains_blocked_word, lookup_forms};
+use baby_birth_guessr::validation::{Invalid, event_slug};
+
+#[test]
+fn generated_keys_follow_the_format_without_ambiguous_characters() {
+    let format = KeyFormat {
+        groups: 2,
+        group_length: 5,
+    };
+    for _ in 0..200 {
+        let key = format.generate();
+        let groups: Vec<&str> = key.split('-').collect();
+        assert_eq!(groups.len(), 2, "{}", key);
+        assert!(groups.iter().all(|group| group.len() == 5), "{}", key);
+        assert!(
+            key.bytes()
+                .filter(|b| *b != b'-')
+                .all(|b| KEY_ALPHABET.contains(&b)),
+            "{}",
+            key
+        );
+    }
+    for ambiguous in [b'0', b'O', b'1', b'I'] {
+        assert!(!KEY_ALPHABET.contains(&ambiguous));
+    }
+    assert_eq!(KeyFormat::default().generate().len(), 14);
+}
+
+#[test]
+fn typed_keys_are_normalized_for_lookup() {
+    assert_eq!(
+        lookup_forms(" k7qm 3xrt  9hpa "),
+        ("K7QM-3XRT-9HPA".to_string(), "k7qm-3xrt-9hpa".to_string())
+    );
+    assert_eq!(
+        lookup_forms("Baby-Virtanen"),
+        ("BABY-VIRTANEN".to_string(), "baby-virtanen".to_string())
+    );
+}
+
+#[test]
+fn blocked_words_are_caught_in_disguise_but_names_pass() {
+    assert!(contains_blocked_word("CUNT-3XRT-9HPA"));
+    assert!(contains_blocked_word("baby-5h1t"));
+    assert!(contains_blocked_word("f-u-c-k"));
+    assert!(contains_blocked_word("mybastardbaby"));
+    assert!(!contains_blocked_word("baby-virtanen"));
+    assert!(!contains_blocked_word("cassandra-2030"));
+    assert!(!contains_blocked_word("K7QM-3XRT-9HPA"));
+}
+
+#[test]
+fn vanity_slugs_are_validated_and_lower_cased() {
+    assert_eq!(
+        event_slug(Some(" Baby-Virtanen ")),
+        Ok(Some("baby-virtanen".to_string()))
+    );
+    assert_eq!(event_slug(Some("  ")), Ok(None));
+    assert_eq!(event_slug(None), Ok(None));
+
+    for bad in [
+        "ab",
+        "baby--virtanen",
+        "-baby",
+        "baby_virtanen",
+        "vauva-ä",
+        "2030",
+    ] {
+        assert!(event_slug(Some(bad)).is_err(), "{} should be rejected", bad);
+    }
+    assert!(event_slug(Some(&"a".repeat(49))).is_err());
+    assert_eq!(
+        event_slug(Some("Share")),
+        Err(Invalid::new("slug_reserved", "slug \"share\" is reserved"))
+    );
+    assert_eq!(
+        event_slug(Some("baby-paska")),
+        Err(Invalid::new("slug_blocked", "slug contains a blocked word"))
+    );
+}
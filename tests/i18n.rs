@@ -0,0 +1,161 @@
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    http::{Request, StatusCode, header},
+    middleware,
+    routing::get,
+};
+use baby_birth_guessr::i18n::{self, Locale};
+use chrono::NaiveDate;
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+#[test]
+fn every_catalog_translates_every_english_key() {
+    let english = Locale::En.keys();
+    for locale in Locale::ALL {
+        let keys = locale.keys();
+        for key in &english {
+            assert!(keys.contains(key), "{} is missing {}", locale.code(), key);
+        }
+    }
+}
+
+#[test]
+fn accept_language_picks_the_most_preferred_supported_language() {
+    assert_eq!(Locale::from_accept_language("fi-FI"), Some(Locale::Fi));
+    assert_eq!(
+        Locale::from_accept_language("de;q=0.9, fi;q=0.5, en;q=0.7"),
+        Some(Locale::En)
+    );
+    assert_eq!(
+        Locale::from_accept_language("en;q=0, fi;q=0.1"),
+        Some(Locale::Fi)
+    );
+    assert_eq!(Locale::from_accept_language("de, sv"), None);
+    assert_eq!(Locale::from_code("FI_fi"), Some(Locale::Fi));
+    assert_eq!(Locale::from_code(""), None);
+}
+
+#[test]
+fn formats_dates_weights_and_counts_per_locale() {
+    let date = NaiveDate::from_ymd_opt(2030, 1, 5)
+        .unwrap()
+        .and_hms_opt(14, 30, 0)
+        .unwrap();
+
+    assert_eq!(Locale::En.long_date(date), "5 January 2030");
+    assert_eq!(Locale::Fi.long_date(date), "5.1.2030");
+    assert_eq!(Locale::Fi.datetime(date), "5.1.2030 klo 14.30");
+    assert_eq!(Locale::En.weight(3.254), "3.25 kg");
+    assert_eq!(Locale::Fi.weight(3.254), "3,25 kg");
+    assert_eq!(Locale::En.guesses(1), "1 guess");
+    assert_eq!(Locale::Fi.guesses(3), "3 arvausta");
+}
+
+#[test]
+fn translates_error_codes_with_values_from_the_english_message() {
+    assert_eq!(
+        Locale::Fi
+            .translate_error("event_not_found", "Event not found")
+            .as_deref(),
+        Some("Tapahtumaa ei löytynyt")
+    );
+    assert_eq!(
+        Locale::Fi
+            .translate_error(
+                "invalid_embed_origin",
+                "Invalid embed origin \"ftp://example.com\""
+            )
+            .as_deref(),
+        Some("Virheellinen upotuksen alkuperä \"ftp://example.com\"")
+    );
+    // The code, not the wording, picks the translation.
+    assert_eq!(
+        Locale::Fi
+            .translate_error("event_not_found", "No such event")
+            .as_deref(),
+        Some("Tapahtumaa ei löytynyt")
+    );
+    assert_eq!(
+        Locale::Fi.translate_error("invalid_embed_origin", "Something else"),
+        None
+    );
+    assert_eq!(
+        Locale::Fi.translate_error("something_unexpected", "Something unexpected"),
+        None
+    );
+}
+
+/// Answers `/small` with a translatable error, and `/sized` and `/streamed` with error bodies
+/// far larger than any error message, with and without a known length.
+fn error_app(large: Bytes) -> Router {
+    let sized = large.clone();
+    Router::new()
+        .route(
+            "/small",
+            get(move || async move {
+                (
+                    StatusCode::NOT_FOUND,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    r#"{"code":"event_not_found","error":"Event not found"}"#,
+                )
+            }),
+        )
+        .route(
+            "/sized",
+            get(move || async move {
+                (
+                    StatusCode::BAD_REQUEST,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    sized,
+                )
+            }),
+        )
+        .route(
+            "/streamed",
+            get(move || async move {
+                let chunks = large
+                    .chunks(4096)
+                    .map(|chunk| Ok::<_, std::io::Error>(Bytes::copy_from_slice(chunk)))
+                    .collect::<Vec<_>>();
+                (
+                    StatusCode::BAD_REQUEST,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Body::from_stream(futures::stream::iter(chunks)),
+                )
+            }),
+        )
+        .layer(middleware::from_fn(i18n::localize_errors))
+}
+
+async fn get_in_finnish(app: &Router, uri: &str) -> (StatusCode, Bytes) {
+    let req = Request::builder()
+        .uri(uri)
+        .header(header::ACCEPT_LANGUAGE, "fi")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    let status = res.status();
+    (status, res.into_body().collect().await.unwrap().to_bytes())
+}
+
+#[tokio::test]
+async fn large_error_bodies_pass_through_untouched() {
+    let large = Bytes::from(format!(
+        r#"{{"code":"event_not_found","error":"Event not found","detail":"{}"}}"#,
+        "x".repeat(200 * 1024)
+    ));
+    let app = error_app(large.clone());
+
+    let (status, body) = get_in_finnish(&app, "/small").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "Tapahtumaa ei löytynyt");
+
+    for uri in ["/sized", "/streamed"] {
+        let (status, body) = get_in_finnish(&app, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, large, "{}", uri);
+    }
+}
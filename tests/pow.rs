@@ -0,0 +1,54 @@
+use baby_birth_guessr::pow::{PowError, ProofOfWork, leading_zero_bits, solve};
+
+#[test]
+fn counts_leading_zero_bits() {
+    assert_eq!(leading_zero_bits(&[0xff]), 0);
+    assert_eq!(leading_zero_bits(&[0x00, 0x0f]), 12);
+    assert_eq!(leading_zero_bits(&[0x00, 0x00]), 16);
+}
+
+#[test]
+fn solved_challenge_verifies_once() {
+    let pow = ProofOfWork::new(b"key", 10, 60);
+    let challenge = pow.issue();
+    let solution = solve(&challenge.challenge, challenge.difficulty);
+
+    assert_eq!(pow.verify(&solution), Ok(()));
+    assert_eq!(pow.verify(&solution), Err(PowError::AlreadyUsed));
+}
+
+#[test]
+fn rejects_forged_and_expired_challenges() {
+    let pow = ProofOfWork::new(b"key", 4, 60);
+
+    let other = ProofOfWork::new(b"other-key", 4, 60).issue();
+    assert_eq!(
+        pow.verify(&solve(&other.challenge, 4)),
+        Err(PowError::BadSignature)
+    );
+
+    // Lowering the difficulty invalidates the signature.
+    let challenge = pow.issue().challenge;
+    let tampered = challenge.replacen(".4.", ".0.", 1);
+    assert_eq!(pow.verify(&solve(&tampered, 0)), Err(PowError::BadSignature));
+
+    let expired = ProofOfWork::new(b"key", 4, -1).issue();
+    assert_eq!(pow.verify(&solve(&expired.challenge, 4)), Err(PowError::Expired));
+
+    assert_eq!(pow.verify("garbage"), Err(PowError::Malformed));
+}
+
+#[test]
+fn scoped_challenges_only_verify_for_their_scope() {
+    let pow = ProofOfWork::new(b"key", 4, 60);
+    let challenge = pow.issue_scoped("event-a", 6);
+    let solution = solve(&challenge.challenge, challenge.difficulty);
+
+    assert_eq!(pow.verify_scoped("event-b", 6, &solution), Err(PowError::WrongScope));
+    assert_eq!(pow.verify(&solution), Err(PowError::WrongScope));
+    assert_eq!(
+        pow.verify_scoped("event-a", 8, &solution),
+        Err(PowError::InsufficientWork)
+    );
+    assert_eq!(pow.verify_scoped("event-a", 6, &solution), Ok(()));
+}
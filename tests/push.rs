@@ -0,0 +1,259 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use axum::http::{HeaderMap, StatusCode};
+use baby_birth_guessr::models::PushSubscription;
+use baby_birth_guessr::push::{self, PushMessage, SendOutcome, VapidKeys, WebPushClient};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hkdf::Hkdf;
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use sha2::Sha256;
+use std::sync::{Arc, Mutex};
+
+fn b64(input: &str) -> Vec<u8> {
+    URL_SAFE_NO_PAD.decode(input).unwrap()
+}
+
+/// Receiver side of RFC 8291, used to check what the sender produced.
+fn decrypt(body: &[u8], ua_secret: &SecretKey, auth: &[u8]) -> Vec<u8> {
+    let salt = &body[..16];
+    let id_len = body[20] as usize;
+    let as_public = PublicKey::from_sec1_bytes(&body[21..21 + id_len]).unwrap();
+    let ciphertext = &body[21 + id_len..];
+
+    let shared = p256::ecdh::diffie_hellman(ua_secret.to_nonzero_scalar(), as_public.as_affine());
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_secret.public_key().to_encoded_point(false).as_bytes());
+    key_info.extend_from_slice(as_public.to_encoded_point(false).as_bytes());
+
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .unwrap();
+
+    let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek).unwrap();
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: nonce\0", &mut nonce).unwrap();
+
+    let mut plaintext = Aes128Gcm::new_from_slice(&cek)
+        .unwrap()
+        .decrypt(Nonce::from_slice(&nonce), ciphertext)
+        .unwrap();
+    assert_eq!(plaintext.pop(), Some(0x02), "missing final record delimiter");
+    plaintext
+}
+
+#[test]
+fn encryption_matches_rfc8291_example() {
+    // RFC 8291, Appendix A.
+    let as_secret =
+        SecretKey::from_slice(&b64("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")).unwrap();
+    let salt: [u8; 16] = b64("DGv6ra1nlYgDCS1FRnbzlw").try_into().unwrap();
+
+    let body = push::encrypt_with(
+        b"When I grow up, I want to be a watermelon",
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+        "BTBZMqHH6r4Tts7J_aSIgg",
+        &as_secret,
+        &salt,
+    )
+    .unwrap();
+
+    assert_eq!(
+        URL_SAFE_NO_PAD.encode(body),
+        "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+    );
+}
+
+#[test]
+fn encrypted_payload_round_trips() {
+    let ua_secret = SecretKey::random(&mut p256::elliptic_curve::rand_core::OsRng);
+    let p256dh = URL_SAFE_NO_PAD.encode(ua_secret.public_key().to_encoded_point(false).as_bytes());
+    let auth = [7u8; 16];
+
+    let body = push::encrypt(b"{\"title\":\"hi\"}", &p256dh, &URL_SAFE_NO_PAD.encode(auth)).unwrap();
+
+    assert_eq!(&body[16..20], &4096u32.to_be_bytes());
+    assert_eq!(decrypt(&body, &ua_secret, &auth), b"{\"title\":\"hi\"}");
+}
+
+#[test]
+fn encryption_rejects_bad_keys() {
+    assert!(push::encrypt(b"x", "not-a-key", "BTBZMqHH6r4Tts7J_aSIgg").is_err());
+    assert!(!push::is_valid_subscription(
+        "https://push.example.com/abc",
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+        "short"
+    ));
+}
+
+#[test]
+fn subscriptions_require_an_https_endpoint() {
+    let p256dh =
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+    let auth = "BTBZMqHH6r4Tts7J_aSIgg";
+    assert!(push::is_valid_subscription(
+        "https://push.example.com/abc",
+        p256dh,
+        auth
+    ));
+    for endpoint in [
+        "http://push.example.com/abc",
+        "ftp://push.example.com/abc",
+        "push.example.com/abc",
+    ] {
+        assert!(
+            !push::is_valid_subscription(endpoint, p256dh, auth),
+            "{endpoint}"
+        );
+    }
+}
+
+#[test]
+fn vapid_jwt_is_verifiable_with_public_key() {
+    let keys = VapidKeys::new(&VapidKeys::generate_private_key(), "mailto:test@example.com").unwrap();
+    let jwt = keys.jwt("https://push.example.com", 1_900_000_000);
+
+    let parts: Vec<&str> = jwt.split('.').collect();
+    assert_eq!(parts.len(), 3);
+
+    let claims: serde_json::Value = serde_json::from_slice(&b64(parts[1])).unwrap();
+    assert_eq!(claims["aud"], "https://push.example.com");
+    assert_eq!(claims["sub"], "mailto:test@example.com");
+    assert_eq!(claims["exp"], 1_900_000_000);
+
+    let verifying_key = VerifyingKey::from_sec1_bytes(&b64(&keys.public_key())).unwrap();
+    let signature = Signature::from_slice(&b64(parts[2])).unwrap();
+    verifying_key
+        .verify(format!("{}.{}", parts[0], parts[1]).as_bytes(), &signature)
+        .unwrap();
+}
+
+type Captured = Arc<Mutex<Vec<(HeaderMap, Vec<u8>)>>>;
+
+async fn spawn_push_service(status: StatusCode) -> (String, Captured) {
+    let captured: Captured = Arc::new(Mutex::new(Vec::new()));
+    let sink = captured.clone();
+    let app = axum::Router::new().route(
+        "/push/{id}",
+        axum::routing::post(move |headers: HeaderMap, body: axum::body::Bytes| {
+            let sink = sink.clone();
+            async move {
+                sink.lock().unwrap().push((headers, body.to_vec()));
+                status
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (format!("http://{}/push/abc", addr), captured)
+}
+
+fn subscription(endpoint: String, ua_secret: &SecretKey, auth: &[u8]) -> PushSubscription {
+    PushSubscription {
+        id: uuid::Uuid::new_v4(),
+        event_id: uuid::Uuid::new_v4(),
+        endpoint,
+        p256dh: URL_SAFE_NO_PAD.encode(ua_secret.public_key().to_encoded_point(false).as_bytes()),
+        auth: URL_SAFE_NO_PAD.encode(auth),
+        created_at: chrono::Utc::now().naive_utc(),
+    }
+}
+
+#[tokio::test]
+async fn client_sends_encrypted_vapid_signed_push() {
+    let (endpoint, captured) = spawn_push_service(StatusCode::CREATED).await;
+    let ua_secret = SecretKey::random(&mut p256::elliptic_curve::rand_core::OsRng);
+    let auth = [3u8; 16];
+
+    let keys = VapidKeys::new(
+        &VapidKeys::generate_private_key(),
+        "mailto:test@example.com",
+    )
+    .unwrap();
+    let public_key = keys.public_key();
+    let client = WebPushClient::new(keys, true);
+
+    let message = PushMessage {
+        title: "The baby has arrived!".to_string(),
+        body: "Results are in".to_string(),
+        url: "http://localhost/event?key=ABC".to_string(),
+        tag: "event-ended".to_string(),
+    };
+    let outcome = client
+        .send(&subscription(endpoint, &ua_secret, &auth), &message)
+        .await;
+    assert_eq!(outcome, SendOutcome::Delivered);
+
+    let requests = captured.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    let (headers, body) = &requests[0];
+
+    assert_eq!(headers["content-encoding"], "aes128gcm");
+    assert!(headers.contains_key("ttl"));
+    let authorization = headers["authorization"].to_str().unwrap();
+    assert!(authorization.starts_with("vapid t="));
+    assert!(authorization.ends_with(&format!("k={}", public_key)));
+
+    let decrypted: serde_json::Value = serde_json::from_slice(&decrypt(body, &ua_secret, &auth)).unwrap();
+    assert_eq!(decrypted["title"], "The baby has arrived!");
+}
+
+#[tokio::test]
+async fn expired_subscriptions_are_reported_as_gone() {
+    let (endpoint, _captured) = spawn_push_service(StatusCode::GONE).await;
+    let ua_secret = SecretKey::random(&mut p256::elliptic_curve::rand_core::OsRng);
+
+    let keys = VapidKeys::new(
+        &VapidKeys::generate_private_key(),
+        "mailto:test@example.com",
+    )
+    .unwrap();
+    let client = WebPushClient::new(keys, true);
+    let message = PushMessage {
+        title: "t".to_string(),
+        body: "b".to_string(),
+        url: "u".to_string(),
+        tag: "x".to_string(),
+    };
+
+    let outcome = client
+        .send(&subscription(endpoint, &ua_secret, &[1u8; 16]), &message)
+        .await;
+    assert_eq!(outcome, SendOutcome::Gone);
+}
+
+#[tokio::test]
+async fn endpoints_on_private_addresses_are_refused() {
+    let (endpoint, captured) = spawn_push_service(StatusCode::CREATED).await;
+    let ua_secret = SecretKey::random(&mut p256::elliptic_curve::rand_core::OsRng);
+
+    let keys = VapidKeys::new(
+        &VapidKeys::generate_private_key(),
+        "mailto:test@example.com",
+    )
+    .unwrap();
+    let client = WebPushClient::new(keys, false);
+    let message = PushMessage {
+        title: "t".to_string(),
+        body: "b".to_string(),
+        url: "u".to_string(),
+        tag: "x".to_string(),
+    };
+
+    let outcome = client
+        .send(&subscription(endpoint, &ua_secret, &[1u8; 16]), &message)
+        .await;
+    assert!(matches!(outcome, SendOutcome::Failed(_)), "{:?}", outcome);
+    assert!(captured.lock().unwrap().is_empty());
+}
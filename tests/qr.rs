@@ -0,0 +1,71 @@
+use baby_birth_guessr::qr::{self, QrOptions, QrQuery};
+use baby_birth_guessr::validation::Invalid;
+use qrcode::EcLevel;
+
+fn options(size: Option<u32>, ec: Option<&str>) -> Result<QrOptions, Invalid> {
+    QrOptions::try_from(&QrQuery {
+        size,
+        ec: ec.map(str::to_string),
+    })
+}
+
+#[test]
+fn query_picks_size_and_error_correction() {
+    assert_eq!(options(None, None), Ok(QrOptions::default()));
+    assert_eq!(
+        options(Some(300), Some("h")),
+        Ok(QrOptions {
+            size: 300,
+            ec_level: EcLevel::H,
+        })
+    );
+    assert_eq!(options(Some(1), None).unwrap().size, qr::MIN_SIZE);
+    assert_eq!(options(Some(100_000), None).unwrap().size, qr::MAX_SIZE);
+    assert_eq!(
+        options(None, Some("X")),
+        Err(Invalid::new(
+            "invalid_ec_level",
+            "ec must be one of L, M, Q, H"
+        ))
+    );
+}
+
+#[test]
+fn higher_error_correction_needs_more_modules() {
+    let url = "https://guessr.example.com/share/K7QM-3XRT-9HPA";
+    let modules = |ec_level| {
+        let svg = qr::to_svg(
+            url,
+            QrOptions {
+                size: 256,
+                ec_level,
+            },
+        )
+        .unwrap();
+        let start = svg.find("viewBox=\"0 0 ").unwrap() + 13;
+        svg[start..start + svg[start..].find(' ').unwrap()]
+            .parse::<usize>()
+            .unwrap()
+    };
+
+    assert!(modules(EcLevel::L) < modules(EcLevel::H));
+    // Version 3 (29 modules) plus the four-module quiet zone on each side.
+    assert_eq!(modules(EcLevel::L), 37);
+}
+
+#[test]
+fn png_has_the_requested_size() {
+    let png = qr::to_png(
+        "https://guessr.example.com/share/baby-virtanen",
+        QrOptions {
+            size: 300,
+            ec_level: EcLevel::Q,
+        },
+    )
+    .unwrap();
+
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    assert_eq!((width, height), (300, 300));
+}
@@ -0,0 +1,113 @@
+use baby_birth_guessr::rate_limit::{
+    CLAIM_EVENT, MemoryStore, RateLimitPolicy, RateLimitStore, SUBMIT_GUESS, run_purge_task,
+    take_token,
+};
+use baby_birth_guessr::shutdown::Shutdown;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+static FAST: RateLimitPolicy = RateLimitPolicy {
+    name: "fast",
+    capacity: 2,
+    refill_per_sec: 1000.0,
+};
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn take_token_reports_remaining_and_retry_after() {
+    let (tokens, decision) = take_token(&SUBMIT_GUESS, 5.0, 0.0);
+    assert!(decision.allowed);
+    assert_eq!(tokens, 4.0);
+    assert_eq!(decision.limit, 5);
+    assert_eq!(decision.remaining, 4);
+    assert_eq!(decision.reset_secs, 6);
+    assert_eq!(decision.retry_after_secs, None);
+
+    let (tokens, decision) = take_token(&SUBMIT_GUESS, 0.5, 0.0);
+    assert!(!decision.allowed);
+    assert_eq!(tokens, 0.5);
+    assert_eq!(decision.remaining, 0);
+    assert_eq!(decision.retry_after_secs, Some(3));
+
+    // Refill is capped at capacity.
+    let (tokens, _) = take_token(&SUBMIT_GUESS, 0.0, 3600.0);
+    assert_eq!(tokens, 4.0);
+}
+
+#[tokio::test]
+async fn buckets_are_separate_per_policy_and_ip() {
+    let store = MemoryStore::default();
+    let client = ip("198.51.100.7");
+
+    for _ in 0..CLAIM_EVENT.capacity {
+        assert!(store.check(&CLAIM_EVENT, client).await.unwrap().allowed);
+    }
+    let denied = store.check(&CLAIM_EVENT, client).await.unwrap();
+    assert!(!denied.allowed);
+    assert!(denied.retry_after_secs.is_some());
+
+    // Exhausting one policy leaves other policies and other clients untouched.
+    assert!(store.check(&SUBMIT_GUESS, client).await.unwrap().allowed);
+    assert!(store.check(&CLAIM_EVENT, ip("198.51.100.8")).await.unwrap().allowed);
+    assert_eq!(store.len(), 3);
+}
+
+#[tokio::test]
+async fn idle_buckets_are_purged() {
+    let store = MemoryStore::default();
+    store.check(&FAST, ip("198.51.100.7")).await.unwrap();
+    store.check(&SUBMIT_GUESS, ip("198.51.100.7")).await.unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+    // The fast bucket has refilled and can be forgotten; the guess bucket cannot.
+    assert_eq!(store.purge_idle().await.unwrap(), 1);
+    assert_eq!(store.len(), 1);
+}
+
+#[tokio::test]
+async fn store_never_grows_past_its_bound() {
+    let store = MemoryStore::new(3);
+    for i in 0..10 {
+        let client = IpAddr::from([198, 51, 100, i]);
+        assert!(store.check(&SUBMIT_GUESS, client).await.unwrap().allowed);
+        assert!(store.len() <= 3);
+    }
+}
+
+#[tokio::test]
+async fn full_store_evicts_the_least_recently_used_bucket() {
+    let store = MemoryStore::new(2);
+    let (a, b, c) = (ip("198.51.100.1"), ip("198.51.100.2"), ip("198.51.100.3"));
+
+    store.check(&CLAIM_EVENT, a).await.unwrap();
+    store.check(&CLAIM_EVENT, b).await.unwrap();
+    let before = store.check(&CLAIM_EVENT, a).await.unwrap();
+    store.check(&CLAIM_EVENT, c).await.unwrap();
+    assert_eq!(store.len(), 2);
+
+    // `a` was used after `b`, so `b` made room for `c` and `a` kept its spent tokens.
+    let after = store.check(&CLAIM_EVENT, a).await.unwrap();
+    assert_eq!(after.remaining, before.remaining - 1);
+    let fresh = store.check(&CLAIM_EVENT, b).await.unwrap();
+    assert_eq!(fresh.remaining, CLAIM_EVENT.capacity - 1);
+}
+
+#[tokio::test]
+async fn purge_task_stops_on_shutdown() {
+    let shutdown = Shutdown::default();
+    let task = tokio::spawn(run_purge_task(
+        Arc::new(MemoryStore::default()),
+        shutdown.clone(),
+    ));
+
+    shutdown.trigger();
+    tokio::time::timeout(Duration::from_secs(5), task)
+        .await
+        .expect("purge task did not stop")
+        .unwrap();
+}
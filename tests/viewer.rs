@@ -0,0 +1,160 @@
+use baby_birth_guessr::models::Event;
+use baby_birth_guessr::validation::{Invalid, event_visibility};
+use baby_birth_guessr::viewer::{Access, AccessDenied, Viewer, ViewerTokens, Visibility};
+use chrono::{NaiveDate, NaiveDateTime};
+use uuid::Uuid;
+
+fn at(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2030, 1, day)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+}
+
+fn event(visibility: &str, passcode: Option<&str>) -> Event {
+    Event {
+        id: Uuid::new_v4(),
+        title: "Baby".to_string(),
+        description: None,
+        due_date: Some(at(10)),
+        event_key: "K7QM-3XRT-9HPA".to_string(),
+        created_at: at(1),
+        guess_close_date: Some(at(5)),
+        secret_key: "secret".to_string(),
+        min_weight_kg: 2.0,
+        max_weight_kg: 5.0,
+        allow_guess_edits: false,
+        birth_date: None,
+        birth_weight_kg: None,
+        ended_at: None,
+        close_reminder_sent_at: None,
+        push_reminder_sent_at: None,
+        guess_pow_difficulty: 0,
+        deleted_at: None,
+        restored_at: None,
+        embed_origins: Vec::new(),
+        language: None,
+        slug: None,
+        visibility: visibility.to_string(),
+        passcode: passcode.map(str::to_string),
+        sealed_guesses: false,
+        guesses_revealed_at: None,
+    }
+}
+
+#[test]
+fn tokens_verify_for_their_event_and_passcode_only() {
+    let tokens = ViewerTokens::new(b"key", 60);
+    let private = event("passcode", Some("stork"));
+    let token = tokens.issue(&private).token;
+    assert!(tokens.verify(&private, &token));
+
+    let other = event("passcode", Some("stork"));
+    assert!(!tokens.verify(&other, &token));
+    assert!(!ViewerTokens::new(b"other-key", 60).verify(&private, &token));
+
+    let mut changed = event("passcode", Some("stork"));
+    changed.id = private.id;
+    changed.passcode = Some("heron".to_string());
+    assert!(!tokens.verify(&changed, &token));
+
+    let expired = ViewerTokens::new(b"key", -1).issue(&private).token;
+    assert!(!tokens.verify(&private, &expired));
+
+    assert!(!tokens.verify(&private, "garbage"));
+    assert!(!tokens.verify(&private, ""));
+}
+
+#[test]
+fn access_depends_on_visibility_viewer_and_close() {
+    let before = at(3);
+    let after = at(6);
+
+    let public = event("public", None);
+    assert_eq!(Access::new(&public, Viewer::Guest, before), Access::Full);
+
+    let private = event("passcode", Some("stork"));
+    assert_eq!(Access::new(&private, Viewer::Guest, after), Access::Locked);
+    assert_eq!(
+        Access::new(&private, Viewer::Unlocked, before),
+        Access::Full
+    );
+    assert_eq!(Access::new(&private, Viewer::Host, before), Access::Full);
+    assert_eq!(Access::Locked.event(), Err(AccessDenied::PasscodeRequired));
+
+    let hidden = event("hidden_guesses", None);
+    let access = Access::new(&hidden, Viewer::Guest, before);
+    assert_eq!(access, Access::EventOnly);
+    assert_eq!(access.event(), Ok(()));
+    assert_eq!(access.guesses(), Err(AccessDenied::GuessesHidden));
+    assert_eq!(Access::new(&hidden, Viewer::Host, before), Access::Full);
+    assert_eq!(Access::new(&hidden, Viewer::Guest, after), Access::Full);
+
+    let mut ended = event("hidden_guesses", None);
+    ended.ended_at = Some(at(2));
+    assert_eq!(Access::new(&ended, Viewer::Guest, before), Access::Full);
+}
+
+#[test]
+fn sealed_guesses_open_at_close_and_stay_hidden_behind_stricter_modes() {
+    let before = at(3);
+    let after = at(6);
+
+    let mut sealed = event("public", None);
+    sealed.sealed_guesses = true;
+    let access = Access::new(&sealed, Viewer::Guest, before);
+    assert_eq!(access, Access::Sealed);
+    assert_eq!(access.event(), Ok(()));
+    assert_eq!(access.guesses(), Err(AccessDenied::GuessesSealed));
+    assert_eq!(Access::new(&sealed, Viewer::Host, before), Access::Full);
+    assert_eq!(Access::new(&sealed, Viewer::Guest, after), Access::Full);
+
+    let mut private = event("passcode", Some("stork"));
+    private.sealed_guesses = true;
+    assert_eq!(Access::new(&private, Viewer::Guest, before), Access::Locked);
+    assert_eq!(
+        Access::new(&private, Viewer::Unlocked, before),
+        Access::Sealed
+    );
+
+    let mut hidden = event("hidden_guesses", None);
+    hidden.sealed_guesses = true;
+    assert_eq!(
+        Access::new(&hidden, Viewer::Guest, before),
+        Access::EventOnly
+    );
+}
+
+#[test]
+fn visibility_settings_are_validated() {
+    assert_eq!(event_visibility(None, None), Ok((Visibility::Public, None)));
+    assert_eq!(
+        event_visibility(Some("hidden_guesses"), Some("stork")),
+        Ok((Visibility::HiddenGuesses, None))
+    );
+    assert_eq!(
+        event_visibility(Some("passcode"), Some(" stork ")),
+        Ok((Visibility::Passcode, Some("stork".to_string())))
+    );
+    assert_eq!(
+        event_visibility(Some("secret"), None),
+        Err(Invalid::new(
+            "invalid_visibility",
+            "visibility must be one of public, passcode, hidden_guesses"
+        ))
+    );
+    assert_eq!(
+        event_visibility(Some("passcode"), Some("  ")),
+        Err(Invalid::new(
+            "passcode_missing",
+            "passcode is required for passcode-protected events"
+        ))
+    );
+    assert_eq!(
+        event_visibility(Some("passcode"), Some("abc")),
+        Err(Invalid::new(
+            "passcode_length",
+            "passcode must be 4 to 64 characters long"
+        ))
+    );
+}